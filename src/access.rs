@@ -0,0 +1,182 @@
+//! This module provides typed access masks for the service control manager and for services.
+//! `ServiceManager` and `ServiceHandle` remember the rights they were opened with, so that a
+//! call needing a right the handle lacks fails with an error naming that right.
+
+use crate::consts::{
+    DELETE, READ_CONTROL, SC_MANAGER_ALL_ACCESS, SC_MANAGER_CONNECT, SC_MANAGER_CREATE_SERVICE,
+    SC_MANAGER_ENUMERATE_SERVICE, SC_MANAGER_LOCK, SC_MANAGER_MODIFY_BOOT_CONFIG,
+    SC_MANAGER_QUERY_LOCK_STATUS, SERVICE_ALL_ACCESS, SERVICE_CHANGE_CONFIG,
+    SERVICE_ENUMERATE_DEPENDENTS, SERVICE_INTERROGATE, SERVICE_PAUSE_CONTINUE,
+    SERVICE_QUERY_CONFIG, SERVICE_QUERY_STATUS, SERVICE_START, SERVICE_STOP,
+    SERVICE_USER_DEFINED_CONTROL, WRITE_DAC, WRITE_OWNER,
+};
+
+#[doc(hidden)]
+macro_rules! flags {
+    (
+        $(#[$meta:meta])*
+        $name:ident {
+            $($(#[$flag_meta:meta])* $flag:ident = $value:expr,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        pub struct $name(u32);
+
+        impl $name {
+            $($(#[$flag_meta])* pub const $flag: Self = Self($value);)*
+
+            #[doc(hidden)]
+            const NAMES: &'static [(u32, &'static str)] = &[$(($value, stringify!($value)),)*];
+
+            /// Returns the empty set.
+            pub const fn empty() -> Self {
+                Self(0)
+            }
+
+            /// Wraps a raw mask, keeping bits that have no named constant.
+            pub const fn from_bits_retain(bits: u32) -> Self {
+                Self(bits)
+            }
+
+            /// Returns the raw mask.
+            pub const fn bits(self) -> u32 {
+                self.0
+            }
+
+            /// Returns whether no flag is set.
+            pub const fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns whether every flag in `other` is also set in `self`.
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Returns the flags of `self` that aren't set in `other`.
+            pub const fn difference(self, other: Self) -> Self {
+                Self(self.0 & !other.0)
+            }
+        }
+
+        impl std::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl std::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl std::ops::BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        /// Lists the Win32 names of the flags, such as `SERVICE_START | SERVICE_STOP`.
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                if self.0 == 0 {
+                    return write!(f, "(none)");
+                }
+                let mut remaining = self.0;
+                let mut separator = "";
+                for (bits, name) in Self::NAMES {
+                    if bits.count_ones() == 1 && remaining & bits != 0 {
+                        write!(f, "{separator}{name}")?;
+                        remaining &= !bits;
+                        separator = " | ";
+                    }
+                }
+                if remaining != 0 {
+                    write!(f, "{separator}{remaining:#x}")?;
+                }
+                Ok(())
+            }
+        }
+    };
+}
+pub(crate) use flags;
+
+/// Adds the access check shared by `ScmAccess` and `ServiceAccess`.
+#[doc(hidden)]
+macro_rules! missing {
+    ($type:ty) => {
+        impl $type {
+            /// Returns the context of an access-denied error naming the rights of `required`
+            /// that `self` lacks, or `None` if it has all of them.
+            #[doc(hidden)]
+            pub(crate) fn missing(self, required: Self, function: &str) -> Option<String> {
+                let missing = required.difference(self);
+                (!missing.is_empty()).then(|| format!("[{function}] the handle lacks {missing}"))
+            }
+        }
+    };
+}
+
+flags! {
+    /// Rights on the service control manager, passed to `OpenSCManagerW`.
+    ScmAccess {
+        CONNECT = SC_MANAGER_CONNECT,
+        CREATE_SERVICE = SC_MANAGER_CREATE_SERVICE,
+        ENUMERATE_SERVICE = SC_MANAGER_ENUMERATE_SERVICE,
+        LOCK = SC_MANAGER_LOCK,
+        QUERY_LOCK_STATUS = SC_MANAGER_QUERY_LOCK_STATUS,
+        MODIFY_BOOT_CONFIG = SC_MANAGER_MODIFY_BOOT_CONFIG,
+        READ_CONTROL = READ_CONTROL,
+        WRITE_DAC = WRITE_DAC,
+        WRITE_OWNER = WRITE_OWNER,
+        DELETE = DELETE,
+        ALL_ACCESS = SC_MANAGER_ALL_ACCESS,
+    }
+}
+
+flags! {
+    /// Rights on a service, passed to `OpenServiceW` and `CreateServiceW`.
+    ServiceAccess {
+        QUERY_CONFIG = SERVICE_QUERY_CONFIG,
+        CHANGE_CONFIG = SERVICE_CHANGE_CONFIG,
+        QUERY_STATUS = SERVICE_QUERY_STATUS,
+        ENUMERATE_DEPENDENTS = SERVICE_ENUMERATE_DEPENDENTS,
+        START = SERVICE_START,
+        STOP = SERVICE_STOP,
+        PAUSE_CONTINUE = SERVICE_PAUSE_CONTINUE,
+        INTERROGATE = SERVICE_INTERROGATE,
+        USER_DEFINED_CONTROL = SERVICE_USER_DEFINED_CONTROL,
+        DELETE = DELETE,
+        READ_CONTROL = READ_CONTROL,
+        WRITE_DAC = WRITE_DAC,
+        WRITE_OWNER = WRITE_OWNER,
+        ALL_ACCESS = SERVICE_ALL_ACCESS,
+    }
+}
+
+missing!(ScmAccess);
+missing!(ServiceAccess);
+
+impl ScmAccess {
+    /// Connecting, enumerating services and reading the lock status, which is what
+    /// `GENERIC_READ` maps to and what non-administrators are normally granted.
+    pub const READ: Self =
+        Self(SC_MANAGER_CONNECT | SC_MANAGER_ENUMERATE_SERVICE | SC_MANAGER_QUERY_LOCK_STATUS);
+}
+
+impl ServiceAccess {
+    /// Querying the configuration, status and dependents, which is what `GENERIC_READ` maps to.
+    pub const READ: Self = Self(
+        SERVICE_QUERY_CONFIG
+            | SERVICE_QUERY_STATUS
+            | SERVICE_ENUMERATE_DEPENDENTS
+            | SERVICE_INTERROGATE
+            | READ_CONTROL,
+    );
+}
@@ -0,0 +1,137 @@
+//! This module provides a typed model of the accounts a Windows service can run under.
+//! Win32 takes account names as free-form strings, so `ServiceAccount` parses and normalizes
+//! the textual forms and knows which accounts need a password.
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{consts::ERROR_INVALID_SERVICE_ACCOUNT, error::ServiceAccountError};
+
+#[doc(hidden)]
+const INVALID_NAME_CHARS: &[char] = &[
+    '"', '/', '\\', '[', ']', ':', ';', '|', '=', ',', '+', '*', '?', '<', '>',
+];
+
+/// The account a Win32 service runs under.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServiceAccount {
+    /// `LocalSystem`
+    LocalSystem,
+    /// `NT AUTHORITY\LocalService`
+    LocalService,
+    /// `NT AUTHORITY\NetworkService`
+    NetworkService,
+    /// `NT SERVICE\<service name>`
+    Virtual(String),
+    /// `DOMAIN\name$`, a group managed service account. `name` excludes the trailing `$`.
+    GroupManaged { domain: String, name: String },
+    /// A regular account in `DOMAIN\name`, `.\name` or `name@domain` form.
+    User(String),
+}
+
+impl ServiceAccount {
+    /// Returns the virtual account of the service named `service_name`.
+    pub fn virtual_account(service_name: &str) -> Self {
+        Self::Virtual(service_name.to_string())
+    }
+
+    /// Returns whether the SCM needs a password to log the service on with this account.
+    pub fn requires_password(&self) -> bool {
+        matches!(self, Self::User(_))
+    }
+
+    /// Interprets an account name reported by the SCM.
+    ///
+    /// Unlike [`str::parse`], this never fails: names it doesn't recognize become [`ServiceAccount::User`].
+    pub fn from_raw(value: &str) -> Self {
+        value
+            .parse()
+            .unwrap_or_else(|_| Self::User(value.to_string()))
+    }
+
+    #[doc(hidden)]
+    fn builtin(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "localsystem" | "system" => Some(Self::LocalSystem),
+            "localservice" | "local service" => Some(Self::LocalService),
+            "networkservice" | "network service" => Some(Self::NetworkService),
+            _ => None,
+        }
+    }
+}
+
+#[doc(hidden)]
+fn invalid(value: &str, reason: &str) -> ServiceAccountError {
+    ServiceAccountError::InvalidServiceAccount(
+        ERROR_INVALID_SERVICE_ACCOUNT,
+        format!("[ServiceAccount::from_str] {value:?}: {reason}"),
+    )
+}
+
+#[doc(hidden)]
+fn check_name<'a>(value: &str, name: &'a str) -> Result<&'a str, ServiceAccountError> {
+    if name.is_empty() {
+        return Err(invalid(value, "empty account name"));
+    }
+    if name.contains(INVALID_NAME_CHARS) || name.contains('\0') {
+        return Err(invalid(value, "invalid character in account name"));
+    }
+    Ok(name)
+}
+
+impl FromStr for ServiceAccount {
+    type Err = ServiceAccountError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            return Err(invalid(value, "empty account"));
+        }
+
+        let Some((domain, name)) = trimmed.split_once('\\') else {
+            if let Some((name, domain)) = trimmed.split_once('@') {
+                check_name(value, name)?;
+                check_name(value, domain)?;
+                return Ok(Self::User(trimmed.to_string()));
+            }
+            return Ok(match Self::builtin(trimmed) {
+                Some(account) => account,
+                None => Self::User(format!(".\\{}", check_name(value, trimmed)?)),
+            });
+        };
+
+        let name = check_name(value, name)?;
+        match domain.to_lowercase().as_str() {
+            "" => Err(invalid(value, "empty domain")),
+            "nt authority" => match Self::builtin(name) {
+                Some(account) => Ok(account),
+                None => Err(invalid(value, "unknown NT AUTHORITY account")),
+            },
+            "nt service" => Ok(Self::Virtual(name.to_string())),
+            "." if name.eq_ignore_ascii_case("localsystem") => Ok(Self::LocalSystem),
+            "." => Ok(Self::User(format!(".\\{name}"))),
+            _ => {
+                let domain = check_name(value, domain)?;
+                match name.strip_suffix('$') {
+                    Some(name) => Ok(Self::GroupManaged {
+                        domain: domain.to_string(),
+                        name: check_name(value, name)?.to_string(),
+                    }),
+                    None => Ok(Self::User(format!("{domain}\\{name}"))),
+                }
+            }
+        }
+    }
+}
+
+impl Display for ServiceAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LocalSystem => write!(f, "LocalSystem"),
+            Self::LocalService => write!(f, "NT AUTHORITY\\LocalService"),
+            Self::NetworkService => write!(f, "NT AUTHORITY\\NetworkService"),
+            Self::Virtual(service_name) => write!(f, "NT SERVICE\\{service_name}"),
+            Self::GroupManaged { domain, name } => write!(f, "{domain}\\{name}$"),
+            Self::User(account) => write!(f, "{account}"),
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! This module defines the backend abstraction that sits underneath `ServiceManager` and `ServiceHandle`.
+//! A backend speaks the raw service control manager protocol (handles, access masks, numeric
+//! states and Win32 error codes), so the typed API on top of it behaves the same whether it is
+//! talking to the real SCM through `win32::Win32Backend` on Windows or to the in-memory
+//! [`crate::fake::FakeScm`].
+
+use std::{any::Any, fmt::Debug, time::Duration};
+
+use crate::{service_manager::ServiceConfig, trigger::Guid};
+
+/// Opaque handle issued by a [`ScmBackend`] for a service control manager or a service.
+pub type ScHandle = isize;
+
+/// Passed in a [`RawConfigChange`] field to leave the corresponding setting untouched.
+pub const SERVICE_NO_CHANGE: u32 = 0xffffffff;
+
+/// Prefix marking a load order group (rather than a service) in a dependency list.
+pub const SC_GROUP_IDENTIFIER: char = '+';
+
+/// Raw contents of a `SERVICE_STATUS_PROCESS` structure.
+///
+/// `process_id` and `service_flags` are zero when the backend only has a `SERVICE_STATUS`.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct RawServiceStatus {
+    pub service_type: u32,
+    pub current_state: u32,
+    pub controls_accepted: u32,
+    pub win32_exit_code: u32,
+    pub service_specific_exit_code: u32,
+    pub check_point: u32,
+    pub wait_hint: u32,
+    pub process_id: u32,
+    pub service_flags: u32,
+}
+
+/// A service as listed by `EnumServicesStatusExW` and `EnumDependentServicesW`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RawServiceEntry {
+    pub service_name: String,
+    pub display_name: String,
+    pub status: RawServiceStatus,
+}
+
+/// Owned copy of a `SERVICE_NOTIFYW` structure filled in by `NotifyServiceStatusChangeW`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RawServiceNotify {
+    /// The `SERVICE_NOTIFY_*` event that fired.
+    pub notification_triggered: u32,
+    /// The status of the service, for notifications on a service handle.
+    pub status: RawServiceStatus,
+    /// The services created or deleted, for notifications on a service control manager handle.
+    /// Created services are prefixed with `/` and deleted ones with `\`.
+    pub service_names: Vec<String>,
+}
+
+/// Owned copy of a `QUERY_SERVICE_CONFIGW` structure.
+///
+/// Group dependencies are prefixed with [`SC_GROUP_IDENTIFIER`].
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RawServiceConfig {
+    pub service_type: u32,
+    pub start_type: u32,
+    pub error_control: u32,
+    pub binary_path_name: String,
+    pub load_order_group: String,
+    pub tag_id: u32,
+    pub dependencies: Vec<String>,
+    pub service_start_name: String,
+    pub display_name: String,
+}
+
+/// Arguments of a `ChangeServiceConfigW` call.
+///
+/// Numeric fields set to [`SERVICE_NO_CHANGE`] and string fields set to `None` are left untouched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawConfigChange {
+    pub service_type: u32,
+    pub start_type: u32,
+    pub error_control: u32,
+    pub binary_path_name: Option<String>,
+    pub load_order_group: Option<String>,
+    pub dependencies: Option<Vec<String>>,
+    pub service_start_name: Option<String>,
+    pub password: Option<String>,
+    pub display_name: Option<String>,
+}
+
+impl Default for RawConfigChange {
+    fn default() -> Self {
+        Self {
+            service_type: SERVICE_NO_CHANGE,
+            start_type: SERVICE_NO_CHANGE,
+            error_control: SERVICE_NO_CHANGE,
+            binary_path_name: None,
+            load_order_group: None,
+            dependencies: None,
+            service_start_name: None,
+            password: None,
+            display_name: None,
+        }
+    }
+}
+
+/// A pending status change registration made by
+/// [`ScmBackend::notify_service_status_change`], such as the buffer Windows writes the
+/// notification to. It is owned by the caller so that it stays alive as long as the handle it
+/// was made on, and is released through [`ScmBackend::close_notify_handle`].
+#[derive(Debug, Default)]
+pub struct NotifyRegistration(pub Option<Box<dyn Any>>);
+
+/// Owned copy of a `SERVICE_FAILURE_ACTIONSW` structure.
+///
+/// Actions are `(SC_ACTION_*, delay in milliseconds)` pairs. When changing the configuration,
+/// every field is applied and empty strings delete the reboot message and command.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RawFailureActions {
+    /// Seconds without failure after which the failure count is reset, or `INFINITE`.
+    pub reset_period: u32,
+    pub reboot_message: String,
+    pub command: String,
+    pub actions: Vec<(u32, u32)>,
+}
+
+/// Owned copy of a `SERVICE_TRIGGER` structure.
+///
+/// Data items are `(SERVICE_TRIGGER_DATA_TYPE_*, bytes)` pairs, with strings encoded as
+/// NUL-terminated UTF-16LE.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RawServiceTrigger {
+    pub trigger_type: u32,
+    pub action: u32,
+    pub subtype: Option<Guid>,
+    pub data_items: Vec<(u32, Vec<u8>)>,
+}
+
+/// The operations a service control manager implementation has to provide.
+///
+/// Every method mirrors the Win32 function of the same name and reports failures with the
+/// Win32 error code that function would leave in `GetLastError`.
+pub trait ScmBackend: Debug + Send + Sync {
+    /// Opens a service control manager database, on the local machine if `machine_name` is
+    /// `None` and the active database if `database_name` is `None`.
+    fn open_sc_manager(
+        &self,
+        machine_name: Option<&str>,
+        database_name: Option<&str>,
+        desired_access: u32,
+    ) -> Result<ScHandle, u32>;
+
+    /// Opens an existing service.
+    fn open_service(
+        &self,
+        scm: ScHandle,
+        service_name: &str,
+        desired_access: u32,
+    ) -> Result<ScHandle, u32>;
+
+    /// Returns the key name of the service with the given display name.
+    fn get_service_key_name(&self, scm: ScHandle, display_name: &str) -> Result<String, u32>;
+
+    /// Returns the display name of the service with the given key name.
+    fn get_service_display_name(&self, scm: ScHandle, service_name: &str) -> Result<String, u32>;
+
+    /// Creates a service and returns a handle to it, along with the tag assigned to it when
+    /// [`ServiceConfig::request_tag`] is set.
+    fn create_service(
+        &self,
+        scm: ScHandle,
+        config: &ServiceConfig,
+        desired_access: u32,
+    ) -> Result<(ScHandle, u32), u32>;
+
+    /// Lists the services matching a `SERVICE_DRIVER`/`SERVICE_WIN32` type mask and a
+    /// `SERVICE_ACTIVE`/`SERVICE_INACTIVE`/`SERVICE_STATE_ALL` filter, optionally restricted
+    /// to a load order group (an empty group selects services that belong to none).
+    fn enum_services_status_ex(
+        &self,
+        scm: ScHandle,
+        service_type: u32,
+        service_state: u32,
+        group_name: Option<&str>,
+    ) -> Result<Vec<RawServiceEntry>, u32>;
+
+    /// Lists the services that depend on the service, directly or indirectly, in reverse start
+    /// order and filtered by `SERVICE_ACTIVE`/`SERVICE_INACTIVE`/`SERVICE_STATE_ALL`.
+    fn enum_dependent_services(
+        &self,
+        service: ScHandle,
+        service_state: u32,
+    ) -> Result<Vec<RawServiceEntry>, u32>;
+
+    /// Enables or disables a privilege in the token of the current process and returns whether
+    /// it was enabled before. Fails with `ERROR_NOT_ALL_ASSIGNED` if the token doesn't hold it.
+    fn adjust_privilege(&self, privilege: &str, enable: bool) -> Result<bool, u32>;
+
+    /// Waits up to `timeout` for one of the `SERVICE_NOTIFY_*` events in `notify_mask` on a
+    /// service or service control manager handle, and returns `None` if none happened in time.
+    ///
+    /// A service handle is notified right away if the service is already in a state of
+    /// `notify_mask`. A registration that times out stays pending in `registration`, and later
+    /// calls for the same handle keep waiting on it; they must come from the thread that made
+    /// it, since that is the thread Windows delivers the notification to.
+    fn notify_service_status_change(
+        &self,
+        handle: ScHandle,
+        registration: &mut NotifyRegistration,
+        notify_mask: u32,
+        timeout: Duration,
+    ) -> Result<Option<RawServiceNotify>, u32>;
+
+    /// Closes a handle returned by one of the open or create functions.
+    fn close_service_handle(&self, handle: ScHandle);
+
+    /// Closes a handle [`notify_service_status_change`](Self::notify_service_status_change)
+    /// was called on, and releases `registration` once nothing can write to it anymore. Must
+    /// be called on the thread that made the registration.
+    fn close_notify_handle(&self, handle: ScHandle, registration: NotifyRegistration);
+
+    /// Marks the service for deletion.
+    fn delete_service(&self, service: ScHandle) -> Result<(), u32>;
+
+    /// Starts the service, passing `arguments` to its `ServiceMain` after the service name.
+    fn start_service(&self, service: ScHandle, arguments: &[String]) -> Result<(), u32>;
+
+    /// Terminates a process of the local machine, as a last resort for a service that doesn't stop.
+    fn terminate_process(&self, process_id: u32, exit_code: u32) -> Result<(), u32>;
+
+    /// Sends a control code to the service and returns its latest status.
+    fn control_service(&self, service: ScHandle, control: u32) -> Result<RawServiceStatus, u32>;
+
+    /// Sends a control code along with a `SERVICE_STOP_REASON_*` code and an optional comment,
+    /// as `ControlServiceExW` does, and returns the latest status of the service.
+    fn control_service_with_reason(
+        &self,
+        service: ScHandle,
+        control: u32,
+        reason: u32,
+        comment: Option<&str>,
+    ) -> Result<RawServiceStatus, u32>;
+
+    /// Returns the current status of the service, including the process it runs in.
+    fn query_service_status(&self, service: ScHandle) -> Result<RawServiceStatus, u32>;
+
+    /// Returns the configuration of the service.
+    fn query_service_config(&self, service: ScHandle) -> Result<RawServiceConfig, u32>;
+
+    /// Changes the configuration of the service.
+    fn change_service_config(&self, service: ScHandle, change: &RawConfigChange)
+        -> Result<(), u32>;
+
+    /// Returns the description of the service, or an empty string if it has none.
+    fn query_service_description(&self, service: ScHandle) -> Result<String, u32>;
+
+    /// Sets the description of the service, deleting it if `description` is empty.
+    fn change_service_description(&self, service: ScHandle, description: &str) -> Result<(), u32>;
+
+    /// Returns what the service control manager does when the service fails.
+    fn query_service_failure_actions(&self, service: ScHandle) -> Result<RawFailureActions, u32>;
+
+    /// Replaces what the service control manager does when the service fails.
+    fn change_service_failure_actions(
+        &self,
+        service: ScHandle,
+        failure_actions: &RawFailureActions,
+    ) -> Result<(), u32>;
+
+    /// Returns whether the failure actions also run when the service stops with an error
+    /// instead of crashing, the `SERVICE_FAILURE_ACTIONS_FLAG` setting.
+    fn query_service_failure_actions_flag(&self, service: ScHandle) -> Result<bool, u32>;
+
+    /// Sets whether the failure actions also run when the service stops with an error.
+    fn change_service_failure_actions_flag(
+        &self,
+        service: ScHandle,
+        enabled: bool,
+    ) -> Result<(), u32>;
+
+    /// Returns whether an auto-start service is started some time after the other ones.
+    fn query_service_delayed_auto_start(&self, service: ScHandle) -> Result<bool, u32>;
+
+    /// Sets whether an auto-start service is started some time after the other ones.
+    fn change_service_delayed_auto_start(
+        &self,
+        service: ScHandle,
+        delayed: bool,
+    ) -> Result<(), u32>;
+
+    /// Returns the milliseconds the service gets to handle `SERVICE_CONTROL_PRESHUTDOWN`.
+    fn query_service_preshutdown_timeout(&self, service: ScHandle) -> Result<u32, u32>;
+
+    /// Sets the milliseconds the service gets to handle `SERVICE_CONTROL_PRESHUTDOWN`.
+    fn change_service_preshutdown_timeout(
+        &self,
+        service: ScHandle,
+        timeout: u32,
+    ) -> Result<(), u32>;
+
+    /// Returns the triggers that start or stop the service.
+    fn query_service_triggers(&self, service: ScHandle) -> Result<Vec<RawServiceTrigger>, u32>;
+
+    /// Replaces the triggers of the service, deleting them all if `triggers` is empty.
+    fn change_service_triggers(
+        &self,
+        service: ScHandle,
+        triggers: &[RawServiceTrigger],
+    ) -> Result<(), u32>;
+
+    /// Returns the `SERVICE_SID_TYPE_*` value of the service.
+    fn query_service_sid_type(&self, service: ScHandle) -> Result<u32, u32>;
+
+    /// Sets the `SERVICE_SID_TYPE_*` value of the service.
+    fn change_service_sid_type(&self, service: ScHandle, sid_type: u32) -> Result<(), u32>;
+
+    /// Returns the names of the privileges the service keeps in its token, empty if it keeps
+    /// every privilege of its account.
+    fn query_service_required_privileges(&self, service: ScHandle) -> Result<Vec<String>, u32>;
+
+    /// Sets the names of the privileges the service keeps in its token.
+    fn change_service_required_privileges(
+        &self,
+        service: ScHandle,
+        privileges: &[String],
+    ) -> Result<(), u32>;
+}
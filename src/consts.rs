@@ -0,0 +1,217 @@
+//! This module defines the Win32 constants used by the service control manager API.
+//! They are declared here rather than imported from `windows-sys` so that the typed API,
+//! the error mappings and the [`FakeScm`](crate::fake::FakeScm) are available on every platform.
+
+// Win32 error codes.
+pub const ERROR_FILE_NOT_FOUND: u32 = 2;
+pub const ERROR_PATH_NOT_FOUND: u32 = 3;
+pub const ERROR_ACCESS_DENIED: u32 = 5;
+pub const ERROR_INVALID_HANDLE: u32 = 6;
+pub const ERROR_INVALID_PARAMETER: u32 = 87;
+pub const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
+pub const ERROR_MORE_DATA: u32 = 234;
+pub const ERROR_INVALID_NAME: u32 = 123;
+pub const ERROR_DEPENDENT_SERVICES_RUNNING: u32 = 1051;
+pub const ERROR_INVALID_SERVICE_CONTROL: u32 = 1052;
+pub const ERROR_SERVICE_REQUEST_TIMEOUT: u32 = 1053;
+pub const ERROR_SERVICE_NO_THREAD: u32 = 1054;
+pub const ERROR_SERVICE_DATABASE_LOCKED: u32 = 1055;
+pub const ERROR_SERVICE_ALREADY_RUNNING: u32 = 1056;
+pub const ERROR_INVALID_SERVICE_ACCOUNT: u32 = 1057;
+pub const ERROR_SERVICE_DISABLED: u32 = 1058;
+pub const ERROR_CIRCULAR_DEPENDENCY: u32 = 1059;
+pub const ERROR_SERVICE_DOES_NOT_EXIST: u32 = 1060;
+pub const ERROR_SERVICE_CANNOT_ACCEPT_CTRL: u32 = 1061;
+pub const ERROR_SERVICE_NOT_ACTIVE: u32 = 1062;
+pub const ERROR_DATABASE_DOES_NOT_EXIST: u32 = 1065;
+pub const ERROR_SERVICE_SPECIFIC_ERROR: u32 = 1066;
+pub const ERROR_PROCESS_ABORTED: u32 = 1067;
+pub const ERROR_SERVICE_DEPENDENCY_FAIL: u32 = 1068;
+pub const ERROR_SERVICE_LOGON_FAILED: u32 = 1069;
+pub const ERROR_SERVICE_MARKED_FOR_DELETE: u32 = 1072;
+pub const ERROR_SERVICE_EXISTS: u32 = 1073;
+pub const ERROR_SERVICE_DEPENDENCY_DELETED: u32 = 1075;
+pub const ERROR_DUPLICATE_SERVICE_NAME: u32 = 1078;
+pub const ERROR_SERVICE_NOTIFY_CLIENT_LAGGING: u32 = 1294;
+pub const ERROR_NOT_ALL_ASSIGNED: u32 = 1300;
+pub const ERROR_NO_SUCH_PRIVILEGE: u32 = 1313;
+pub const ERROR_PRIVILEGE_NOT_HELD: u32 = 1314;
+pub const ERROR_TIMEOUT: u32 = 1460;
+pub const RPC_S_SERVER_UNAVAILABLE: u32 = 1722;
+
+// Service control manager databases.
+pub const SERVICES_ACTIVE_DATABASE: &str = "ServicesActive";
+
+// Service types.
+pub const SERVICE_KERNEL_DRIVER: u32 = 0x00000001;
+pub const SERVICE_FILE_SYSTEM_DRIVER: u32 = 0x00000002;
+pub const SERVICE_ADAPTER: u32 = 0x00000004;
+pub const SERVICE_RECOGNIZER_DRIVER: u32 = 0x00000008;
+pub const SERVICE_WIN32_OWN_PROCESS: u32 = 0x00000010;
+pub const SERVICE_WIN32_SHARE_PROCESS: u32 = 0x00000020;
+pub const SERVICE_USER_SERVICE: u32 = 0x00000040;
+pub const SERVICE_USERSERVICE_INSTANCE: u32 = 0x00000080;
+pub const SERVICE_INTERACTIVE_PROCESS: u32 = 0x00000100;
+pub const SERVICE_PKG_SERVICE: u32 = 0x00000200;
+pub const SERVICE_DRIVER: u32 = 0x0000000B;
+pub const SERVICE_WIN32: u32 = 0x00000030;
+
+// Enumeration state filters.
+pub const SERVICE_ACTIVE: u32 = 0x00000001;
+pub const SERVICE_INACTIVE: u32 = 0x00000002;
+pub const SERVICE_STATE_ALL: u32 = 0x00000003;
+
+// Start types.
+pub const SERVICE_BOOT_START: u32 = 0x00000000;
+pub const SERVICE_SYSTEM_START: u32 = 0x00000001;
+pub const SERVICE_AUTO_START: u32 = 0x00000002;
+pub const SERVICE_DEMAND_START: u32 = 0x00000003;
+pub const SERVICE_DISABLED: u32 = 0x00000004;
+
+// Error control levels.
+pub const SERVICE_ERROR_IGNORE: u32 = 0x00000000;
+pub const SERVICE_ERROR_NORMAL: u32 = 0x00000001;
+pub const SERVICE_ERROR_SEVERE: u32 = 0x00000002;
+pub const SERVICE_ERROR_CRITICAL: u32 = 0x00000003;
+
+// Current states.
+pub const SERVICE_STOPPED: u32 = 0x00000001;
+pub const SERVICE_START_PENDING: u32 = 0x00000002;
+pub const SERVICE_STOP_PENDING: u32 = 0x00000003;
+pub const SERVICE_RUNNING: u32 = 0x00000004;
+pub const SERVICE_CONTINUE_PENDING: u32 = 0x00000005;
+pub const SERVICE_PAUSE_PENDING: u32 = 0x00000006;
+pub const SERVICE_PAUSED: u32 = 0x00000007;
+
+// Control codes.
+pub const SERVICE_CONTROL_STOP: u32 = 0x00000001;
+pub const SERVICE_CONTROL_PAUSE: u32 = 0x00000002;
+pub const SERVICE_CONTROL_CONTINUE: u32 = 0x00000003;
+pub const SERVICE_CONTROL_INTERROGATE: u32 = 0x00000004;
+pub const SERVICE_CONTROL_PARAMCHANGE: u32 = 0x00000006;
+pub const SERVICE_CONTROL_NETBINDADD: u32 = 0x00000007;
+pub const SERVICE_CONTROL_NETBINDREMOVE: u32 = 0x00000008;
+pub const SERVICE_CONTROL_NETBINDENABLE: u32 = 0x00000009;
+pub const SERVICE_CONTROL_NETBINDDISABLE: u32 = 0x0000000A;
+
+// Stop reasons, passed to ControlServiceExW with SERVICE_CONTROL_STATUS_REASON_INFO.
+pub const SERVICE_CONTROL_STATUS_REASON_INFO: u32 = 1;
+pub const MAX_SERVICE_STOP_COMMENT_LEN: usize = 128;
+pub const SERVICE_STOP_REASON_FLAG_UNPLANNED: u32 = 0x10000000;
+pub const SERVICE_STOP_REASON_FLAG_CUSTOM: u32 = 0x20000000;
+pub const SERVICE_STOP_REASON_FLAG_PLANNED: u32 = 0x40000000;
+pub const SERVICE_STOP_REASON_MAJOR_OTHER: u32 = 0x00010000;
+pub const SERVICE_STOP_REASON_MAJOR_HARDWARE: u32 = 0x00020000;
+pub const SERVICE_STOP_REASON_MAJOR_OPERATINGSYSTEM: u32 = 0x00030000;
+pub const SERVICE_STOP_REASON_MAJOR_SOFTWARE: u32 = 0x00040000;
+pub const SERVICE_STOP_REASON_MAJOR_APPLICATION: u32 = 0x00050000;
+pub const SERVICE_STOP_REASON_MAJOR_NONE: u32 = 0x00060000;
+pub const SERVICE_STOP_REASON_MINOR_OTHER: u32 = 0x00000001;
+pub const SERVICE_STOP_REASON_MINOR_MAINTENANCE: u32 = 0x00000002;
+pub const SERVICE_STOP_REASON_MINOR_INSTALLATION: u32 = 0x00000003;
+pub const SERVICE_STOP_REASON_MINOR_UPGRADE: u32 = 0x00000004;
+pub const SERVICE_STOP_REASON_MINOR_RECONFIG: u32 = 0x00000005;
+pub const SERVICE_STOP_REASON_MINOR_HUNG: u32 = 0x00000006;
+pub const SERVICE_STOP_REASON_MINOR_UNSTABLE: u32 = 0x00000007;
+pub const SERVICE_STOP_REASON_MINOR_DISK: u32 = 0x00000008;
+pub const SERVICE_STOP_REASON_MINOR_NETWORKCARD: u32 = 0x00000009;
+pub const SERVICE_STOP_REASON_MINOR_ENVIRONMENT: u32 = 0x0000000A;
+pub const SERVICE_STOP_REASON_MINOR_HARDWARE_DRIVER: u32 = 0x0000000B;
+pub const SERVICE_STOP_REASON_MINOR_OTHERDRIVER: u32 = 0x0000000C;
+pub const SERVICE_STOP_REASON_MINOR_SERVICEPACK: u32 = 0x0000000D;
+pub const SERVICE_STOP_REASON_MINOR_SOFTWARE_UPDATE: u32 = 0x0000000E;
+pub const SERVICE_STOP_REASON_MINOR_SECURITYFIX: u32 = 0x0000000F;
+pub const SERVICE_STOP_REASON_MINOR_SECURITY: u32 = 0x00000010;
+pub const SERVICE_STOP_REASON_MINOR_NETWORK_CONNECTIVITY: u32 = 0x00000011;
+pub const SERVICE_STOP_REASON_MINOR_WMI: u32 = 0x00000012;
+pub const SERVICE_STOP_REASON_MINOR_SERVICEPACK_UNINSTALL: u32 = 0x00000013;
+pub const SERVICE_STOP_REASON_MINOR_SOFTWARE_UPDATE_UNINSTALL: u32 = 0x00000014;
+pub const SERVICE_STOP_REASON_MINOR_SECURITYFIX_UNINSTALL: u32 = 0x00000015;
+pub const SERVICE_STOP_REASON_MINOR_MMC: u32 = 0x00000016;
+pub const SERVICE_STOP_REASON_MINOR_NONE: u32 = 0x00000017;
+
+// Failure actions.
+pub const INFINITE: u32 = 0xFFFFFFFF;
+pub const SC_ACTION_NONE: u32 = 0;
+pub const SC_ACTION_RESTART: u32 = 1;
+pub const SC_ACTION_REBOOT: u32 = 2;
+pub const SC_ACTION_RUN_COMMAND: u32 = 3;
+
+// Service SID types.
+pub const SERVICE_SID_TYPE_NONE: u32 = 0;
+pub const SERVICE_SID_TYPE_UNRESTRICTED: u32 = 1;
+pub const SERVICE_SID_TYPE_RESTRICTED: u32 = 3;
+
+// Trigger types, actions and data item types.
+pub const SERVICE_TRIGGER_TYPE_DEVICE_INTERFACE_ARRIVAL: u32 = 1;
+pub const SERVICE_TRIGGER_TYPE_IP_ADDRESS_AVAILABILITY: u32 = 2;
+pub const SERVICE_TRIGGER_TYPE_DOMAIN_JOIN: u32 = 3;
+pub const SERVICE_TRIGGER_TYPE_FIREWALL_PORT_EVENT: u32 = 4;
+pub const SERVICE_TRIGGER_TYPE_GROUP_POLICY: u32 = 5;
+pub const SERVICE_TRIGGER_TYPE_NETWORK_ENDPOINT: u32 = 6;
+pub const SERVICE_TRIGGER_TYPE_CUSTOM_SYSTEM_STATE_CHANGE: u32 = 7;
+pub const SERVICE_TRIGGER_TYPE_CUSTOM: u32 = 20;
+pub const SERVICE_TRIGGER_TYPE_AGGREGATE: u32 = 30;
+pub const SERVICE_TRIGGER_ACTION_SERVICE_START: u32 = 1;
+pub const SERVICE_TRIGGER_ACTION_SERVICE_STOP: u32 = 2;
+pub const SERVICE_TRIGGER_DATA_TYPE_BINARY: u32 = 1;
+pub const SERVICE_TRIGGER_DATA_TYPE_STRING: u32 = 2;
+pub const SERVICE_TRIGGER_DATA_TYPE_LEVEL: u32 = 3;
+pub const SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ANY: u32 = 4;
+pub const SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ALL: u32 = 5;
+
+// Status change notifications.
+pub const SERVICE_NOTIFY_STOPPED: u32 = 0x00000001;
+pub const SERVICE_NOTIFY_START_PENDING: u32 = 0x00000002;
+pub const SERVICE_NOTIFY_STOP_PENDING: u32 = 0x00000004;
+pub const SERVICE_NOTIFY_RUNNING: u32 = 0x00000008;
+pub const SERVICE_NOTIFY_CONTINUE_PENDING: u32 = 0x00000010;
+pub const SERVICE_NOTIFY_PAUSE_PENDING: u32 = 0x00000020;
+pub const SERVICE_NOTIFY_PAUSED: u32 = 0x00000040;
+pub const SERVICE_NOTIFY_CREATED: u32 = 0x00000080;
+pub const SERVICE_NOTIFY_DELETED: u32 = 0x00000100;
+pub const SERVICE_NOTIFY_DELETE_PENDING: u32 = 0x00000200;
+
+// Service flags.
+pub const SERVICE_RUNS_IN_SYSTEM_PROCESS: u32 = 0x00000001;
+
+// Controls accepted.
+pub const SERVICE_ACCEPT_STOP: u32 = 0x00000001;
+pub const SERVICE_ACCEPT_PAUSE_CONTINUE: u32 = 0x00000002;
+pub const SERVICE_ACCEPT_SHUTDOWN: u32 = 0x00000004;
+pub const SERVICE_ACCEPT_PARAMCHANGE: u32 = 0x00000008;
+pub const SERVICE_ACCEPT_NETBINDCHANGE: u32 = 0x00000010;
+pub const SERVICE_ACCEPT_HARDWAREPROFILECHANGE: u32 = 0x00000020;
+pub const SERVICE_ACCEPT_POWEREVENT: u32 = 0x00000040;
+pub const SERVICE_ACCEPT_SESSIONCHANGE: u32 = 0x00000080;
+pub const SERVICE_ACCEPT_PRESHUTDOWN: u32 = 0x00000100;
+pub const SERVICE_ACCEPT_TIMECHANGE: u32 = 0x00000200;
+pub const SERVICE_ACCEPT_TRIGGEREVENT: u32 = 0x00000400;
+pub const SERVICE_ACCEPT_USERMODEREBOOT: u32 = 0x00000800;
+
+// Standard access rights.
+pub const DELETE: u32 = 0x00010000;
+pub const READ_CONTROL: u32 = 0x00020000;
+pub const WRITE_DAC: u32 = 0x00040000;
+pub const WRITE_OWNER: u32 = 0x00080000;
+
+// Service control manager access rights.
+pub const SC_MANAGER_CONNECT: u32 = 0x0001;
+pub const SC_MANAGER_CREATE_SERVICE: u32 = 0x0002;
+pub const SC_MANAGER_ENUMERATE_SERVICE: u32 = 0x0004;
+pub const SC_MANAGER_LOCK: u32 = 0x0008;
+pub const SC_MANAGER_QUERY_LOCK_STATUS: u32 = 0x0010;
+pub const SC_MANAGER_MODIFY_BOOT_CONFIG: u32 = 0x0020;
+pub const SC_MANAGER_ALL_ACCESS: u32 = 0x000F003F;
+
+// Service access rights.
+pub const SERVICE_QUERY_CONFIG: u32 = 0x0001;
+pub const SERVICE_CHANGE_CONFIG: u32 = 0x0002;
+pub const SERVICE_QUERY_STATUS: u32 = 0x0004;
+pub const SERVICE_ENUMERATE_DEPENDENTS: u32 = 0x0008;
+pub const SERVICE_START: u32 = 0x0010;
+pub const SERVICE_STOP: u32 = 0x0020;
+pub const SERVICE_PAUSE_CONTINUE: u32 = 0x0040;
+pub const SERVICE_INTERROGATE: u32 = 0x0080;
+pub const SERVICE_USER_DEFINED_CONTROL: u32 = 0x0100;
+pub const SERVICE_ALL_ACCESS: u32 = 0x000F01FF;
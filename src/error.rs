@@ -1,18 +1,24 @@
-use thiserror::Error;
-use windows_sys::Win32::Foundation::{
+use crate::consts::{
     ERROR_ACCESS_DENIED, ERROR_CIRCULAR_DEPENDENCY, ERROR_DATABASE_DOES_NOT_EXIST,
-    ERROR_DUPLICATE_SERVICE_NAME, ERROR_FILE_NOT_FOUND, ERROR_INVALID_HANDLE, ERROR_INVALID_NAME,
-    ERROR_INVALID_PARAMETER, ERROR_INVALID_SERVICE_ACCOUNT, ERROR_PATH_NOT_FOUND,
-    ERROR_SERVICE_ALREADY_RUNNING, ERROR_SERVICE_DATABASE_LOCKED, ERROR_SERVICE_DEPENDENCY_DELETED,
-    ERROR_SERVICE_DEPENDENCY_FAIL, ERROR_SERVICE_DISABLED, ERROR_SERVICE_DOES_NOT_EXIST,
-    ERROR_SERVICE_EXISTS, ERROR_SERVICE_LOGON_FAILED, ERROR_SERVICE_MARKED_FOR_DELETE,
-    ERROR_SERVICE_NOT_ACTIVE, ERROR_SERVICE_NO_THREAD, ERROR_SERVICE_REQUEST_TIMEOUT,
+    ERROR_DEPENDENT_SERVICES_RUNNING, ERROR_DUPLICATE_SERVICE_NAME, ERROR_FILE_NOT_FOUND,
+    ERROR_INVALID_HANDLE, ERROR_INVALID_NAME, ERROR_INVALID_PARAMETER,
+    ERROR_INVALID_SERVICE_ACCOUNT, ERROR_INVALID_SERVICE_CONTROL, ERROR_NOT_ALL_ASSIGNED,
+    ERROR_NO_SUCH_PRIVILEGE, ERROR_PATH_NOT_FOUND, ERROR_PRIVILEGE_NOT_HELD,
+    ERROR_SERVICE_ALREADY_RUNNING, ERROR_SERVICE_CANNOT_ACCEPT_CTRL, ERROR_SERVICE_DATABASE_LOCKED,
+    ERROR_SERVICE_DEPENDENCY_DELETED, ERROR_SERVICE_DEPENDENCY_FAIL, ERROR_SERVICE_DISABLED,
+    ERROR_SERVICE_DOES_NOT_EXIST, ERROR_SERVICE_EXISTS, ERROR_SERVICE_LOGON_FAILED,
+    ERROR_SERVICE_MARKED_FOR_DELETE, ERROR_SERVICE_NOT_ACTIVE, ERROR_SERVICE_NO_THREAD,
+    ERROR_SERVICE_REQUEST_TIMEOUT, ERROR_TIMEOUT, RPC_S_SERVER_UNAVAILABLE,
 };
+use crate::service::RestartReport;
+use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum OpenServiceError {
     #[error("Access denied: {0}, {1}")]
     AccessDenied(u32, String),
+    #[error("Ambiguous service name: {0}, {1}")]
+    AmbiguousName(u32, String),
     #[error("Invalid handle: {0}, {1}")]
     InvalidHandle(u32, String),
     #[error("Invalid name: {0}, {1}")]
@@ -28,6 +34,7 @@ impl From<(u32, String)> for OpenServiceError {
         let (err, display) = value;
         match err {
             ERROR_ACCESS_DENIED => Self::AccessDenied(err, display),
+            ERROR_DUPLICATE_SERVICE_NAME => Self::AmbiguousName(err, display),
             ERROR_INVALID_HANDLE => Self::InvalidHandle(err, display),
             ERROR_INVALID_NAME => Self::InvalidName(err, display),
             ERROR_SERVICE_DOES_NOT_EXIST => Self::ServiceDoesNotExist(err, display),
@@ -50,6 +57,8 @@ pub enum CreateServiceError {
     InvalidParameter(u32, String),
     #[error("Invalid service account: {0}, {1}")]
     InvalidServiceAccount(u32, String),
+    #[error("Invalid service configuration: {0}, {1}")]
+    InvalidConfig(u32, String),
     #[error("Service already exists: {0}, {1}")]
     ServiceExists(u32, String),
     #[error("Service marked for deletion: {0}, {1}")]
@@ -84,6 +93,8 @@ pub enum ServiceManagerError {
     DatabaseDoesNotExist(u32, String),
     #[error("Invalid Service Manager handle: {0}, {1}")]
     InvalidHandle(u32, String),
+    #[error("Machine unavailable: {0}, {1}")]
+    MachineUnavailable(u32, String),
     #[error("Unknown error: {0}, {1}")]
     Unknown(u32, String),
 }
@@ -95,6 +106,7 @@ impl From<(u32, String)> for ServiceManagerError {
             ERROR_ACCESS_DENIED => Self::AccessDenied(err, display),
             ERROR_DATABASE_DOES_NOT_EXIST => Self::DatabaseDoesNotExist(err, display),
             ERROR_INVALID_HANDLE => Self::DatabaseDoesNotExist(err, display),
+            RPC_S_SERVER_UNAVAILABLE => Self::MachineUnavailable(err, display),
             _ => Self::Unknown(err, display),
         }
     }
@@ -109,6 +121,14 @@ pub enum ControlServiceError {
     PathNotFound(u32, String),
     #[error("Service already running: {0}, {1}")]
     ServiceAlreadyRunning(u32, String),
+    #[error("Service cannot accept control: {0}, {1}")]
+    CannotAcceptControl(u32, String),
+    #[error("Invalid service control: {0}, {1}")]
+    InvalidServiceControl(u32, String),
+    #[error("Invalid parameter: {0}, {1}")]
+    InvalidParameter(u32, String),
+    #[error("Dependent services running: {0}, {1}")]
+    DependentServicesRunning(u32, String),
     #[error("Service not active: {0}, {1}")]
     ServiceNotActive(u32, String),
     #[error("Service database locked: {0}, {1}")]
@@ -127,10 +147,28 @@ pub enum ControlServiceError {
     ServiceNoThread(u32, String),
     #[error("Service request timeout: {0}, {1}")]
     ServiceRequestTimeout(u32, String),
+    #[error("Timed out while the service was making progress: {0}, {1}")]
+    TimedOut(u32, String),
+    #[error("Service stopped making progress: {0}, {1}")]
+    Stalled(u32, String),
+    #[error("Service in unexpected state: {0}, {1}")]
+    UnexpectedState(u32, String),
     #[error("Unknown error: {0}, {1}")]
     Unknown(u32, String),
 }
 
+/// A failed [`ServiceHandle::restart`](crate::service::ServiceHandle::restart), along with what
+/// it had done before failing.
+#[derive(Error, Debug)]
+#[error("{error}")]
+pub struct RestartError {
+    pub error: ControlServiceError,
+    /// The phases that completed, and the dependents that had been stopped.
+    pub report: Box<RestartReport>,
+    /// The stopped dependents that couldn't be started again after the failure.
+    pub left_stopped: Vec<String>,
+}
+
 impl From<(u32, String)> for ControlServiceError {
     fn from(value: (u32, String)) -> Self {
         let (err, display) = value;
@@ -139,6 +177,10 @@ impl From<(u32, String)> for ControlServiceError {
             ERROR_INVALID_HANDLE => Self::InvalidHandle(err, display),
             ERROR_PATH_NOT_FOUND | ERROR_FILE_NOT_FOUND => Self::PathNotFound(err, display),
             ERROR_SERVICE_ALREADY_RUNNING => Self::ServiceAlreadyRunning(err, display),
+            ERROR_SERVICE_CANNOT_ACCEPT_CTRL => Self::CannotAcceptControl(err, display),
+            ERROR_INVALID_SERVICE_CONTROL => Self::InvalidServiceControl(err, display),
+            ERROR_INVALID_PARAMETER => Self::InvalidParameter(err, display),
+            ERROR_DEPENDENT_SERVICES_RUNNING => Self::DependentServicesRunning(err, display),
             ERROR_SERVICE_NOT_ACTIVE => Self::ServiceNotActive(err, display),
             ERROR_SERVICE_DATABASE_LOCKED => Self::ServiceDatabaseLocked(err, display),
             ERROR_SERVICE_DEPENDENCY_DELETED => Self::ServiceDependencyDeleted(err, display),
@@ -148,11 +190,25 @@ impl From<(u32, String)> for ControlServiceError {
             ERROR_SERVICE_MARKED_FOR_DELETE => Self::ServiceMarkedForDelete(err, display),
             ERROR_SERVICE_NO_THREAD => Self::ServiceNoThread(err, display),
             ERROR_SERVICE_REQUEST_TIMEOUT => Self::ServiceRequestTimeout(err, display),
+            ERROR_TIMEOUT => Self::TimedOut(err, display),
             _ => Self::Unknown(err, display),
         }
     }
 }
 
+impl From<QueryServiceError> for ControlServiceError {
+    fn from(value: QueryServiceError) -> Self {
+        match value {
+            QueryServiceError::AccessDenied(err, display) => Self::AccessDenied(err, display),
+            QueryServiceError::InvalidHandle(err, display) => Self::InvalidHandle(err, display),
+            QueryServiceError::InvalidParameter(err, display) => {
+                Self::InvalidParameter(err, display)
+            }
+            QueryServiceError::Unknown(err, display) => Self::from((err, display)),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum DeleteServiceError {
     #[error("Access denied: {0}, {1}")]
@@ -177,7 +233,6 @@ impl From<(u32, String)> for DeleteServiceError {
     }
 }
 
-
 #[derive(Error, Debug)]
 pub enum UpdateServiceError {
     #[error("Access denied: {0}, {1}")]
@@ -192,6 +247,8 @@ pub enum UpdateServiceError {
     InvalidParameter(u32, String),
     #[error("Invalid service account: {0}, {1}")]
     InvalidServiceAccount(u32, String),
+    #[error("Invalid service configuration: {0}, {1}")]
+    InvalidConfig(u32, String),
     #[error("Service marked for deletion: {0}, {1}")]
     ServiceMarkedForDelete(u32, String),
     #[error("Unknown error: {0}, {1}")]
@@ -214,6 +271,18 @@ impl From<(u32, String)> for UpdateServiceError {
     }
 }
 
+impl From<QueryServiceError> for UpdateServiceError {
+    fn from(value: QueryServiceError) -> Self {
+        match value {
+            QueryServiceError::AccessDenied(err, display) => Self::AccessDenied(err, display),
+            QueryServiceError::InvalidHandle(err, display) => Self::InvalidHandle(err, display),
+            QueryServiceError::InvalidParameter(err, display) => {
+                Self::InvalidParameter(err, display)
+            }
+            QueryServiceError::Unknown(err, display) => Self::from((err, display)),
+        }
+    }
+}
 
 #[derive(Error, Debug)]
 pub enum QueryServiceError {
@@ -221,6 +290,8 @@ pub enum QueryServiceError {
     AccessDenied(u32, String),
     #[error("Invalid service handle: {0}, {1}")]
     InvalidHandle(u32, String),
+    #[error("Invalid parameter: {0}, {1}")]
+    InvalidParameter(u32, String),
     #[error("Unknown error: {0}, {1}")]
     Unknown(u32, String),
 }
@@ -231,6 +302,43 @@ impl From<(u32, String)> for QueryServiceError {
         match err {
             ERROR_ACCESS_DENIED => Self::AccessDenied(err, display),
             ERROR_INVALID_HANDLE => Self::InvalidHandle(err, display),
+            ERROR_INVALID_PARAMETER => Self::InvalidParameter(err, display),
+            _ => Self::Unknown(err, display),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ServiceAccountError {
+    #[error("Invalid service account: {0}, {1}")]
+    InvalidServiceAccount(u32, String),
+}
+
+#[derive(Error, Debug)]
+pub enum ServiceTriggerError {
+    #[error("Invalid service trigger: {0}, {1}")]
+    InvalidTrigger(u32, String),
+}
+
+#[derive(Error, Debug)]
+pub enum PrivilegeError {
+    #[error("Access denied: {0}, {1}")]
+    AccessDenied(u32, String),
+    #[error("No such privilege: {0}, {1}")]
+    NoSuchPrivilege(u32, String),
+    #[error("Privileges not held: {0}, {1}")]
+    NotAllAssigned(u32, String),
+    #[error("Unknown error: {0}, {1}")]
+    Unknown(u32, String),
+}
+
+impl From<(u32, String)> for PrivilegeError {
+    fn from(value: (u32, String)) -> Self {
+        let (err, display) = value;
+        match err {
+            ERROR_ACCESS_DENIED => Self::AccessDenied(err, display),
+            ERROR_NO_SUCH_PRIVILEGE => Self::NoSuchPrivilege(err, display),
+            ERROR_NOT_ALL_ASSIGNED | ERROR_PRIVILEGE_NOT_HELD => Self::NotAllAssigned(err, display),
             _ => Self::Unknown(err, display),
         }
     }
@@ -0,0 +1,1536 @@
+//! This module provides an in-memory service control manager for tests.
+//! `FakeScm` keeps its own service database, walks services through the same pending, running,
+//! paused and stopped states as Windows and fails with the Win32 error codes the real SCM uses,
+//! so code built on `ServiceManager` can be exercised without an elevated Windows machine.
+//! Remote machines added with [`FakeScm::add_machine`] get service databases of their own.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    account::ServiceAccount,
+    backend::{
+        NotifyRegistration, RawConfigChange, RawFailureActions, RawServiceConfig, RawServiceEntry,
+        RawServiceNotify, RawServiceStatus, RawServiceTrigger, ScHandle, ScmBackend,
+        SC_GROUP_IDENTIFIER, SERVICE_NO_CHANGE,
+    },
+    consts::{
+        DELETE, ERROR_ACCESS_DENIED, ERROR_CIRCULAR_DEPENDENCY, ERROR_DATABASE_DOES_NOT_EXIST,
+        ERROR_DEPENDENT_SERVICES_RUNNING, ERROR_DUPLICATE_SERVICE_NAME, ERROR_INVALID_HANDLE,
+        ERROR_INVALID_NAME, ERROR_INVALID_PARAMETER, ERROR_INVALID_SERVICE_ACCOUNT,
+        ERROR_INVALID_SERVICE_CONTROL, ERROR_NOT_ALL_ASSIGNED, ERROR_NO_SUCH_PRIVILEGE,
+        ERROR_PROCESS_ABORTED, ERROR_SERVICE_ALREADY_RUNNING, ERROR_SERVICE_CANNOT_ACCEPT_CTRL,
+        ERROR_SERVICE_DEPENDENCY_DELETED, ERROR_SERVICE_DEPENDENCY_FAIL, ERROR_SERVICE_DISABLED,
+        ERROR_SERVICE_DOES_NOT_EXIST, ERROR_SERVICE_EXISTS, ERROR_SERVICE_MARKED_FOR_DELETE,
+        ERROR_SERVICE_NOT_ACTIVE, MAX_SERVICE_STOP_COMMENT_LEN, RPC_S_SERVER_UNAVAILABLE,
+        SC_ACTION_REBOOT, SC_ACTION_RESTART, SC_ACTION_RUN_COMMAND, SC_MANAGER_CONNECT,
+        SC_MANAGER_CREATE_SERVICE, SC_MANAGER_ENUMERATE_SERVICE, SERVICES_ACTIVE_DATABASE,
+        SERVICE_ACCEPT_NETBINDCHANGE, SERVICE_ACCEPT_PARAMCHANGE, SERVICE_ACCEPT_PAUSE_CONTINUE,
+        SERVICE_ACCEPT_STOP, SERVICE_ACTIVE, SERVICE_BOOT_START, SERVICE_CHANGE_CONFIG,
+        SERVICE_CONTINUE_PENDING, SERVICE_CONTROL_CONTINUE, SERVICE_CONTROL_INTERROGATE,
+        SERVICE_CONTROL_NETBINDADD, SERVICE_CONTROL_NETBINDDISABLE, SERVICE_CONTROL_NETBINDENABLE,
+        SERVICE_CONTROL_NETBINDREMOVE, SERVICE_CONTROL_PARAMCHANGE, SERVICE_CONTROL_PAUSE,
+        SERVICE_CONTROL_STOP, SERVICE_DISABLED, SERVICE_DRIVER, SERVICE_ENUMERATE_DEPENDENTS,
+        SERVICE_INACTIVE, SERVICE_INTERROGATE, SERVICE_NOTIFY_CREATED, SERVICE_NOTIFY_DELETED,
+        SERVICE_NOTIFY_DELETE_PENDING, SERVICE_PAUSED, SERVICE_PAUSE_CONTINUE,
+        SERVICE_PAUSE_PENDING, SERVICE_QUERY_CONFIG, SERVICE_QUERY_STATUS, SERVICE_RUNNING,
+        SERVICE_SID_TYPE_NONE, SERVICE_SID_TYPE_RESTRICTED, SERVICE_SID_TYPE_UNRESTRICTED,
+        SERVICE_START, SERVICE_START_PENDING, SERVICE_STATE_ALL, SERVICE_STOP, SERVICE_STOPPED,
+        SERVICE_STOP_PENDING, SERVICE_STOP_REASON_FLAG_CUSTOM, SERVICE_STOP_REASON_FLAG_PLANNED,
+        SERVICE_STOP_REASON_FLAG_UNPLANNED, SERVICE_STOP_REASON_MAJOR_NONE,
+        SERVICE_STOP_REASON_MAJOR_OTHER, SERVICE_STOP_REASON_MINOR_NONE,
+        SERVICE_STOP_REASON_MINOR_OTHER, SERVICE_SYSTEM_START,
+        SERVICE_TRIGGER_ACTION_SERVICE_START, SERVICE_TRIGGER_ACTION_SERVICE_STOP,
+        SERVICE_TRIGGER_DATA_TYPE_BINARY, SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ALL,
+        SERVICE_TRIGGER_TYPE_AGGREGATE, SERVICE_TRIGGER_TYPE_CUSTOM,
+        SERVICE_TRIGGER_TYPE_CUSTOM_SYSTEM_STATE_CHANGE,
+        SERVICE_TRIGGER_TYPE_DEVICE_INTERFACE_ARRIVAL, SERVICE_USER_DEFINED_CONTROL, SERVICE_WIN32,
+        SERVICE_WIN32_OWN_PROCESS, SERVICE_WIN32_SHARE_PROCESS,
+    },
+    privilege::Privilege,
+    service::{
+        ServiceDependency, ServiceErrorControl, ServiceStartType, ServiceState, ServiceType,
+    },
+    service_manager::ServiceConfig,
+};
+
+#[doc(hidden)]
+const MAX_SERVICE_NAME_LEN: usize = 256;
+
+/// Milliseconds a service gets to handle `SERVICE_CONTROL_PRESHUTDOWN` unless configured otherwise.
+#[doc(hidden)]
+const DEFAULT_PRESHUTDOWN_TIMEOUT: u32 = 180_000;
+
+/// Describes how a fake service reacts to start and control requests.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FakeServiceBehavior {
+    /// Number of status queries that report a pending state before the transition completes.
+    /// Zero completes every transition immediately.
+    pub pending_steps: u32,
+    /// Wait hint, in milliseconds, reported while a transition is pending.
+    pub wait_hint: u32,
+    /// Win32 exit code the service stops with instead of reaching the running state.
+    pub start_failure: Option<u32>,
+    /// `SERVICE_ACCEPT_*` mask reported while running. `None` picks the default for the service type.
+    pub controls_accepted: Option<u32>,
+    /// Pending state in which transitions stop advancing, like a service that hangs.
+    pub hang_in: Option<ServiceState>,
+}
+
+impl Default for FakeServiceBehavior {
+    fn default() -> Self {
+        Self {
+            pending_steps: 1,
+            wait_hint: 3000,
+            start_failure: None,
+            controls_accepted: None,
+            hang_in: None,
+        }
+    }
+}
+
+/// In-memory implementation of [`ScmBackend`].
+#[derive(Default, Debug)]
+pub struct FakeScm {
+    database: Mutex<FakeDatabase>,
+    /// Signaled whenever the database changes, to wake up pending notifications.
+    changed: Condvar,
+}
+
+#[derive(Debug)]
+struct FakeDatabase {
+    last_handle: ScHandle,
+    /// Privileges held by the simulated process token, and whether each one is enabled.
+    privileges: BTreeMap<Privilege, bool>,
+    handles: HashMap<ScHandle, FakeHandle>,
+    /// Simulated machines by [`machine_key`], the local machine being `""`.
+    machines: BTreeMap<String, FakeMachine>,
+}
+
+#[derive(Default, Debug)]
+struct FakeMachine {
+    last_process_id: u32,
+    services: BTreeMap<String, FakeService>,
+}
+
+#[derive(Debug)]
+struct FakeHandle {
+    /// Key of the machine the handle was opened on.
+    machine: String,
+    /// Key of the service this handle refers to, or `None` for a service control manager handle.
+    service: Option<String>,
+    access: u32,
+    /// Services of the machine by key, as of the last notification on a service control
+    /// manager handle or its opening. `None` for service handles.
+    known_services: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug)]
+struct FakeService {
+    name: String,
+    config: RawServiceConfig,
+    status: RawServiceStatus,
+    pending: Option<FakePending>,
+    marked_for_delete: bool,
+    behavior: FakeServiceBehavior,
+    /// Arguments of the last `StartServiceW` call.
+    start_arguments: Vec<String>,
+    /// Reason code and comment of the last `ControlServiceExW` call.
+    stop_reason: Option<(u32, Option<String>)>,
+    description: String,
+    failure_actions: RawFailureActions,
+    failure_actions_on_non_crash_failures: bool,
+    delayed_auto_start: bool,
+    preshutdown_timeout: u32,
+    triggers: Vec<RawServiceTrigger>,
+    sid_type: u32,
+    required_privileges: Vec<String>,
+}
+
+#[derive(Debug)]
+struct FakePending {
+    target_state: u32,
+    remaining: u32,
+}
+
+/// Checks a `SERVICE_STOP_REASON_*` code the way `ControlServiceExW` does: exactly one of the
+/// planned, unplanned and custom flags, and major and minor codes in the range of that kind.
+#[doc(hidden)]
+fn valid_stop_reason(reason: u32) -> bool {
+    let major = (reason >> 16) & 0xff;
+    let minor = reason & 0xffff;
+    match reason & 0xff000000 {
+        SERVICE_STOP_REASON_FLAG_CUSTOM => (0x40..=0xff).contains(&major) && minor >= 0x100,
+        SERVICE_STOP_REASON_FLAG_PLANNED | SERVICE_STOP_REASON_FLAG_UNPLANNED => {
+            (SERVICE_STOP_REASON_MAJOR_OTHER..=SERVICE_STOP_REASON_MAJOR_NONE)
+                .contains(&(major << 16))
+                && (SERVICE_STOP_REASON_MINOR_OTHER..=SERVICE_STOP_REASON_MINOR_NONE)
+                    .contains(&minor)
+        }
+        _ => false,
+    }
+}
+
+#[doc(hidden)]
+fn service_key(name: &str) -> String {
+    name.to_lowercase()
+}
+
+/// Normalizes a machine name, so that `HOST`, `host` and `\\host` refer to the same machine.
+#[doc(hidden)]
+fn machine_key(machine_name: Option<&str>) -> String {
+    machine_name
+        .map(|machine_name| machine_name.trim_start_matches('\\').to_lowercase())
+        .unwrap_or_default()
+}
+
+#[doc(hidden)]
+fn validate_service_name(name: &str) -> Result<(), u32> {
+    if name.is_empty() || name.len() > MAX_SERVICE_NAME_LEN || name.contains(['/', '\\', '\0']) {
+        return Err(ERROR_INVALID_NAME);
+    }
+    Ok(())
+}
+
+#[doc(hidden)]
+fn validate_types(service_type: u32, start_type: u32, error_control: u32) -> Result<(), u32> {
+    ServiceType::try_from(service_type).map_err(|_| ERROR_INVALID_PARAMETER)?;
+    ServiceStartType::try_from(start_type).map_err(|_| ERROR_INVALID_PARAMETER)?;
+    ServiceErrorControl::try_from(error_control).map_err(|_| ERROR_INVALID_PARAMETER)?;
+
+    // Only drivers can be loaded by the boot loader or during kernel initialization.
+    let is_win32 = service_type & (SERVICE_WIN32_OWN_PROCESS | SERVICE_WIN32_SHARE_PROCESS) != 0;
+    if is_win32 && (start_type == SERVICE_BOOT_START || start_type == SERVICE_SYSTEM_START) {
+        return Err(ERROR_INVALID_PARAMETER);
+    }
+    Ok(())
+}
+
+impl FakeService {
+    fn default_controls_accepted(&self) -> u32 {
+        if let Some(controls) = self.behavior.controls_accepted {
+            return controls;
+        }
+        if self.config.service_type & (SERVICE_WIN32_OWN_PROCESS | SERVICE_WIN32_SHARE_PROCESS) != 0
+        {
+            SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_PAUSE_CONTINUE
+        } else {
+            SERVICE_ACCEPT_STOP
+        }
+    }
+
+    fn status(&self) -> RawServiceStatus {
+        RawServiceStatus {
+            service_type: self.config.service_type,
+            ..self.status
+        }
+    }
+
+    fn entry(&self) -> RawServiceEntry {
+        RawServiceEntry {
+            service_name: self.name.clone(),
+            display_name: self.config.display_name.clone(),
+            status: self.status(),
+        }
+    }
+
+    /// Applies a `SERVICE_ACTIVE`/`SERVICE_INACTIVE`/`SERVICE_STATE_ALL` enumeration filter.
+    fn matches_state(&self, service_state: u32) -> bool {
+        let active = self.status.current_state != SERVICE_STOPPED;
+        match service_state {
+            SERVICE_ACTIVE => active,
+            SERVICE_INACTIVE => !active,
+            _ => true,
+        }
+    }
+
+    fn begin_transition(&mut self, pending_state: u32, target_state: u32) {
+        self.status.current_state = pending_state;
+        self.status.controls_accepted = 0;
+        self.status.check_point = 0;
+        self.status.wait_hint = self.behavior.wait_hint;
+        self.pending = Some(FakePending {
+            target_state,
+            remaining: self.behavior.pending_steps,
+        });
+        if self.behavior.pending_steps == 0 {
+            self.complete_transition();
+        }
+    }
+
+    fn advance(&mut self) {
+        let current_state = self.status.current_state;
+        let hung = |state: &ServiceState| state.clone() as u32 == current_state;
+        let Some(pending) = self
+            .pending
+            .as_mut()
+            .filter(|_| !self.behavior.hang_in.as_ref().is_some_and(hung))
+        else {
+            return;
+        };
+        if pending.remaining == 0 {
+            self.complete_transition();
+        } else {
+            pending.remaining -= 1;
+            self.status.check_point += 1;
+        }
+    }
+
+    fn complete_transition(&mut self) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+        self.status.check_point = 0;
+        self.status.wait_hint = 0;
+        match (pending.target_state, self.behavior.start_failure) {
+            (SERVICE_RUNNING, Some(exit_code))
+                if self.status.current_state == SERVICE_START_PENDING =>
+            {
+                self.status.current_state = SERVICE_STOPPED;
+                self.status.controls_accepted = 0;
+                self.status.process_id = 0;
+                self.status.win32_exit_code = exit_code;
+            }
+            (SERVICE_STOPPED, _) => {
+                self.status.current_state = SERVICE_STOPPED;
+                self.status.controls_accepted = 0;
+                self.status.process_id = 0;
+            }
+            (target_state, _) => {
+                self.status.current_state = target_state;
+                self.status.controls_accepted = self.default_controls_accepted();
+            }
+        }
+    }
+
+    fn run_immediately(&mut self, process_id: u32) -> Result<(), u32> {
+        if let Some(exit_code) = self.behavior.start_failure {
+            self.status.win32_exit_code = exit_code;
+            return Err(ERROR_SERVICE_DEPENDENCY_FAIL);
+        }
+        self.status.current_state = SERVICE_RUNNING;
+        self.status.process_id = process_id;
+        self.status.controls_accepted = self.default_controls_accepted();
+        self.status.win32_exit_code = 0;
+        Ok(())
+    }
+}
+
+impl Default for FakeDatabase {
+    fn default() -> Self {
+        Self {
+            last_handle: 0,
+            privileges: BTreeMap::new(),
+            handles: HashMap::new(),
+            machines: BTreeMap::from([(String::new(), FakeMachine::default())]),
+        }
+    }
+}
+
+impl FakeDatabase {
+    fn insert_handle(&mut self, machine: String, service: Option<String>, access: u32) -> ScHandle {
+        self.last_handle += 1;
+        self.handles.insert(
+            self.last_handle,
+            FakeHandle {
+                machine,
+                service,
+                access,
+                known_services: None,
+            },
+        );
+        self.last_handle
+    }
+
+    fn local_mut(&mut self) -> &mut FakeMachine {
+        self.machines.entry(String::new()).or_default()
+    }
+
+    fn machine_mut(&mut self, machine: &str) -> Result<&mut FakeMachine, u32> {
+        self.machines.get_mut(machine).ok_or(ERROR_INVALID_HANDLE)
+    }
+
+    /// Returns the key of the machine a service control manager handle was opened on.
+    fn manager(&self, handle: ScHandle, required_access: u32) -> Result<String, u32> {
+        match self.handles.get(&handle) {
+            Some(FakeHandle {
+                machine,
+                service: None,
+                access,
+                ..
+            }) if access & required_access == required_access => Ok(machine.clone()),
+            Some(FakeHandle { service: None, .. }) => Err(ERROR_ACCESS_DENIED),
+            _ => Err(ERROR_INVALID_HANDLE),
+        }
+    }
+
+    /// Returns the keys of the machine and the service a service handle refers to.
+    fn service_key(&self, handle: ScHandle, required_access: u32) -> Result<(String, String), u32> {
+        match self.handles.get(&handle) {
+            Some(FakeHandle {
+                machine,
+                service: Some(key),
+                access,
+                ..
+            }) => {
+                if access & required_access != required_access {
+                    return Err(ERROR_ACCESS_DENIED);
+                }
+                Ok((machine.clone(), key.clone()))
+            }
+            _ => Err(ERROR_INVALID_HANDLE),
+        }
+    }
+
+    /// Returns the machine a service handle was opened on, along with the key of the service.
+    fn service_machine(
+        &mut self,
+        handle: ScHandle,
+        required_access: u32,
+    ) -> Result<(&mut FakeMachine, String), u32> {
+        let (machine, key) = self.service_key(handle, required_access)?;
+        Ok((self.machine_mut(&machine)?, key))
+    }
+
+    fn service_mut(
+        &mut self,
+        handle: ScHandle,
+        required_access: u32,
+    ) -> Result<&mut FakeService, u32> {
+        let (machine, key) = self.service_machine(handle, required_access)?;
+        machine.services.get_mut(&key).ok_or(ERROR_INVALID_HANDLE)
+    }
+
+    /// Returns the notification `handle` would receive now for the events in `notify_mask`.
+    fn notification(
+        &mut self,
+        handle: ScHandle,
+        notify_mask: u32,
+    ) -> Result<Option<RawServiceNotify>, u32> {
+        let FakeHandle {
+            machine,
+            service,
+            access,
+            ..
+        } = self.handles.get(&handle).ok_or(ERROR_INVALID_HANDLE)?;
+        let machine = self.machines.get(machine).ok_or(ERROR_INVALID_HANDLE)?;
+        let scm_events = SERVICE_NOTIFY_CREATED | SERVICE_NOTIFY_DELETED;
+
+        let Some(key) = service else {
+            if access & SC_MANAGER_ENUMERATE_SERVICE == 0 {
+                return Err(ERROR_ACCESS_DENIED);
+            }
+            if notify_mask == 0 || notify_mask & !scm_events != 0 {
+                return Err(ERROR_INVALID_PARAMETER);
+            }
+            let current = machine.service_names();
+            let Some(known) = self
+                .handles
+                .get_mut(&handle)
+                .and_then(|handle| handle.known_services.as_mut())
+            else {
+                return Err(ERROR_INVALID_HANDLE);
+            };
+
+            let mut notify = RawServiceNotify::default();
+            for (key, name) in &current {
+                if notify_mask & SERVICE_NOTIFY_CREATED != 0 && !known.contains_key(key) {
+                    notify.notification_triggered |= SERVICE_NOTIFY_CREATED;
+                    notify.service_names.push(format!("/{name}"));
+                }
+            }
+            for (key, name) in known.iter() {
+                if notify_mask & SERVICE_NOTIFY_DELETED != 0 && !current.contains_key(key) {
+                    notify.notification_triggered |= SERVICE_NOTIFY_DELETED;
+                    notify.service_names.push(format!("\\{name}"));
+                }
+            }
+            *known = current;
+            return Ok((notify.notification_triggered != 0).then_some(notify));
+        };
+
+        if access & SERVICE_QUERY_STATUS == 0 {
+            return Err(ERROR_ACCESS_DENIED);
+        }
+        if notify_mask == 0 || notify_mask & scm_events != 0 {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        let service = machine.services.get(key).ok_or(ERROR_INVALID_HANDLE)?;
+        let notification_triggered = if service.marked_for_delete {
+            if notify_mask & SERVICE_NOTIFY_DELETE_PENDING == 0 {
+                return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+            }
+            SERVICE_NOTIFY_DELETE_PENDING
+        } else {
+            // The SERVICE_NOTIFY_* state bits follow the order of the SERVICE_* states.
+            1 << (service.status.current_state - 1)
+        };
+        Ok(
+            (notify_mask & notification_triggered != 0).then(|| RawServiceNotify {
+                notification_triggered,
+                status: service.status(),
+                service_names: Vec::new(),
+            }),
+        )
+    }
+
+    /// Moves the pending transition of the service `handle` refers to one step further, as the
+    /// service would while a caller waits for it. Returns `false` if nothing is pending.
+    fn advance_watched(&mut self, handle: ScHandle) -> bool {
+        let Ok(service) = self.service_mut(handle, 0) else {
+            return false;
+        };
+        if service.pending.is_none() {
+            return false;
+        }
+        service.advance();
+        true
+    }
+
+    /// Drops services that are marked for deletion once they are stopped and no handle refers to them.
+    fn purge(&mut self) {
+        let handles = &self.handles;
+        for (machine_key, machine) in &mut self.machines {
+            machine.services.retain(|key, service| {
+                !service.marked_for_delete
+                    || service.status.current_state != SERVICE_STOPPED
+                    || handles.values().any(|handle| {
+                        handle.machine == *machine_key
+                            && handle.service.as_deref() == Some(key.as_str())
+                    })
+            });
+        }
+    }
+}
+
+impl FakeMachine {
+    /// Returns the names of the installed services by key.
+    fn service_names(&self) -> BTreeMap<String, String> {
+        self.services
+            .iter()
+            .map(|(key, service)| (key.clone(), service.name.clone()))
+            .collect()
+    }
+
+    /// Picks the process a starting service runs in. Drivers have none, and Win32 services
+    /// sharing a binary with a running share-process service join its process.
+    fn allocate_process_id(&mut self, key: &str) -> u32 {
+        let Some(service) = self.services.get(key) else {
+            return 0;
+        };
+        if service.config.service_type & (SERVICE_WIN32_OWN_PROCESS | SERVICE_WIN32_SHARE_PROCESS)
+            == 0
+        {
+            return 0;
+        }
+        if service.config.service_type & SERVICE_WIN32_SHARE_PROCESS != 0 {
+            let binary_path = service_key(&service.config.binary_path_name);
+            if let Some(host) = self.services.values().find(|other| {
+                other.config.service_type & SERVICE_WIN32_SHARE_PROCESS != 0
+                    && other.status.process_id != 0
+                    && service_key(&other.config.binary_path_name) == binary_path
+            }) {
+                return host.status.process_id;
+            }
+        }
+        self.last_process_id = self.last_process_id.max(996) + 4;
+        self.last_process_id
+    }
+
+    fn display_name_taken(&self, display_name: &str, except: Option<&str>) -> bool {
+        let display_key = service_key(display_name);
+        self.services.iter().any(|(key, service)| {
+            Some(key.as_str()) != except
+                && (*key == display_key || service_key(&service.config.display_name) == display_key)
+        })
+    }
+
+    /// Returns whether `from` reaches `target` by following service dependencies.
+    fn depends_on(&self, from: &[String], target: &str, visited: &mut Vec<String>) -> bool {
+        from.iter()
+            .filter(|dependency| !dependency.starts_with(SC_GROUP_IDENTIFIER))
+            .map(|dependency| service_key(dependency))
+            .any(|dependency| {
+                if dependency == target {
+                    return true;
+                }
+                if visited.contains(&dependency) {
+                    return false;
+                }
+                visited.push(dependency.clone());
+                self.services.get(&dependency).is_some_and(|service| {
+                    self.depends_on(&service.config.dependencies, target, visited)
+                })
+            })
+    }
+
+    /// Returns the services listing `key`, or its load order group, as a dependency.
+    fn direct_dependents(&self, key: &str) -> Vec<String> {
+        let Some(service) = self.services.get(key) else {
+            return Vec::new();
+        };
+        let group = (!service.config.load_order_group.is_empty()).then(|| {
+            service_key(&format!(
+                "{SC_GROUP_IDENTIFIER}{}",
+                service.config.load_order_group
+            ))
+        });
+
+        self.services
+            .iter()
+            .filter(|(_, other)| {
+                other.config.dependencies.iter().any(|dependency| {
+                    let dependency = service_key(dependency);
+                    dependency == key || group.as_ref() == Some(&dependency)
+                })
+            })
+            .map(|(other_key, _)| other_key.clone())
+            .collect()
+    }
+
+    /// Collects the transitive dependents of `key`, each one after its own dependents.
+    fn collect_dependents(
+        &self,
+        key: &str,
+        visited: &mut Vec<String>,
+        dependents: &mut Vec<String>,
+    ) {
+        for dependent in self.direct_dependents(key) {
+            if visited.contains(&dependent) {
+                continue;
+            }
+            visited.push(dependent.clone());
+            self.collect_dependents(&dependent, visited, dependents);
+            dependents.push(dependent);
+        }
+    }
+
+    fn has_active_dependents(&self, key: &str) -> bool {
+        self.services.values().any(|service| {
+            service.status.current_state != SERVICE_STOPPED
+                && service
+                    .config
+                    .dependencies
+                    .iter()
+                    .any(|dependency| service_key(dependency) == key)
+        })
+    }
+
+    /// Starts the dependencies of `key`, which the SCM brings up before the service itself.
+    fn start_dependencies(&mut self, key: &str, visited: &mut Vec<String>) -> Result<(), u32> {
+        let dependencies = self
+            .services
+            .get(key)
+            .map(|service| service.config.dependencies.clone())
+            .unwrap_or_default();
+
+        for dependency in dependencies
+            .iter()
+            .filter(|dependency| !dependency.starts_with(SC_GROUP_IDENTIFIER))
+        {
+            let dependency = service_key(dependency);
+            if visited.contains(&dependency) {
+                continue;
+            }
+            visited.push(dependency.clone());
+
+            match self.services.get(&dependency) {
+                None => return Err(ERROR_SERVICE_DEPENDENCY_DELETED),
+                Some(service) if service.marked_for_delete => {
+                    return Err(ERROR_SERVICE_DEPENDENCY_DELETED)
+                }
+                Some(service) if service.config.start_type == SERVICE_DISABLED => {
+                    return Err(ERROR_SERVICE_DEPENDENCY_FAIL)
+                }
+                _ => {}
+            }
+
+            self.start_dependencies(&dependency, visited)?;
+
+            let process_id = self.allocate_process_id(&dependency);
+            let service = self
+                .services
+                .get_mut(&dependency)
+                .ok_or(ERROR_SERVICE_DEPENDENCY_DELETED)?;
+            if service.status.current_state == SERVICE_STOPPED {
+                service.run_immediately(process_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FakeScm {
+    /// Creates an empty service database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a remote machine with an empty service database, which a
+    /// [`ServiceManager`](crate::service_manager::ServiceManager) can then connect to by name.
+    ///
+    /// Returns `false` if a machine with that name already exists.
+    pub fn add_machine(&self, machine_name: &str) -> bool {
+        let key = machine_key(Some(machine_name));
+        let mut database = self.lock();
+        if key.is_empty() || database.machines.contains_key(&key) {
+            return false;
+        }
+        database.machines.insert(key, FakeMachine::default());
+        true
+    }
+
+    /// Replaces the behavior of a service installed on the local machine.
+    ///
+    /// Returns `false` if no service with that name exists.
+    pub fn set_behavior(&self, service_name: &str, behavior: FakeServiceBehavior) -> bool {
+        let mut database = self.lock();
+        match database
+            .local_mut()
+            .services
+            .get_mut(&service_key(service_name))
+        {
+            Some(service) => {
+                service.behavior = behavior;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Overwrites the display name of a service installed on the local machine without the uniqueness check
+    /// `ChangeServiceConfigW` performs, as editing the registry directly would.
+    ///
+    /// Returns `false` if no service with that name exists.
+    pub fn set_display_name(&self, service_name: &str, display_name: &str) -> bool {
+        let mut database = self.lock();
+        match database
+            .local_mut()
+            .services
+            .get_mut(&service_key(service_name))
+        {
+            Some(service) => {
+                service.config.display_name = display_name.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Overwrites the raw type of a service installed on the local machine, including modifier
+    /// bits such as `SERVICE_INTERACTIVE_PROCESS` that [`ServiceConfig`](crate::service_manager::ServiceConfig)
+    /// can't express, as editing the registry directly would.
+    ///
+    /// Returns `false` if no service with that name exists.
+    pub fn set_service_type(&self, service_name: &str, service_type: u32) -> bool {
+        let mut database = self.lock();
+        match database
+            .local_mut()
+            .services
+            .get_mut(&service_key(service_name))
+        {
+            Some(service) => {
+                service.config.service_type = service_type;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a disabled privilege to the simulated process token, so that a
+    /// [`PrivilegeGuard`](crate::privilege::PrivilegeGuard) can enable it.
+    pub fn grant_privilege(&self, privilege: Privilege) {
+        self.lock().privileges.entry(privilege).or_insert(false);
+    }
+
+    /// Returns whether the simulated process token holds `privilege` and has it enabled.
+    pub fn privilege_enabled(&self, privilege: Privilege) -> bool {
+        self.lock()
+            .privileges
+            .get(&privilege)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Returns the arguments a service installed on the local machine was last started with.
+    ///
+    /// Returns `None` if no service with that name exists.
+    pub fn start_arguments(&self, service_name: &str) -> Option<Vec<String>> {
+        self.lock()
+            .local_mut()
+            .services
+            .get(&service_key(service_name))
+            .map(|service| service.start_arguments.clone())
+    }
+
+    /// Returns the reason code and comment a service installed on the local machine was last
+    /// controlled with through `ControlServiceExW`.
+    ///
+    /// Returns `None` if no service with that name exists or it was never given a reason.
+    pub fn stop_reason(&self, service_name: &str) -> Option<(u32, Option<String>)> {
+        self.lock()
+            .local_mut()
+            .services
+            .get(&service_key(service_name))
+            .and_then(|service| service.stop_reason.clone())
+    }
+
+    /// Returns whether a service with that name is still in the database of the local machine.
+    pub fn contains(&self, service_name: &str) -> bool {
+        self.lock()
+            .local_mut()
+            .services
+            .contains_key(&service_key(service_name))
+    }
+
+    /// Wakes up the callers waiting for a notification, after the database changed.
+    fn notify_changed(&self) {
+        self.changed.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FakeDatabase> {
+        // A panicking test must not poison the database for the tests that share it.
+        self.database
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ScmBackend for FakeScm {
+    fn open_sc_manager(
+        &self,
+        machine_name: Option<&str>,
+        database_name: Option<&str>,
+        desired_access: u32,
+    ) -> Result<ScHandle, u32> {
+        let mut database = self.lock();
+        let machine = machine_key(machine_name);
+        if !database.machines.contains_key(&machine) {
+            return Err(RPC_S_SERVER_UNAVAILABLE);
+        }
+        if database_name.is_some_and(|name| !name.eq_ignore_ascii_case(SERVICES_ACTIVE_DATABASE)) {
+            return Err(ERROR_DATABASE_DOES_NOT_EXIST);
+        }
+        let known_services = database.machines[&machine].service_names();
+        let handle = database.insert_handle(machine, None, desired_access);
+        if let Some(handle) = database.handles.get_mut(&handle) {
+            handle.known_services = Some(known_services);
+        }
+        Ok(handle)
+    }
+
+    fn open_service(
+        &self,
+        scm: ScHandle,
+        service_name: &str,
+        desired_access: u32,
+    ) -> Result<ScHandle, u32> {
+        let mut database = self.lock();
+        let machine = database.manager(scm, 0)?;
+        validate_service_name(service_name)?;
+
+        let key = service_key(service_name);
+        if !database.machine_mut(&machine)?.services.contains_key(&key) {
+            return Err(ERROR_SERVICE_DOES_NOT_EXIST);
+        }
+        Ok(database.insert_handle(machine, Some(key), desired_access))
+    }
+
+    fn get_service_key_name(&self, scm: ScHandle, display_name: &str) -> Result<String, u32> {
+        let mut database = self.lock();
+        let machine = database.manager(scm, SC_MANAGER_CONNECT)?;
+
+        let display_key = service_key(display_name);
+        database
+            .machine_mut(&machine)?
+            .services
+            .values()
+            .find(|service| service_key(&service.config.display_name) == display_key)
+            .map(|service| service.name.clone())
+            .ok_or(ERROR_SERVICE_DOES_NOT_EXIST)
+    }
+
+    fn get_service_display_name(&self, scm: ScHandle, service_name: &str) -> Result<String, u32> {
+        let mut database = self.lock();
+        let machine = database.manager(scm, SC_MANAGER_CONNECT)?;
+
+        let service = database
+            .machine_mut(&machine)?
+            .services
+            .get(&service_key(service_name))
+            .ok_or(ERROR_SERVICE_DOES_NOT_EXIST)?;
+        Ok(if service.config.display_name.is_empty() {
+            service.name.clone()
+        } else {
+            service.config.display_name.clone()
+        })
+    }
+
+    fn create_service(
+        &self,
+        scm: ScHandle,
+        config: &ServiceConfig,
+        desired_access: u32,
+    ) -> Result<(ScHandle, u32), u32> {
+        let mut database = self.lock();
+        let machine_key = database.manager(scm, SC_MANAGER_CREATE_SERVICE)?;
+        let machine = database.machine_mut(&machine_key)?;
+        validate_service_name(&config.service_name)?;
+
+        let key = service_key(&config.service_name);
+        if let Some(existing) = machine.services.get(&key) {
+            return Err(if existing.marked_for_delete {
+                ERROR_SERVICE_MARKED_FOR_DELETE
+            } else {
+                ERROR_SERVICE_EXISTS
+            });
+        }
+
+        let display_name = if config.display_name.is_empty() {
+            config.service_name.clone()
+        } else {
+            config.display_name.clone()
+        };
+        if machine.display_name_taken(&display_name, None) {
+            return Err(ERROR_DUPLICATE_SERVICE_NAME);
+        }
+
+        let service_type = config.service_type as u32;
+        let start_type = config.start_type as u32;
+        validate_types(service_type, start_type, config.error_control as u32)?;
+        if config.binary_path.is_empty() {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+
+        let dependencies: Vec<String> = config
+            .dependencies
+            .iter()
+            .map(ServiceDependency::to_raw)
+            .collect();
+        if dependencies
+            .iter()
+            .any(|dependency| service_key(dependency) == key)
+        {
+            return Err(ERROR_CIRCULAR_DEPENDENCY);
+        }
+
+        let load_order_group = config.load_order_group.clone().unwrap_or_default();
+        let tag_id = if config.request_tag {
+            if load_order_group.is_empty() {
+                return Err(ERROR_INVALID_PARAMETER);
+            }
+            machine
+                .services
+                .values()
+                .filter(|service| {
+                    service_key(&service.config.load_order_group) == service_key(&load_order_group)
+                })
+                .map(|service| service.config.tag_id)
+                .max()
+                .unwrap_or(0)
+                + 1
+        } else {
+            0
+        };
+
+        let service_start_name = match &config.account {
+            Some(account) => account.to_string(),
+            None if !config.service_type.is_driver() => "LocalSystem".to_string(),
+            None => String::new(),
+        };
+
+        machine.services.insert(
+            key.clone(),
+            FakeService {
+                name: config.service_name.clone(),
+                config: RawServiceConfig {
+                    service_type,
+                    start_type,
+                    error_control: config.error_control as u32,
+                    binary_path_name: config.binary_path.clone(),
+                    load_order_group,
+                    tag_id,
+                    dependencies,
+                    service_start_name,
+                    display_name,
+                },
+                status: RawServiceStatus {
+                    service_type,
+                    current_state: SERVICE_STOPPED,
+                    ..Default::default()
+                },
+                pending: None,
+                marked_for_delete: false,
+                behavior: FakeServiceBehavior::default(),
+                start_arguments: Vec::new(),
+                stop_reason: None,
+                description: String::new(),
+                failure_actions: RawFailureActions::default(),
+                failure_actions_on_non_crash_failures: false,
+                delayed_auto_start: false,
+                preshutdown_timeout: DEFAULT_PRESHUTDOWN_TIMEOUT,
+                triggers: Vec::new(),
+                sid_type: SERVICE_SID_TYPE_NONE,
+                required_privileges: Vec::new(),
+            },
+        );
+        let handle = database.insert_handle(machine_key, Some(key), desired_access);
+        self.notify_changed();
+        Ok((handle, tag_id))
+    }
+
+    fn enum_services_status_ex(
+        &self,
+        scm: ScHandle,
+        service_type: u32,
+        service_state: u32,
+        group_name: Option<&str>,
+    ) -> Result<Vec<RawServiceEntry>, u32> {
+        let mut database = self.lock();
+        let machine = database.manager(scm, SC_MANAGER_ENUMERATE_SERVICE)?;
+        if service_type & (SERVICE_DRIVER | SERVICE_WIN32) == 0
+            || !(SERVICE_ACTIVE..=SERVICE_STATE_ALL).contains(&service_state)
+        {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+
+        Ok(database
+            .machine_mut(&machine)?
+            .services
+            .values()
+            .filter(|service| service.config.service_type & service_type != 0)
+            .filter(|service| service.matches_state(service_state))
+            .filter(|service| {
+                group_name.map_or(true, |group| {
+                    service_key(&service.config.load_order_group) == service_key(group)
+                })
+            })
+            .map(FakeService::entry)
+            .collect())
+    }
+
+    fn enum_dependent_services(
+        &self,
+        service: ScHandle,
+        service_state: u32,
+    ) -> Result<Vec<RawServiceEntry>, u32> {
+        let mut database = self.lock();
+        let (machine, key) = database.service_machine(service, SERVICE_ENUMERATE_DEPENDENTS)?;
+        if !(SERVICE_ACTIVE..=SERVICE_STATE_ALL).contains(&service_state) {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+
+        let mut dependents = Vec::new();
+        machine.collect_dependents(&key, &mut vec![key.clone()], &mut dependents);
+        Ok(dependents
+            .iter()
+            .filter_map(|dependent| machine.services.get(dependent))
+            .filter(|service| service.matches_state(service_state))
+            .map(FakeService::entry)
+            .collect())
+    }
+
+    fn adjust_privilege(&self, privilege: &str, enable: bool) -> Result<bool, u32> {
+        let privilege: Privilege = privilege.parse().map_err(|_| ERROR_NO_SUCH_PRIVILEGE)?;
+        let mut database = self.lock();
+        let enabled = database
+            .privileges
+            .get_mut(&privilege)
+            .ok_or(ERROR_NOT_ALL_ASSIGNED)?;
+        Ok(std::mem::replace(enabled, enable))
+    }
+
+    fn notify_service_status_change(
+        &self,
+        handle: ScHandle,
+        _registration: &mut NotifyRegistration,
+        notify_mask: u32,
+        timeout: Duration,
+    ) -> Result<Option<RawServiceNotify>, u32> {
+        let deadline = Instant::now() + timeout;
+        let mut database = self.lock();
+        loop {
+            if let Some(notify) = database.notification(handle, notify_mask)? {
+                return Ok(Some(notify));
+            }
+            if database.advance_watched(handle) {
+                database.purge();
+                self.notify_changed();
+                continue;
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            database = self
+                .changed
+                .wait_timeout(database, remaining)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+    }
+
+    fn close_service_handle(&self, handle: ScHandle) {
+        let mut database = self.lock();
+        database.handles.remove(&handle);
+        database.purge();
+        self.notify_changed();
+    }
+
+    fn close_notify_handle(&self, handle: ScHandle, _registration: NotifyRegistration) {
+        self.close_service_handle(handle);
+    }
+
+    fn delete_service(&self, service: ScHandle) -> Result<(), u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, DELETE)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.marked_for_delete = true;
+        self.notify_changed();
+        Ok(())
+    }
+
+    fn start_service(&self, service: ScHandle, arguments: &[String]) -> Result<(), u32> {
+        if arguments.iter().any(|argument| argument.contains('\0')) {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        let mut database = self.lock();
+        let (machine, key) = database.service_machine(service, SERVICE_START)?;
+        {
+            let service = machine.services.get(&key).ok_or(ERROR_INVALID_HANDLE)?;
+            if service.marked_for_delete {
+                return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+            }
+            if service.config.start_type == SERVICE_DISABLED {
+                return Err(ERROR_SERVICE_DISABLED);
+            }
+            if service.status.current_state != SERVICE_STOPPED {
+                return Err(ERROR_SERVICE_ALREADY_RUNNING);
+            }
+        }
+
+        machine.start_dependencies(&key, &mut vec![key.clone()])?;
+
+        let process_id = machine.allocate_process_id(&key);
+        let service = machine.services.get_mut(&key).ok_or(ERROR_INVALID_HANDLE)?;
+        service.status.process_id = process_id;
+        service.status.win32_exit_code = 0;
+        service.status.service_specific_exit_code = 0;
+        service.start_arguments = arguments.to_vec();
+        service.begin_transition(SERVICE_START_PENDING, SERVICE_RUNNING);
+        self.notify_changed();
+        Ok(())
+    }
+
+    fn terminate_process(&self, process_id: u32, _exit_code: u32) -> Result<(), u32> {
+        let mut database = self.lock();
+        let services: Vec<_> = database
+            .local_mut()
+            .services
+            .values_mut()
+            .filter(|service| process_id != 0 && service.status.process_id == process_id)
+            .collect();
+        if services.is_empty() {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+
+        // The service control manager reports every service of a process that exits without
+        // stopping as aborted, whatever its exit code.
+        for service in services {
+            service.pending = None;
+            service.status.current_state = SERVICE_STOPPED;
+            service.status.win32_exit_code = ERROR_PROCESS_ABORTED;
+            service.status.controls_accepted = 0;
+            service.status.check_point = 0;
+            service.status.wait_hint = 0;
+            service.status.process_id = 0;
+        }
+        database.purge();
+        self.notify_changed();
+        Ok(())
+    }
+
+    fn control_service(&self, service: ScHandle, control: u32) -> Result<RawServiceStatus, u32> {
+        let (required_access, accept_flag) = match control {
+            SERVICE_CONTROL_STOP => (SERVICE_STOP, SERVICE_ACCEPT_STOP),
+            SERVICE_CONTROL_PAUSE | SERVICE_CONTROL_CONTINUE => {
+                (SERVICE_PAUSE_CONTINUE, SERVICE_ACCEPT_PAUSE_CONTINUE)
+            }
+            SERVICE_CONTROL_INTERROGATE => (SERVICE_INTERROGATE, 0),
+            SERVICE_CONTROL_PARAMCHANGE => (SERVICE_PAUSE_CONTINUE, SERVICE_ACCEPT_PARAMCHANGE),
+            SERVICE_CONTROL_NETBINDADD
+            | SERVICE_CONTROL_NETBINDREMOVE
+            | SERVICE_CONTROL_NETBINDENABLE
+            | SERVICE_CONTROL_NETBINDDISABLE => {
+                (SERVICE_PAUSE_CONTINUE, SERVICE_ACCEPT_NETBINDCHANGE)
+            }
+            128..=255 => (SERVICE_USER_DEFINED_CONTROL, 0),
+            _ => return Err(ERROR_INVALID_PARAMETER),
+        };
+
+        let mut database = self.lock();
+        let (machine, key) = database.service_machine(service, required_access)?;
+        let has_active_dependents = machine.has_active_dependents(&key);
+        let service = machine.services.get_mut(&key).ok_or(ERROR_INVALID_HANDLE)?;
+
+        match service.status.current_state {
+            SERVICE_STOPPED => return Err(ERROR_SERVICE_NOT_ACTIVE),
+            SERVICE_START_PENDING
+            | SERVICE_STOP_PENDING
+            | SERVICE_PAUSE_PENDING
+            | SERVICE_CONTINUE_PENDING => return Err(ERROR_SERVICE_CANNOT_ACCEPT_CTRL),
+            _ => {}
+        }
+        if accept_flag != 0 && service.status.controls_accepted & accept_flag == 0 {
+            return Err(ERROR_INVALID_SERVICE_CONTROL);
+        }
+
+        match (control, service.status.current_state) {
+            (SERVICE_CONTROL_STOP, _) => {
+                if has_active_dependents {
+                    return Err(ERROR_DEPENDENT_SERVICES_RUNNING);
+                }
+                service.begin_transition(SERVICE_STOP_PENDING, SERVICE_STOPPED);
+            }
+            (SERVICE_CONTROL_PAUSE, SERVICE_RUNNING) => {
+                service.begin_transition(SERVICE_PAUSE_PENDING, SERVICE_PAUSED);
+            }
+            (SERVICE_CONTROL_CONTINUE, SERVICE_PAUSED) => {
+                service.begin_transition(SERVICE_CONTINUE_PENDING, SERVICE_RUNNING);
+            }
+            _ => {}
+        }
+        let status = service.status();
+        database.purge();
+        self.notify_changed();
+        Ok(status)
+    }
+
+    fn control_service_with_reason(
+        &self,
+        service: ScHandle,
+        control: u32,
+        reason: u32,
+        comment: Option<&str>,
+    ) -> Result<RawServiceStatus, u32> {
+        if !valid_stop_reason(reason)
+            || comment.is_some_and(|comment| {
+                comment.contains('\0')
+                    || comment.encode_utf16().count() >= MAX_SERVICE_STOP_COMMENT_LEN
+            })
+        {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+
+        let status = self.control_service(service, control)?;
+        let mut database = self.lock();
+        // A stopped service marked for deletion is already gone from the database.
+        if let Ok(service) = database.service_mut(service, 0) {
+            service.stop_reason = Some((reason, comment.map(str::to_string)));
+        }
+        Ok(status)
+    }
+
+    fn query_service_status(&self, service: ScHandle) -> Result<RawServiceStatus, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_STATUS)?;
+        service.advance();
+        let status = service.status();
+        database.purge();
+        self.notify_changed();
+        Ok(status)
+    }
+
+    fn query_service_config(&self, service: ScHandle) -> Result<RawServiceConfig, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.config.clone())
+    }
+
+    fn query_service_description(&self, service: ScHandle) -> Result<String, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.description.clone())
+    }
+
+    fn change_service_description(&self, service: ScHandle, description: &str) -> Result<(), u32> {
+        if description.contains('\0') {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_CHANGE_CONFIG)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.description = description.to_string();
+        Ok(())
+    }
+
+    fn query_service_failure_actions(&self, service: ScHandle) -> Result<RawFailureActions, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.failure_actions.clone())
+    }
+
+    fn change_service_failure_actions(
+        &self,
+        service: ScHandle,
+        failure_actions: &RawFailureActions,
+    ) -> Result<(), u32> {
+        if failure_actions.reboot_message.contains('\0')
+            || failure_actions.command.contains('\0')
+            || failure_actions
+                .actions
+                .iter()
+                .any(|&(action_type, _)| action_type > SC_ACTION_RUN_COMMAND)
+        {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        let has_action = |wanted: u32| {
+            failure_actions
+                .actions
+                .iter()
+                .any(|&(action_type, _)| action_type == wanted)
+        };
+
+        let mut database = self.lock();
+        // A reboot action needs the shutdown privilege enabled, and a restart action a handle
+        // that may start the service.
+        if has_action(SC_ACTION_REBOOT)
+            && !database
+                .privileges
+                .get(&Privilege::Shutdown)
+                .copied()
+                .unwrap_or(false)
+        {
+            return Err(ERROR_ACCESS_DENIED);
+        }
+        let required_access = if has_action(SC_ACTION_RESTART) {
+            SERVICE_CHANGE_CONFIG | SERVICE_START
+        } else {
+            SERVICE_CHANGE_CONFIG
+        };
+        let service = database.service_mut(service, required_access)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.failure_actions = failure_actions.clone();
+        Ok(())
+    }
+
+    fn query_service_failure_actions_flag(&self, service: ScHandle) -> Result<bool, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.failure_actions_on_non_crash_failures)
+    }
+
+    fn change_service_failure_actions_flag(
+        &self,
+        service: ScHandle,
+        enabled: bool,
+    ) -> Result<(), u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_CHANGE_CONFIG)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.failure_actions_on_non_crash_failures = enabled;
+        Ok(())
+    }
+
+    fn query_service_delayed_auto_start(&self, service: ScHandle) -> Result<bool, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.delayed_auto_start)
+    }
+
+    fn change_service_delayed_auto_start(
+        &self,
+        service: ScHandle,
+        delayed: bool,
+    ) -> Result<(), u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_CHANGE_CONFIG)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.delayed_auto_start = delayed;
+        Ok(())
+    }
+
+    fn query_service_preshutdown_timeout(&self, service: ScHandle) -> Result<u32, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.preshutdown_timeout)
+    }
+
+    fn change_service_preshutdown_timeout(
+        &self,
+        service: ScHandle,
+        timeout: u32,
+    ) -> Result<(), u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_CHANGE_CONFIG)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.preshutdown_timeout = timeout;
+        Ok(())
+    }
+
+    fn query_service_triggers(&self, service: ScHandle) -> Result<Vec<RawServiceTrigger>, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.triggers.clone())
+    }
+
+    fn change_service_triggers(
+        &self,
+        service: ScHandle,
+        triggers: &[RawServiceTrigger],
+    ) -> Result<(), u32> {
+        let valid = triggers.iter().all(|trigger| {
+            matches!(
+                trigger.action,
+                SERVICE_TRIGGER_ACTION_SERVICE_START | SERVICE_TRIGGER_ACTION_SERVICE_STOP
+            ) && matches!(
+                trigger.trigger_type,
+                SERVICE_TRIGGER_TYPE_DEVICE_INTERFACE_ARRIVAL
+                    ..=SERVICE_TRIGGER_TYPE_CUSTOM_SYSTEM_STATE_CHANGE
+                        | SERVICE_TRIGGER_TYPE_CUSTOM
+                        | SERVICE_TRIGGER_TYPE_AGGREGATE
+            ) && trigger.data_items.iter().all(|(data_type, _)| {
+                (SERVICE_TRIGGER_DATA_TYPE_BINARY..=SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ALL)
+                    .contains(data_type)
+            })
+        });
+        if !valid {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_CHANGE_CONFIG)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.triggers = triggers.to_vec();
+        Ok(())
+    }
+
+    fn query_service_sid_type(&self, service: ScHandle) -> Result<u32, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.sid_type)
+    }
+
+    fn change_service_sid_type(&self, service: ScHandle, sid_type: u32) -> Result<(), u32> {
+        if !matches!(
+            sid_type,
+            SERVICE_SID_TYPE_NONE | SERVICE_SID_TYPE_UNRESTRICTED | SERVICE_SID_TYPE_RESTRICTED
+        ) {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_CHANGE_CONFIG)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.sid_type = sid_type;
+        Ok(())
+    }
+
+    fn query_service_required_privileges(&self, service: ScHandle) -> Result<Vec<String>, u32> {
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_QUERY_CONFIG)?;
+        Ok(service.required_privileges.clone())
+    }
+
+    fn change_service_required_privileges(
+        &self,
+        service: ScHandle,
+        privileges: &[String],
+    ) -> Result<(), u32> {
+        if privileges
+            .iter()
+            .any(|privilege| privilege.parse::<Privilege>().is_err())
+        {
+            return Err(ERROR_NO_SUCH_PRIVILEGE);
+        }
+        let mut database = self.lock();
+        let service = database.service_mut(service, SERVICE_CHANGE_CONFIG)?;
+        if service.marked_for_delete {
+            return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+        }
+        service.required_privileges = privileges.to_vec();
+        Ok(())
+    }
+
+    fn change_service_config(
+        &self,
+        service: ScHandle,
+        change: &RawConfigChange,
+    ) -> Result<(), u32> {
+        let mut database = self.lock();
+        let (machine, key) = database.service_machine(service, SERVICE_CHANGE_CONFIG)?;
+        let current = {
+            let service = machine.services.get(&key).ok_or(ERROR_INVALID_HANDLE)?;
+            if service.marked_for_delete {
+                return Err(ERROR_SERVICE_MARKED_FOR_DELETE);
+            }
+            service.config.clone()
+        };
+
+        let pick = |value: u32, current: u32| {
+            if value == SERVICE_NO_CHANGE {
+                current
+            } else {
+                value
+            }
+        };
+        let mut config = RawServiceConfig {
+            service_type: pick(change.service_type, current.service_type),
+            start_type: pick(change.start_type, current.start_type),
+            error_control: pick(change.error_control, current.error_control),
+            ..current
+        };
+        validate_types(config.service_type, config.start_type, config.error_control)?;
+
+        if let Some(display_name) = &change.display_name {
+            if machine.display_name_taken(display_name, Some(&key)) {
+                return Err(ERROR_DUPLICATE_SERVICE_NAME);
+            }
+            config.display_name = display_name.clone();
+        }
+        if let Some(dependencies) = &change.dependencies {
+            if machine.depends_on(dependencies, &key, &mut Vec::new()) {
+                return Err(ERROR_CIRCULAR_DEPENDENCY);
+            }
+            config.dependencies = dependencies.clone();
+        }
+        if let Some(binary_path_name) = &change.binary_path_name {
+            config.binary_path_name = binary_path_name.clone();
+        }
+        if let Some(load_order_group) = &change.load_order_group {
+            config.load_order_group = load_order_group.clone();
+        }
+        if let Some(service_start_name) = &change.service_start_name {
+            if !service_start_name.is_empty() {
+                service_start_name
+                    .parse::<ServiceAccount>()
+                    .map_err(|_| ERROR_INVALID_SERVICE_ACCOUNT)?;
+            }
+            config.service_start_name = service_start_name.clone();
+        }
+
+        let service = machine.services.get_mut(&key).ok_or(ERROR_INVALID_HANDLE)?;
+        service.status.service_type = config.service_type;
+        service.config = config;
+        self.notify_changed();
+        Ok(())
+    }
+}
@@ -1,5 +1,16 @@
+pub mod access;
+pub mod account;
+pub mod backend;
+#[cfg(windows)]
 pub mod common;
+pub mod consts;
 pub mod error;
+pub mod fake;
+pub mod notify;
+pub mod privilege;
 pub mod service;
 pub mod service_manager;
 pub mod test;
+pub mod trigger;
+#[cfg(windows)]
+pub mod win32;
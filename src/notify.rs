@@ -0,0 +1,316 @@
+//! This module provides subscriptions to service status changes, built on `NotifyServiceStatusChangeW`.
+//! Each subscription runs on a thread of its own that holds its own handle and sleeps until the
+//! service control manager calls back, so events arrive within milliseconds without polling.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{
+    access::flags,
+    backend::{NotifyRegistration, RawServiceNotify, ScHandle},
+    consts::{
+        ERROR_SERVICE_MARKED_FOR_DELETE, ERROR_SERVICE_NOTIFY_CLIENT_LAGGING,
+        SC_MANAGER_ENUMERATE_SERVICE, SERVICE_NOTIFY_CONTINUE_PENDING, SERVICE_NOTIFY_CREATED,
+        SERVICE_NOTIFY_DELETED, SERVICE_NOTIFY_DELETE_PENDING, SERVICE_NOTIFY_PAUSED,
+        SERVICE_NOTIFY_PAUSE_PENDING, SERVICE_NOTIFY_RUNNING, SERVICE_NOTIFY_START_PENDING,
+        SERVICE_NOTIFY_STOPPED, SERVICE_NOTIFY_STOP_PENDING, SERVICE_QUERY_STATUS,
+    },
+    error::QueryServiceError,
+    service::ServiceState,
+    service_manager::{on_machine, ScmConnection},
+};
+
+/// How long the subscription thread waits for a notification before checking whether the
+/// subscription was dropped.
+#[doc(hidden)]
+const NOTIFY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+flags! {
+    /// Events a subscription reports, passed to `NotifyServiceStatusChangeW`.
+    ServiceNotify {
+        STOPPED = SERVICE_NOTIFY_STOPPED,
+        START_PENDING = SERVICE_NOTIFY_START_PENDING,
+        STOP_PENDING = SERVICE_NOTIFY_STOP_PENDING,
+        RUNNING = SERVICE_NOTIFY_RUNNING,
+        CONTINUE_PENDING = SERVICE_NOTIFY_CONTINUE_PENDING,
+        PAUSE_PENDING = SERVICE_NOTIFY_PAUSE_PENDING,
+        PAUSED = SERVICE_NOTIFY_PAUSED,
+        /// A service was created, on a service control manager.
+        CREATED = SERVICE_NOTIFY_CREATED,
+        /// A service was deleted, on a service control manager.
+        DELETED = SERVICE_NOTIFY_DELETED,
+        /// The service was marked for deletion.
+        DELETE_PENDING = SERVICE_NOTIFY_DELETE_PENDING,
+    }
+}
+
+impl ServiceNotify {
+    /// Every pending state.
+    pub const PENDING: Self = Self(
+        SERVICE_NOTIFY_START_PENDING
+            | SERVICE_NOTIFY_STOP_PENDING
+            | SERVICE_NOTIFY_CONTINUE_PENDING
+            | SERVICE_NOTIFY_PAUSE_PENDING,
+    );
+
+    /// Every state a service can enter.
+    pub const STATE_CHANGES: Self = Self(
+        SERVICE_NOTIFY_STOPPED | SERVICE_NOTIFY_RUNNING | SERVICE_NOTIFY_PAUSED | Self::PENDING.0,
+    );
+
+    /// The events of a [`ServiceHandle`](crate::service::ServiceHandle) subscription.
+    pub const SERVICE_EVENTS: Self = Self(Self::STATE_CHANGES.0 | SERVICE_NOTIFY_DELETE_PENDING);
+
+    /// The events of a [`ServiceManager`](crate::service_manager::ServiceManager) subscription.
+    pub const MANAGER_EVENTS: Self = Self(SERVICE_NOTIFY_CREATED | SERVICE_NOTIFY_DELETED);
+
+    /// Returns the event reported when a service enters `state`.
+    pub fn from_state(state: &ServiceState) -> Self {
+        // The SERVICE_NOTIFY_* state bits follow the order of the SERVICE_* states.
+        Self(1 << (state.clone() as u32 - 1))
+    }
+}
+
+/// A change reported by a [`ServiceEvents`] subscription.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServiceEvent {
+    /// The service entered a state.
+    StateChanged(ServiceState),
+    /// The service was marked for deletion. It is the last event of the subscription.
+    DeletePending,
+    /// A service with this name was created.
+    Created(String),
+    /// A service with this name was deleted.
+    Deleted(String),
+}
+
+/// A stream of [`ServiceEvent`]s, returned by [`ServiceHandle::subscribe`](crate::service::ServiceHandle::subscribe)
+/// and [`ServiceManager::subscribe`](crate::service_manager::ServiceManager::subscribe).
+///
+/// Iterating blocks until the next event and ends when the subscription does, such as after a
+/// failure, which is reported as a last `Err` item. Dropping the subscription stops its thread.
+#[derive(Debug)]
+pub struct ServiceEvents {
+    receiver: Receiver<Result<ServiceEvent, QueryServiceError>>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+/// The state a subscription thread works with.
+#[doc(hidden)]
+struct Subscription {
+    connection: Arc<ScmConnection>,
+    /// The service being watched, or `None` for the service control manager itself.
+    service_name: Option<String>,
+    mask: ServiceNotify,
+    handle: ScHandle,
+    sender: Sender<Result<ServiceEvent, QueryServiceError>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ServiceEvents {
+    /// Opens a handle of its own to the service named `service_name`, or to the service control
+    /// manager if it is `None`, and starts reporting the events in `mask` on a new thread.
+    #[doc(hidden)]
+    pub(crate) fn subscribe<E: From<(u32, String)>>(
+        connection: Arc<ScmConnection>,
+        service_name: Option<&str>,
+        mask: ServiceNotify,
+    ) -> Result<Self, E> {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut subscription = Subscription {
+            connection,
+            service_name: service_name.map(str::to_string),
+            mask,
+            handle: 0,
+            sender,
+            stop: stop.clone(),
+        };
+        subscription.handle = subscription
+            .open()
+            .map_err(|err| E::from((err, subscription.context("can't open a handle to watch"))))?;
+
+        Ok(Self {
+            receiver,
+            stop,
+            worker: Some(std::thread::spawn(move || subscription.run())),
+        })
+    }
+
+    /// Waits up to `timeout` for the next event, and returns `None` if none arrived in time or
+    /// the subscription ended.
+    pub fn recv_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Option<Result<ServiceEvent, QueryServiceError>> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for ServiceEvents {
+    type Item = Result<ServiceEvent, QueryServiceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for ServiceEvents {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Subscription {
+    #[doc(hidden)]
+    fn context(&self, message: &str) -> String {
+        let context = match &self.service_name {
+            Some(service_name) => format!("[subscribe] {message} {service_name:?}"),
+            None => format!("[subscribe] {message} the service control manager"),
+        };
+        on_machine(context, self.connection.machine_name.as_deref())
+    }
+
+    #[doc(hidden)]
+    fn open(&self) -> Result<ScHandle, u32> {
+        let backend = &self.connection.backend;
+        match &self.service_name {
+            Some(service_name) => {
+                backend.open_service(self.connection.handle, service_name, SERVICE_QUERY_STATUS)
+            }
+            None => backend.open_sc_manager(
+                self.connection.machine_name.as_deref(),
+                self.connection.database_name.as_deref(),
+                SC_MANAGER_ENUMERATE_SERVICE,
+            ),
+        }
+    }
+
+    /// Turns a notification into the events the subscriber asked for, remembering the state
+    /// of the service in `state`. Returns `false` once the subscription is over.
+    #[doc(hidden)]
+    fn report(&self, notify: RawServiceNotify, state: &mut Option<ServiceState>) -> bool {
+        let events = if self.service_name.is_none() {
+            notify
+                .service_names
+                .into_iter()
+                .filter_map(|name| {
+                    if let Some(name) = name.strip_prefix('/') {
+                        Some(ServiceEvent::Created(name.to_string()))
+                            .filter(|_| self.mask.contains(ServiceNotify::CREATED))
+                    } else {
+                        name.strip_prefix('\\')
+                            .map(|name| ServiceEvent::Deleted(name.to_string()))
+                            .filter(|_| self.mask.contains(ServiceNotify::DELETED))
+                    }
+                })
+                .map(Ok)
+                .collect()
+        } else if notify.notification_triggered & SERVICE_NOTIFY_DELETE_PENDING != 0 {
+            vec![Ok(ServiceEvent::DeletePending)]
+        } else {
+            match ServiceState::try_from(notify.status.current_state) {
+                Ok(current) => {
+                    *state = Some(current.clone());
+                    if self.mask.contains(ServiceNotify::from_state(&current)) {
+                        vec![Ok(ServiceEvent::StateChanged(current))]
+                    } else {
+                        Vec::new()
+                    }
+                }
+                Err(err) => vec![Err(err)],
+            }
+        };
+
+        for event in events {
+            let done = matches!(event, Err(_) | Ok(ServiceEvent::DeletePending));
+            if self.sender.send(event).is_err() || done {
+                return false;
+            }
+        }
+        true
+    }
+
+    #[doc(hidden)]
+    fn run(mut self) {
+        let backend = self.connection.backend.clone();
+        let mut state = None;
+        // Owned by this thread, which Windows delivers the notifications to.
+        let mut registration = NotifyRegistration::default();
+
+        while !self.stop.load(Ordering::Relaxed) {
+            // A service handle is notified right away while the service is in a requested
+            // state, so every state but the current one is watched and filtered afterwards.
+            let notify_mask = match (&self.service_name, &state) {
+                (None, _) => self.mask,
+                (Some(_), None) => ServiceNotify::STATE_CHANGES,
+                (Some(_), Some(state)) => {
+                    ServiceNotify::STATE_CHANGES.difference(ServiceNotify::from_state(state))
+                }
+            } | (self.mask & ServiceNotify::DELETE_PENDING);
+
+            match backend.notify_service_status_change(
+                self.handle,
+                &mut registration,
+                notify_mask.bits(),
+                NOTIFY_POLL_INTERVAL,
+            ) {
+                Ok(None) => {}
+                Ok(Some(notify)) => {
+                    if !self.report(notify, &mut state) {
+                        break;
+                    }
+                }
+                // The service was marked for deletion before the notification was registered,
+                // so its end is reported here rather than through a notification.
+                Err(ERROR_SERVICE_MARKED_FOR_DELETE) => {
+                    let _ =
+                        self.sender
+                            .send(if self.mask.contains(ServiceNotify::DELETE_PENDING) {
+                                Ok(ServiceEvent::DeletePending)
+                            } else {
+                                Err(QueryServiceError::from((
+                                    ERROR_SERVICE_MARKED_FOR_DELETE,
+                                    self.context("stopped watching the deleted service"),
+                                )))
+                            });
+                    break;
+                }
+                // The SCM dropped notifications the thread didn't pick up in time, and only
+                // notifies a new handle again.
+                Err(ERROR_SERVICE_NOTIFY_CLIENT_LAGGING) => {
+                    backend.close_notify_handle(self.handle, std::mem::take(&mut registration));
+                    match self.open() {
+                        Ok(handle) => self.handle = handle,
+                        Err(err) => {
+                            let _ = self.sender.send(Err(QueryServiceError::from((
+                                err,
+                                self.context("can't reopen a handle to watch"),
+                            ))));
+                            return;
+                        }
+                    }
+                }
+                Err(err) => {
+                    let _ = self.sender.send(Err(QueryServiceError::from((
+                        err,
+                        self.context("NotifyServiceStatusChangeW failed for"),
+                    ))));
+                    break;
+                }
+            }
+        }
+        backend.close_notify_handle(self.handle, registration);
+    }
+}
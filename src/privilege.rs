@@ -0,0 +1,174 @@
+//! This module provides typed Windows privileges and a guard that enables them in the token of
+//! the current process for as long as it lives, restoring their previous state on drop.
+
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use crate::{
+    backend::ScmBackend,
+    consts::{ERROR_NOT_ALL_ASSIGNED, ERROR_NO_SUCH_PRIVILEGE},
+    error::PrivilegeError,
+};
+
+#[doc(hidden)]
+macro_rules! privileges {
+    ($($(#[$meta:meta])* $variant:ident => $name:literal,)*) => {
+        /// A privilege that can be held in an access token, named after its `Se*Privilege` constant.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum Privilege {
+            $($(#[$meta])* $variant,)*
+        }
+
+        impl Privilege {
+            /// Every privilege, in alphabetical order of their names.
+            pub const ALL: &'static [Privilege] = &[$(Privilege::$variant,)*];
+
+            /// Returns the name `LookupPrivilegeValueW` knows the privilege by, such as `SeLoadDriverPrivilege`.
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Privilege::$variant => $name,)*
+                }
+            }
+        }
+    };
+}
+
+privileges! {
+    AssignPrimaryToken => "SeAssignPrimaryTokenPrivilege",
+    Audit => "SeAuditPrivilege",
+    Backup => "SeBackupPrivilege",
+    ChangeNotify => "SeChangeNotifyPrivilege",
+    CreateGlobal => "SeCreateGlobalPrivilege",
+    CreatePagefile => "SeCreatePagefilePrivilege",
+    CreatePermanent => "SeCreatePermanentPrivilege",
+    CreateSymbolicLink => "SeCreateSymbolicLinkPrivilege",
+    CreateToken => "SeCreateTokenPrivilege",
+    Debug => "SeDebugPrivilege",
+    DelegateSessionUserImpersonate => "SeDelegateSessionUserImpersonatePrivilege",
+    EnableDelegation => "SeEnableDelegationPrivilege",
+    Impersonate => "SeImpersonatePrivilege",
+    IncreaseBasePriority => "SeIncreaseBasePriorityPrivilege",
+    IncreaseQuota => "SeIncreaseQuotaPrivilege",
+    IncreaseWorkingSet => "SeIncreaseWorkingSetPrivilege",
+    LoadDriver => "SeLoadDriverPrivilege",
+    LockMemory => "SeLockMemoryPrivilege",
+    MachineAccount => "SeMachineAccountPrivilege",
+    ManageVolume => "SeManageVolumePrivilege",
+    ProfileSingleProcess => "SeProfileSingleProcessPrivilege",
+    Relabel => "SeRelabelPrivilege",
+    RemoteShutdown => "SeRemoteShutdownPrivilege",
+    Restore => "SeRestorePrivilege",
+    Security => "SeSecurityPrivilege",
+    Shutdown => "SeShutdownPrivilege",
+    SyncAgent => "SeSyncAgentPrivilege",
+    SystemEnvironment => "SeSystemEnvironmentPrivilege",
+    SystemProfile => "SeSystemProfilePrivilege",
+    Systemtime => "SeSystemtimePrivilege",
+    TakeOwnership => "SeTakeOwnershipPrivilege",
+    Tcb => "SeTcbPrivilege",
+    TimeZone => "SeTimeZonePrivilege",
+    TrustedCredManAccess => "SeTrustedCredManAccessPrivilege",
+    Undock => "SeUndockPrivilege",
+    UnsolicitedInput => "SeUnsolicitedInputPrivilege",
+}
+
+impl Display for Privilege {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Parses a privilege name, ignoring case like `LookupPrivilegeValueW` does.
+impl FromStr for Privilege {
+    type Err = PrivilegeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|privilege| privilege.name().eq_ignore_ascii_case(value))
+            .ok_or_else(|| {
+                PrivilegeError::NoSuchPrivilege(
+                    ERROR_NO_SUCH_PRIVILEGE,
+                    format!("[Privilege::from_str] unknown privilege {value:?}"),
+                )
+            })
+    }
+}
+
+/// Keeps a set of privileges enabled in the token of the current process.
+///
+/// Only the privileges the guard actually turned on are turned back off when it is dropped,
+/// so privileges that were already enabled stay enabled.
+#[derive(Debug)]
+pub struct PrivilegeGuard {
+    backend: Arc<dyn ScmBackend>,
+    enabled: Vec<Privilege>,
+}
+
+impl Drop for PrivilegeGuard {
+    fn drop(&mut self) {
+        for privilege in self.enabled.iter().rev() {
+            let _ = self.backend.adjust_privilege(privilege.name(), false);
+        }
+    }
+}
+
+impl PrivilegeGuard {
+    /// Enables `privileges` in the token of the current process.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`PrivilegeError::NotAllAssigned`] naming the privileges the
+    /// token doesn't hold, after restoring the ones it did enable.
+    #[cfg(windows)]
+    pub fn enable(privileges: &[Privilege]) -> Result<Self, PrivilegeError> {
+        Self::with_backend(Arc::new(crate::win32::Win32Backend), privileges)
+    }
+
+    /// Enables `privileges` through `backend`, such as a [`FakeScm`](crate::fake::FakeScm) in tests.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`PrivilegeError::NotAllAssigned`] naming the privileges the
+    /// token doesn't hold, after restoring the ones it did enable.
+    pub fn with_backend(
+        backend: Arc<dyn ScmBackend>,
+        privileges: &[Privilege],
+    ) -> Result<Self, PrivilegeError> {
+        let mut guard = Self {
+            backend,
+            enabled: Vec::new(),
+        };
+        let mut not_granted = Vec::new();
+
+        for &privilege in privileges {
+            match guard.backend.adjust_privilege(privilege.name(), true) {
+                Ok(true) => {}
+                Ok(false) => guard.enabled.push(privilege),
+                Err(ERROR_NOT_ALL_ASSIGNED) => not_granted.push(privilege.name()),
+                Err(err) => {
+                    return Err(PrivilegeError::from((
+                        err,
+                        format!("[PrivilegeGuard::enable] can't enable {privilege}"),
+                    )))
+                }
+            }
+        }
+
+        if !not_granted.is_empty() {
+            return Err(PrivilegeError::NotAllAssigned(
+                ERROR_NOT_ALL_ASSIGNED,
+                format!(
+                    "[PrivilegeGuard::enable] not held by the token: {}",
+                    not_granted.join(", ")
+                ),
+            ));
+        }
+        Ok(guard)
+    }
+
+    /// Returns the privileges this guard enabled and will disable again when dropped.
+    pub fn enabled(&self) -> &[Privilege] {
+        &self.enabled
+    }
+}
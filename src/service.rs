@@ -2,33 +2,93 @@
 //! It includes structs and enums for handling service configurations and states,
 //! and functions for starting, stopping, pausing, and querying services.
 
-use std::fmt::Display;
-use widestring::U16CString;
-use windows_sys::Win32::{
-    Foundation::{ERROR_INSUFFICIENT_BUFFER, FALSE},
-    Security::SC_HANDLE,
-    System::Services::{
-        ChangeServiceConfigW, CloseServiceHandle, ControlService, DeleteService,
-        QueryServiceConfigW, QueryServiceStatus, StartServiceW, QUERY_SERVICE_CONFIGW,
-        SERVICE_ADAPTER, SERVICE_AUTO_START, SERVICE_BOOT_START, SERVICE_CONTINUE_PENDING,
-        SERVICE_CONTROL_PAUSE, SERVICE_CONTROL_STOP, SERVICE_DEMAND_START, SERVICE_DISABLED,
-        SERVICE_FILE_SYSTEM_DRIVER, SERVICE_KERNEL_DRIVER, SERVICE_PAUSED, SERVICE_PAUSE_PENDING,
-        SERVICE_RECOGNIZER_DRIVER, SERVICE_RUNNING, SERVICE_START_PENDING, SERVICE_STATUS,
-        SERVICE_STOPPED, SERVICE_STOP_PENDING, SERVICE_SYSTEM_START, SERVICE_WIN32_OWN_PROCESS,
-        SERVICE_WIN32_SHARE_PROCESS,
-    },
+use std::{
+    fmt::Display,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    common::get_last_error,
-    error::{ControlServiceError, DeleteServiceError, QueryServiceError, UpdateServiceError},
-    service_manager::ServiceConfig,
+    access::{flags, ServiceAccess},
+    account::ServiceAccount,
+    backend::{
+        RawConfigChange, RawFailureActions, RawServiceConfig, RawServiceEntry, RawServiceStatus,
+        RawServiceTrigger, ScHandle, ScmBackend, SC_GROUP_IDENTIFIER, SERVICE_NO_CHANGE,
+    },
+    consts::{
+        ERROR_ACCESS_DENIED, ERROR_INVALID_PARAMETER, ERROR_INVALID_SERVICE_CONTROL,
+        ERROR_PRIVILEGE_NOT_HELD, ERROR_PROCESS_ABORTED, ERROR_SERVICE_CANNOT_ACCEPT_CTRL,
+        ERROR_SERVICE_DOES_NOT_EXIST, ERROR_SERVICE_MARKED_FOR_DELETE,
+        ERROR_SERVICE_REQUEST_TIMEOUT, ERROR_SERVICE_SPECIFIC_ERROR, ERROR_TIMEOUT, INFINITE,
+        MAX_SERVICE_STOP_COMMENT_LEN, SC_ACTION_NONE, SC_ACTION_REBOOT, SC_ACTION_RESTART,
+        SC_ACTION_RUN_COMMAND, SERVICE_ACCEPT_HARDWAREPROFILECHANGE, SERVICE_ACCEPT_NETBINDCHANGE,
+        SERVICE_ACCEPT_PARAMCHANGE, SERVICE_ACCEPT_PAUSE_CONTINUE, SERVICE_ACCEPT_POWEREVENT,
+        SERVICE_ACCEPT_PRESHUTDOWN, SERVICE_ACCEPT_SESSIONCHANGE, SERVICE_ACCEPT_SHUTDOWN,
+        SERVICE_ACCEPT_STOP, SERVICE_ACCEPT_TIMECHANGE, SERVICE_ACCEPT_TRIGGEREVENT,
+        SERVICE_ACCEPT_USERMODEREBOOT, SERVICE_ACTIVE, SERVICE_ADAPTER, SERVICE_AUTO_START,
+        SERVICE_BOOT_START, SERVICE_CONTINUE_PENDING, SERVICE_CONTROL_CONTINUE,
+        SERVICE_CONTROL_INTERROGATE, SERVICE_CONTROL_NETBINDADD, SERVICE_CONTROL_NETBINDDISABLE,
+        SERVICE_CONTROL_NETBINDENABLE, SERVICE_CONTROL_NETBINDREMOVE, SERVICE_CONTROL_PARAMCHANGE,
+        SERVICE_CONTROL_PAUSE, SERVICE_CONTROL_STOP, SERVICE_DEMAND_START, SERVICE_DISABLED,
+        SERVICE_DRIVER, SERVICE_ERROR_CRITICAL, SERVICE_ERROR_IGNORE, SERVICE_ERROR_NORMAL,
+        SERVICE_ERROR_SEVERE, SERVICE_FILE_SYSTEM_DRIVER, SERVICE_INTERACTIVE_PROCESS,
+        SERVICE_KERNEL_DRIVER, SERVICE_PAUSED, SERVICE_PAUSE_PENDING, SERVICE_PKG_SERVICE,
+        SERVICE_RECOGNIZER_DRIVER, SERVICE_RUNNING, SERVICE_RUNS_IN_SYSTEM_PROCESS,
+        SERVICE_SID_TYPE_NONE, SERVICE_SID_TYPE_RESTRICTED, SERVICE_SID_TYPE_UNRESTRICTED,
+        SERVICE_START_PENDING, SERVICE_STOPPED, SERVICE_STOP_PENDING,
+        SERVICE_STOP_REASON_FLAG_PLANNED, SERVICE_STOP_REASON_FLAG_UNPLANNED,
+        SERVICE_STOP_REASON_MAJOR_APPLICATION, SERVICE_STOP_REASON_MAJOR_HARDWARE,
+        SERVICE_STOP_REASON_MAJOR_NONE, SERVICE_STOP_REASON_MAJOR_OPERATINGSYSTEM,
+        SERVICE_STOP_REASON_MAJOR_OTHER, SERVICE_STOP_REASON_MAJOR_SOFTWARE,
+        SERVICE_STOP_REASON_MINOR_DISK, SERVICE_STOP_REASON_MINOR_ENVIRONMENT,
+        SERVICE_STOP_REASON_MINOR_HARDWARE_DRIVER, SERVICE_STOP_REASON_MINOR_HUNG,
+        SERVICE_STOP_REASON_MINOR_INSTALLATION, SERVICE_STOP_REASON_MINOR_MAINTENANCE,
+        SERVICE_STOP_REASON_MINOR_MMC, SERVICE_STOP_REASON_MINOR_NETWORKCARD,
+        SERVICE_STOP_REASON_MINOR_NETWORK_CONNECTIVITY, SERVICE_STOP_REASON_MINOR_NONE,
+        SERVICE_STOP_REASON_MINOR_OTHER, SERVICE_STOP_REASON_MINOR_OTHERDRIVER,
+        SERVICE_STOP_REASON_MINOR_RECONFIG, SERVICE_STOP_REASON_MINOR_SECURITY,
+        SERVICE_STOP_REASON_MINOR_SECURITYFIX, SERVICE_STOP_REASON_MINOR_SECURITYFIX_UNINSTALL,
+        SERVICE_STOP_REASON_MINOR_SERVICEPACK, SERVICE_STOP_REASON_MINOR_SERVICEPACK_UNINSTALL,
+        SERVICE_STOP_REASON_MINOR_SOFTWARE_UPDATE,
+        SERVICE_STOP_REASON_MINOR_SOFTWARE_UPDATE_UNINSTALL, SERVICE_STOP_REASON_MINOR_UNSTABLE,
+        SERVICE_STOP_REASON_MINOR_UPGRADE, SERVICE_STOP_REASON_MINOR_WMI, SERVICE_SYSTEM_START,
+        SERVICE_USERSERVICE_INSTANCE, SERVICE_USER_SERVICE, SERVICE_WIN32,
+        SERVICE_WIN32_OWN_PROCESS, SERVICE_WIN32_SHARE_PROCESS,
+    },
+    error::{
+        ControlServiceError, DeleteServiceError, QueryServiceError, RestartError,
+        UpdateServiceError,
+    },
+    notify::{ServiceEvents, ServiceNotify},
+    privilege::{Privilege, PrivilegeGuard},
+    service_manager::{on_machine, ScmConnection, ServiceStateFilter},
+    trigger::{ServiceTrigger, TriggerDataItem, TriggerEvent},
 };
 
+/// Number of status queries in a row that may fail before a blocking control gives up.
+#[doc(hidden)]
+const MAX_QUERY_FAILURES: u32 = 3;
+
+/// Bounds of the interval between status queries while a blocking control waits.
+#[doc(hidden)]
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+#[doc(hidden)]
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Shortest time a pending service may keep the same checkpoint before a blocking control
+/// considers it stalled, since many services report no wait hint at all.
+#[doc(hidden)]
+const MIN_STALL_WINDOW: Duration = Duration::from_secs(1);
+
 /// Represents a handle to a Windows service.
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct ServiceHandle {
-    handle: Option<SC_HANDLE>,
+    backend: Arc<dyn ScmBackend>,
+    handle: Option<ScHandle>,
+    tag_id: Option<u32>,
+    manager: Option<Arc<ScmConnection>>,
+    service_name: Option<String>,
+    access: ServiceAccess,
 }
 
 /// Defines the error control levels for a Windows service.
@@ -54,6 +114,19 @@ pub enum ServiceStartType {
     SystemStart = 0x00000001,
 }
 
+/// How a service is started, as shown by services.msc: its start type, with auto-start
+/// services started some time after the others told apart as `AutoDelayed`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceStartMode {
+    Boot,
+    System,
+    Auto,
+    AutoDelayed,
+    #[default]
+    Demand,
+    Disabled,
+}
+
 /// Defines the types of a Windows service.
 #[repr(u32)]
 #[derive(Default, Clone, Copy, Debug, PartialEq)]
@@ -80,6 +153,498 @@ pub enum ServiceState {
     Paused,
 }
 
+/// A change of network binding, sent by [`ServiceHandle::netbind_change`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NetBindChange {
+    /// A new component to bind to is available.
+    Add = SERVICE_CONTROL_NETBINDADD,
+    /// A component the service is bound to was removed.
+    Remove = SERVICE_CONTROL_NETBINDREMOVE,
+    /// A previously disabled binding was enabled.
+    Enable = SERVICE_CONTROL_NETBINDENABLE,
+    /// A binding was disabled.
+    Disable = SERVICE_CONTROL_NETBINDDISABLE,
+}
+
+/// Whether a stop was planned, the kind of a [`StopReason`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReasonKind {
+    Planned = SERVICE_STOP_REASON_FLAG_PLANNED,
+    Unplanned = SERVICE_STOP_REASON_FLAG_UNPLANNED,
+}
+
+/// The broad cause of a stop, the major part of a [`StopReason`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReasonMajor {
+    Other = SERVICE_STOP_REASON_MAJOR_OTHER,
+    Hardware = SERVICE_STOP_REASON_MAJOR_HARDWARE,
+    OperatingSystem = SERVICE_STOP_REASON_MAJOR_OPERATINGSYSTEM,
+    Software = SERVICE_STOP_REASON_MAJOR_SOFTWARE,
+    Application = SERVICE_STOP_REASON_MAJOR_APPLICATION,
+    None = SERVICE_STOP_REASON_MAJOR_NONE,
+}
+
+/// The precise cause of a stop, the minor part of a [`StopReason`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReasonMinor {
+    Other = SERVICE_STOP_REASON_MINOR_OTHER,
+    Maintenance = SERVICE_STOP_REASON_MINOR_MAINTENANCE,
+    Installation = SERVICE_STOP_REASON_MINOR_INSTALLATION,
+    Upgrade = SERVICE_STOP_REASON_MINOR_UPGRADE,
+    Reconfig = SERVICE_STOP_REASON_MINOR_RECONFIG,
+    Hung = SERVICE_STOP_REASON_MINOR_HUNG,
+    Unstable = SERVICE_STOP_REASON_MINOR_UNSTABLE,
+    Disk = SERVICE_STOP_REASON_MINOR_DISK,
+    NetworkCard = SERVICE_STOP_REASON_MINOR_NETWORKCARD,
+    Environment = SERVICE_STOP_REASON_MINOR_ENVIRONMENT,
+    HardwareDriver = SERVICE_STOP_REASON_MINOR_HARDWARE_DRIVER,
+    OtherDriver = SERVICE_STOP_REASON_MINOR_OTHERDRIVER,
+    ServicePack = SERVICE_STOP_REASON_MINOR_SERVICEPACK,
+    SoftwareUpdate = SERVICE_STOP_REASON_MINOR_SOFTWARE_UPDATE,
+    SecurityFix = SERVICE_STOP_REASON_MINOR_SECURITYFIX,
+    Security = SERVICE_STOP_REASON_MINOR_SECURITY,
+    NetworkConnectivity = SERVICE_STOP_REASON_MINOR_NETWORK_CONNECTIVITY,
+    Wmi = SERVICE_STOP_REASON_MINOR_WMI,
+    ServicePackUninstall = SERVICE_STOP_REASON_MINOR_SERVICEPACK_UNINSTALL,
+    SoftwareUpdateUninstall = SERVICE_STOP_REASON_MINOR_SOFTWARE_UPDATE_UNINSTALL,
+    SecurityFixUninstall = SERVICE_STOP_REASON_MINOR_SECURITYFIX_UNINSTALL,
+    Mmc = SERVICE_STOP_REASON_MINOR_MMC,
+    None = SERVICE_STOP_REASON_MINOR_NONE,
+}
+
+/// Why a service is stopped, recorded by [`ServiceHandle::stop_with_reason`] in the system
+/// event log.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StopReason {
+    pub kind: StopReasonKind,
+    pub major: StopReasonMajor,
+    pub minor: StopReasonMinor,
+}
+
+/// A service or load order group that must be started before a service.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServiceDependency {
+    Service(String),
+    Group(String),
+}
+
+/// The description of a service, shown by services.msc and `sc qdescription`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServiceDescription {
+    Text(String),
+    /// An indirect string `@module,-id`, which Windows loads from the string table of `module`
+    /// in the language of the user, such as `@%SystemRoot%\system32\wuaueng.dll,-106`.
+    Resource {
+        module: String,
+        id: u32,
+    },
+}
+
+/// What the service control manager does after a service fails.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureActionType {
+    None = SC_ACTION_NONE,
+    /// Starts the service again, which needs a handle with [`ServiceAccess::START`].
+    Restart = SC_ACTION_RESTART,
+    /// Reboots the computer, which needs [`Privilege::Shutdown`].
+    Reboot = SC_ACTION_REBOOT,
+    /// Runs [`FailureActions::command`].
+    RunCommand = SC_ACTION_RUN_COMMAND,
+}
+
+/// One step of the recovery of a service, taken `delay` after it failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FailureAction {
+    pub action_type: FailureActionType,
+    pub delay: Duration,
+}
+
+/// What the service control manager does when a service fails, as on the Recovery tab of
+/// services.msc. The first action is taken on the first failure, the second on the second
+/// and the last one on every failure after that.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FailureActions {
+    /// How long the service must run without failing for the failure count to be reset,
+    /// or `None` to never reset it. Always `None` when there are no actions.
+    pub reset_period: Option<Duration>,
+    /// Message broadcast to the users of the computer before a reboot action.
+    pub reboot_message: Option<String>,
+    /// Command line of the run-command action.
+    pub command: Option<String>,
+    pub actions: Vec<FailureAction>,
+    /// Also takes the actions when the service stops itself with a non-zero exit code, not
+    /// only when its process crashes.
+    pub on_non_crash_failures: bool,
+}
+
+/// The security identifier the service control manager adds to the token of a service,
+/// `NT SERVICE\<name>`, which access control lists can grant rights to.
+#[repr(u32)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceSidType {
+    /// The service has no SID of its own.
+    #[default]
+    None = SERVICE_SID_TYPE_NONE,
+    /// The service SID is added to the groups of the token.
+    Unrestricted = SERVICE_SID_TYPE_UNRESTRICTED,
+    /// The service SID is also added to the restricting SIDs of a write-restricted token,
+    /// so the service can only write where its SID, `Everyone` or the logon SID may.
+    /// Every service hosted in the same process must be restricted too.
+    Restricted = SERVICE_SID_TYPE_RESTRICTED,
+}
+
+/// Owned snapshot of a service's configuration, as returned by `QueryServiceConfigW`, along
+/// with the start mode and preshutdown timeout `QueryServiceConfig2W` reports.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceConfigSnapshot {
+    pub service_type: ServiceType,
+    /// The modifier bits configured alongside `service_type`.
+    pub service_type_flags: ServiceTypeFlags,
+    pub start_type: ServiceStartType,
+    pub start_mode: ServiceStartMode,
+    /// How long the service gets to handle `SERVICE_CONTROL_PRESHUTDOWN`, `None` for drivers.
+    pub preshutdown_timeout: Option<Duration>,
+    pub error_control: ServiceErrorControl,
+    pub binary_path: String,
+    pub load_order_group: Option<String>,
+    pub tag_id: u32,
+    pub dependencies: Vec<ServiceDependency>,
+    /// The account a Win32 service runs under, `None` for drivers.
+    pub account: Option<ServiceAccount>,
+    pub display_name: String,
+}
+
+flags! {
+    /// Controls a service accepts, as reported in `dwControlsAccepted`.
+    ServiceControls {
+        STOP = SERVICE_ACCEPT_STOP,
+        PAUSE_CONTINUE = SERVICE_ACCEPT_PAUSE_CONTINUE,
+        SHUTDOWN = SERVICE_ACCEPT_SHUTDOWN,
+        PARAMCHANGE = SERVICE_ACCEPT_PARAMCHANGE,
+        NETBINDCHANGE = SERVICE_ACCEPT_NETBINDCHANGE,
+        HARDWAREPROFILECHANGE = SERVICE_ACCEPT_HARDWAREPROFILECHANGE,
+        POWEREVENT = SERVICE_ACCEPT_POWEREVENT,
+        SESSIONCHANGE = SERVICE_ACCEPT_SESSIONCHANGE,
+        PRESHUTDOWN = SERVICE_ACCEPT_PRESHUTDOWN,
+        TIMECHANGE = SERVICE_ACCEPT_TIMECHANGE,
+        TRIGGEREVENT = SERVICE_ACCEPT_TRIGGEREVENT,
+        USERMODEREBOOT = SERVICE_ACCEPT_USERMODEREBOOT,
+    }
+}
+
+flags! {
+    /// Modifier bits the SCM may report on top of a [`ServiceType`].
+    ServiceTypeFlags {
+        /// A template from which a per-user service is created at logon.
+        USER_SERVICE = SERVICE_USER_SERVICE,
+        /// A per-user service created from a [`USER_SERVICE`](Self::USER_SERVICE) template.
+        USERSERVICE_INSTANCE = SERVICE_USERSERVICE_INSTANCE,
+        /// A legacy service allowed to interact with the desktop.
+        INTERACTIVE_PROCESS = SERVICE_INTERACTIVE_PROCESS,
+        /// A service hosted by a packaged app.
+        PKG_SERVICE = SERVICE_PKG_SERVICE,
+    }
+}
+
+impl ServiceTypeFlags {
+    /// Splits a raw `dwServiceType` into its modifier bits, leaving the base type to
+    /// [`ServiceType::try_from`].
+    pub fn from_service_type(service_type: u32) -> Self {
+        Self::from_bits_retain(service_type & !(SERVICE_DRIVER | SERVICE_ADAPTER | SERVICE_WIN32))
+    }
+}
+
+flags! {
+    /// Service flags, as reported in `dwServiceFlags`.
+    ServiceFlags {
+        /// The service runs in a process essential to the system, which must not be terminated.
+        RUNS_IN_SYSTEM_PROCESS = SERVICE_RUNS_IN_SYSTEM_PROCESS,
+    }
+}
+
+/// The status of a service, as returned by [`ServiceHandle::status`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceStatus {
+    pub service_type: ServiceType,
+    /// The modifier bits reported alongside `service_type`.
+    pub service_type_flags: ServiceTypeFlags,
+    pub state: ServiceState,
+    pub controls_accepted: ServiceControls,
+    /// The Win32 error the service reported when starting or stopping, or
+    /// `ERROR_SERVICE_SPECIFIC_ERROR` if it reported `service_specific_exit_code` instead.
+    pub win32_exit_code: u32,
+    pub service_specific_exit_code: u32,
+    pub check_point: u32,
+    /// Milliseconds the pending operation is expected to take before the next checkpoint.
+    pub wait_hint: u32,
+    /// The process the service runs in, `None` while it is stopped or for drivers.
+    pub process_id: Option<u32>,
+    pub service_flags: ServiceFlags,
+}
+
+impl TryFrom<RawServiceStatus> for ServiceStatus {
+    type Error = QueryServiceError;
+
+    fn try_from(status: RawServiceStatus) -> Result<Self, Self::Error> {
+        Ok(Self {
+            service_type: ServiceType::try_from(status.service_type)?,
+            service_type_flags: ServiceTypeFlags::from_service_type(status.service_type),
+            state: ServiceState::try_from(status.current_state)?,
+            controls_accepted: ServiceControls::from_bits_retain(status.controls_accepted),
+            win32_exit_code: status.win32_exit_code,
+            service_specific_exit_code: status.service_specific_exit_code,
+            check_point: status.check_point,
+            wait_hint: status.wait_hint,
+            process_id: Some(status.process_id).filter(|&process_id| process_id != 0),
+            service_flags: ServiceFlags::from_bits_retain(status.service_flags),
+        })
+    }
+}
+
+/// How [`ServiceHandle::restart`] stops and starts a service.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RestartOptions {
+    /// How long to wait for the service, and each dependent, to stop, `None` to wait as long as
+    /// it makes progress.
+    pub stop_timeout: Option<Duration>,
+    /// How long to wait for the service, and each dependent, to start, `None` to wait as long
+    /// as it makes progress.
+    pub start_timeout: Option<Duration>,
+    /// Terminates the process of a service or dependent that doesn't stop in time. Only services
+    /// running alone in a process of the local machine are terminated.
+    pub force_kill: bool,
+    /// Stops the running dependents first and starts them again afterwards.
+    pub restart_dependents: bool,
+}
+
+/// What [`ServiceHandle::restart`] did, and how long each phase took.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RestartReport {
+    /// The dependents that were stopped and started again, in the order they were stopped.
+    pub stopped_dependents: Vec<String>,
+    /// The process that was terminated because the service didn't stop in time.
+    pub killed_process_id: Option<u32>,
+    /// The dependents whose process was terminated because they didn't stop in time, with
+    /// that process.
+    pub killed_dependents: Vec<(String, u32)>,
+    pub stop_dependents: Duration,
+    pub stop: Duration,
+    pub start: Duration,
+    pub start_dependents: Duration,
+}
+
+/// A service returned by [`ServiceHandle::all_dependents`] and [`ServiceHandle::direct_dependents`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DependentService {
+    pub service_name: String,
+    pub display_name: String,
+    pub state: ServiceState,
+}
+
+impl TryFrom<RawServiceEntry> for DependentService {
+    type Error = QueryServiceError;
+
+    fn try_from(entry: RawServiceEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            state: ServiceState::try_from(entry.status.current_state)?,
+            service_name: entry.service_name,
+            display_name: entry.display_name,
+        })
+    }
+}
+
+/// A partial configuration update for [`ServiceHandle::update_config`].
+///
+/// Fields left as `None` keep their current value.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct ServiceConfigChange {
+    pub service_type: Option<ServiceType>,
+    pub start_type: Option<ServiceStartType>,
+    /// Sets the start type and, for auto-start, whether the start is delayed. It must agree
+    /// with `start_type` if both are set.
+    pub start_mode: Option<ServiceStartMode>,
+    pub preshutdown_timeout: Option<Duration>,
+    pub error_control: Option<ServiceErrorControl>,
+    pub binary_path: Option<String>,
+    pub load_order_group: Option<String>,
+    pub dependencies: Option<Vec<ServiceDependency>>,
+    pub account: Option<ServiceAccount>,
+    pub password: Option<String>,
+    pub display_name: Option<String>,
+}
+
+impl ServiceDependency {
+    /// Parses an entry of a raw dependency list, where groups carry the `+` prefix.
+    pub fn from_raw(value: &str) -> Self {
+        match value.strip_prefix(SC_GROUP_IDENTIFIER) {
+            Some(group) => Self::Group(group.to_string()),
+            None => Self::Service(value.to_string()),
+        }
+    }
+
+    /// Returns the entry as it appears in a raw dependency list.
+    pub fn to_raw(&self) -> String {
+        match self {
+            Self::Service(name) => name.clone(),
+            Self::Group(group) => format!("{SC_GROUP_IDENTIFIER}{group}"),
+        }
+    }
+}
+
+impl ServiceDescription {
+    /// Parses a description as stored by the SCM, recognizing indirect `@module,-id` strings.
+    pub fn from_raw(value: &str) -> Self {
+        value
+            .strip_prefix('@')
+            .and_then(|resource| resource.rsplit_once(",-"))
+            .filter(|(module, id)| !module.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|(module, id)| {
+                Some(Self::Resource {
+                    module: module.to_string(),
+                    id: id.parse().ok()?,
+                })
+            })
+            .unwrap_or_else(|| Self::Text(value.to_string()))
+    }
+}
+
+impl From<&str> for ServiceDescription {
+    fn from(value: &str) -> Self {
+        Self::from_raw(value)
+    }
+}
+
+impl From<String> for ServiceDescription {
+    fn from(value: String) -> Self {
+        Self::from_raw(&value)
+    }
+}
+
+/// Formats the description as stored by the SCM.
+impl Display for ServiceDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(text) => write!(f, "{text}"),
+            Self::Resource { module, id } => write!(f, "@{module},-{id}"),
+        }
+    }
+}
+
+impl Display for ServiceDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_raw())
+    }
+}
+
+impl ServiceStartMode {
+    /// Combines a start type with the `SERVICE_CONFIG_DELAYED_AUTO_START_INFO` setting, which
+    /// only matters for auto-start services.
+    pub fn new(start_type: ServiceStartType, delayed_auto_start: bool) -> Self {
+        match start_type {
+            ServiceStartType::BootStart => Self::Boot,
+            ServiceStartType::SystemStart => Self::System,
+            ServiceStartType::AutoStart if delayed_auto_start => Self::AutoDelayed,
+            ServiceStartType::AutoStart => Self::Auto,
+            ServiceStartType::DemandStart => Self::Demand,
+            ServiceStartType::Disabled => Self::Disabled,
+        }
+    }
+
+    /// Returns the start type passed to `ChangeServiceConfigW`.
+    pub fn start_type(self) -> ServiceStartType {
+        match self {
+            Self::Boot => ServiceStartType::BootStart,
+            Self::System => ServiceStartType::SystemStart,
+            Self::Auto | Self::AutoDelayed => ServiceStartType::AutoStart,
+            Self::Demand => ServiceStartType::DemandStart,
+            Self::Disabled => ServiceStartType::Disabled,
+        }
+    }
+
+    /// Returns the delayed auto-start setting, or `None` if the mode isn't an auto-start one.
+    pub fn delayed_auto_start(self) -> Option<bool> {
+        match self {
+            Self::Auto => Some(false),
+            Self::AutoDelayed => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl From<ServiceStartType> for ServiceStartMode {
+    fn from(start_type: ServiceStartType) -> Self {
+        Self::new(start_type, false)
+    }
+}
+
+impl TryFrom<RawServiceConfig> for ServiceConfigSnapshot {
+    type Error = QueryServiceError;
+
+    /// Converts the `QueryServiceConfigW` part, leaving the start mode undelayed and the
+    /// preshutdown timeout unset.
+    fn try_from(config: RawServiceConfig) -> Result<Self, Self::Error> {
+        let service_type = ServiceType::try_from(config.service_type)?;
+        let start_type = ServiceStartType::try_from(config.start_type)?;
+        Ok(Self {
+            service_type,
+            service_type_flags: ServiceTypeFlags::from_service_type(config.service_type),
+            start_type,
+            start_mode: ServiceStartMode::from(start_type),
+            preshutdown_timeout: None,
+            error_control: ServiceErrorControl::try_from(config.error_control)?,
+            binary_path: config.binary_path_name,
+            load_order_group: Some(config.load_order_group).filter(|group| !group.is_empty()),
+            tag_id: config.tag_id,
+            dependencies: config
+                .dependencies
+                .iter()
+                .map(|dependency| ServiceDependency::from_raw(dependency))
+                .collect(),
+            account: Some(config.service_start_name)
+                .filter(|account| !service_type.is_driver() && !account.is_empty())
+                .map(|account| ServiceAccount::from_raw(&account)),
+            display_name: config.display_name,
+        })
+    }
+}
+
+impl From<ServiceConfigChange> for RawConfigChange {
+    fn from(change: ServiceConfigChange) -> Self {
+        Self {
+            service_type: change
+                .service_type
+                .map_or(SERVICE_NO_CHANGE, |service_type| service_type as u32),
+            start_type: change
+                .start_type
+                .or(change.start_mode.map(ServiceStartMode::start_type))
+                .map_or(SERVICE_NO_CHANGE, |start_type| start_type as u32),
+            error_control: change
+                .error_control
+                .map_or(SERVICE_NO_CHANGE, |error_control| error_control as u32),
+            binary_path_name: change.binary_path,
+            load_order_group: change.load_order_group,
+            dependencies: change
+                .dependencies
+                .map(|dependencies| dependencies.iter().map(ServiceDependency::to_raw).collect()),
+            service_start_name: change.account.map(|account| account.to_string()),
+            password: change.password,
+            display_name: change.display_name,
+        }
+    }
+}
+
+impl ServiceType {
+    /// Returns whether services of this type are drivers rather than Win32 processes.
+    pub fn is_driver(&self) -> bool {
+        !matches!(self, Self::Win32OwnProcess | Self::Win32ShareProcess)
+    }
+}
+
 impl Display for ServiceType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
@@ -107,6 +672,28 @@ impl Display for ServiceState {
     }
 }
 
+impl ServiceState {
+    /// Returns whether the service is between two states, waiting for a start or control
+    /// request to complete.
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            Self::StartPending | Self::StopPending | Self::ContinuePending | Self::PausePending
+        )
+    }
+}
+
+impl StopReason {
+    pub fn new(kind: StopReasonKind, major: StopReasonMajor, minor: StopReasonMinor) -> Self {
+        Self { kind, major, minor }
+    }
+
+    /// Returns the `SERVICE_STOP_REASON_*` code passed to `ControlServiceExW`.
+    pub fn bits(self) -> u32 {
+        self.kind as u32 | self.major as u32 | self.minor as u32
+    }
+}
+
 impl Display for ServiceHandle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let _ = writeln!(f, "StartType: {:?}", self.get_start_type());
@@ -118,8 +705,9 @@ impl Display for ServiceHandle {
 impl TryFrom<u32> for ServiceType {
     type Error = QueryServiceError;
 
+    /// Converts the base type, ignoring the [`ServiceTypeFlags`] modifier bits.
     fn try_from(value: u32) -> Result<Self, Self::Error> {
-        match value {
+        match value & (SERVICE_DRIVER | SERVICE_ADAPTER | SERVICE_WIN32) {
             SERVICE_ADAPTER => Ok(ServiceType::Adapter),
             SERVICE_FILE_SYSTEM_DRIVER => Ok(ServiceType::FileSystemDriver),
             SERVICE_KERNEL_DRIVER => Ok(ServiceType::KernelDriver),
@@ -134,6 +722,93 @@ impl TryFrom<u32> for ServiceType {
     }
 }
 
+impl TryFrom<u32> for ServiceSidType {
+    type Error = QueryServiceError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            SERVICE_SID_TYPE_NONE => Ok(Self::None),
+            SERVICE_SID_TYPE_UNRESTRICTED => Ok(Self::Unrestricted),
+            SERVICE_SID_TYPE_RESTRICTED => Ok(Self::Restricted),
+            _ => Err(QueryServiceError::from((
+                0,
+                "invalid service SID type".to_string(),
+            ))),
+        }
+    }
+}
+
+impl TryFrom<u32> for FailureActionType {
+    type Error = QueryServiceError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            SC_ACTION_NONE => Ok(Self::None),
+            SC_ACTION_RESTART => Ok(Self::Restart),
+            SC_ACTION_REBOOT => Ok(Self::Reboot),
+            SC_ACTION_RUN_COMMAND => Ok(Self::RunCommand),
+            _ => Err(QueryServiceError::from((
+                0,
+                "invalid failure action type".to_string(),
+            ))),
+        }
+    }
+}
+
+impl FailureAction {
+    pub fn new(action_type: FailureActionType, delay: Duration) -> Self {
+        Self { action_type, delay }
+    }
+}
+
+impl TryFrom<RawFailureActions> for FailureActions {
+    type Error = QueryServiceError;
+
+    /// Converts everything but [`on_non_crash_failures`](FailureActions::on_non_crash_failures),
+    /// which has a setting of its own.
+    fn try_from(failure_actions: RawFailureActions) -> Result<Self, Self::Error> {
+        Ok(Self {
+            // Without actions there is no failure count, and the reset period is meaningless.
+            reset_period: Some(failure_actions.reset_period)
+                .filter(|&reset_period| {
+                    reset_period != INFINITE && !failure_actions.actions.is_empty()
+                })
+                .map(|reset_period| Duration::from_secs(reset_period.into())),
+            reboot_message: Some(failure_actions.reboot_message)
+                .filter(|reboot_message| !reboot_message.is_empty()),
+            command: Some(failure_actions.command).filter(|command| !command.is_empty()),
+            actions: failure_actions
+                .actions
+                .into_iter()
+                .map(|(action_type, delay)| {
+                    Ok(FailureAction::new(
+                        FailureActionType::try_from(action_type)?,
+                        Duration::from_millis(delay.into()),
+                    ))
+                })
+                .collect::<Result<_, QueryServiceError>>()?,
+            on_non_crash_failures: false,
+        })
+    }
+}
+
+impl TryFrom<u32> for ServiceErrorControl {
+    type Error = QueryServiceError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            SERVICE_ERROR_CRITICAL => Ok(Self::ErrorCritical),
+            SERVICE_ERROR_IGNORE => Ok(Self::ErrorIgnore),
+            SERVICE_ERROR_NORMAL => Ok(Self::ErrorNormal),
+            SERVICE_ERROR_SEVERE => Ok(Self::ErrorSevere),
+            _ => Err(QueryServiceError::from((
+                0,
+                "invalid service error control".to_string(),
+            ))),
+        }
+    }
+}
+
 impl TryFrom<u32> for ServiceStartType {
     type Error = QueryServiceError;
 
@@ -175,21 +850,155 @@ impl TryFrom<u32> for ServiceState {
 impl Drop for ServiceHandle {
     fn drop(&mut self) {
         if let Some(handle) = self.handle {
-            unsafe {
-                CloseServiceHandle(handle);
-            }
+            self.backend.close_service_handle(handle);
         }
     }
 }
 
+/// Describes the exit code a service reported, for the context of an error.
+#[doc(hidden)]
+fn exit_code_suffix(status: &RawServiceStatus) -> String {
+    match status.win32_exit_code {
+        0 => String::new(),
+        ERROR_SERVICE_SPECIFIC_ERROR => format!(
+            " with service-specific exit code {}",
+            status.service_specific_exit_code
+        ),
+        exit_code => format!(" with exit code {exit_code}"),
+    }
+}
+
+#[doc(hidden)]
+fn collect_arguments<I, S>(arguments: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    arguments
+        .into_iter()
+        .map(|argument| argument.as_ref().to_string())
+        .collect()
+}
+
+/// Returns the flag the service must accept to be sent `control`, empty for controls every
+/// service accepts.
+#[doc(hidden)]
+fn control_accepted(control: u32) -> ServiceControls {
+    match control {
+        SERVICE_CONTROL_STOP => ServiceControls::STOP,
+        SERVICE_CONTROL_PAUSE | SERVICE_CONTROL_CONTINUE => ServiceControls::PAUSE_CONTINUE,
+        SERVICE_CONTROL_PARAMCHANGE => ServiceControls::PARAMCHANGE,
+        SERVICE_CONTROL_NETBINDADD
+        | SERVICE_CONTROL_NETBINDREMOVE
+        | SERVICE_CONTROL_NETBINDENABLE
+        | SERVICE_CONTROL_NETBINDDISABLE => ServiceControls::NETBINDCHANGE,
+        _ => ServiceControls::empty(),
+    }
+}
+
+/// Returns the right `ControlService` requires for `control`.
+#[doc(hidden)]
+fn control_access(control: u32) -> ServiceAccess {
+    match control {
+        SERVICE_CONTROL_STOP => ServiceAccess::STOP,
+        SERVICE_CONTROL_INTERROGATE => ServiceAccess::INTERROGATE,
+        128..=255 => ServiceAccess::USER_DEFINED_CONTROL,
+        _ => ServiceAccess::PAUSE_CONTINUE,
+    }
+}
+
 impl ServiceHandle {
-    /// Creates a new `ServiceHandle`.
-    pub fn new(handle: SC_HANDLE) -> Self {
+    /// Creates a new `ServiceHandle` from a handle returned by the Win32 service control manager.
+    #[cfg(windows)]
+    pub fn new(handle: ScHandle) -> Self {
+        Self::with_backend(Arc::new(crate::win32::Win32Backend), handle)
+    }
+
+    /// Creates a new `ServiceHandle` from a handle issued by `backend`.
+    pub fn with_backend(backend: Arc<dyn ScmBackend>, handle: ScHandle) -> Self {
         Self {
+            backend,
             handle: Some(handle),
+            tag_id: None,
+            manager: None,
+            service_name: None,
+            access: ServiceAccess::ALL_ACCESS,
+        }
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn with_access(mut self, access: ServiceAccess) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Returns the rights the service was opened with.
+    ///
+    /// A handle built with [`ServiceHandle::with_backend`] is assumed to have every right, and
+    /// leaves the access checks to the service control manager.
+    pub fn access(&self) -> ServiceAccess {
+        self.access
+    }
+
+    #[doc(hidden)]
+    fn check_access<E: From<(u32, String)>>(
+        &self,
+        required: ServiceAccess,
+        function: &str,
+    ) -> Result<(), E> {
+        match self.access.missing(required, function) {
+            Some(context) => Err(E::from((ERROR_ACCESS_DENIED, self.context(context)))),
+            None => Ok(()),
         }
     }
 
+    #[doc(hidden)]
+    fn context(&self, context: String) -> String {
+        on_machine(context, self.machine_name())
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn with_manager(mut self, manager: Arc<ScmConnection>, service_name: &str) -> Self {
+        self.manager = Some(manager);
+        self.service_name = Some(service_name.to_string());
+        self
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn with_tag_id(mut self, tag_id: u32) -> Self {
+        self.tag_id = Some(tag_id);
+        self
+    }
+
+    /// Returns the name the service was opened or created with, if it came from a
+    /// [`ServiceManager`](crate::service_manager::ServiceManager).
+    pub fn service_name(&self) -> Option<&str> {
+        self.service_name.as_deref()
+    }
+
+    /// Returns the remote machine the service was opened on, or `None` for the local machine.
+    pub fn machine_name(&self) -> Option<&str> {
+        self.manager
+            .as_ref()
+            .and_then(|manager| manager.machine_name.as_deref())
+    }
+
+    /// Returns the tag `CreateServiceW` assigned to the service if it was created with
+    /// [`ServiceConfig::request_tag`](crate::service_manager::ServiceConfig::request_tag).
+    pub fn tag_id(&self) -> Option<u32> {
+        self.tag_id
+    }
+
+    /// Returns the full status of the service, including the process it runs in and the exit
+    /// codes it reported.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't retrieve the service status.
+    pub fn status(&self) -> Result<ServiceStatus, QueryServiceError> {
+        ServiceStatus::try_from(self.get_status()?)
+    }
+
     /// Returns the current state of this `ServiceHandle`.
     ///
     /// # Errors
@@ -198,144 +1007,1338 @@ impl ServiceHandle {
     pub fn state(&self) -> Result<ServiceState, QueryServiceError> {
         let status = self.get_status()?;
 
-        ServiceState::try_from(status.dwCurrentState)
+        ServiceState::try_from(status.current_state)
     }
 
-    /// Updates the configuration of the service.
+    /// Subscribes to the state changes of this service selected by `mask`, and to it being
+    /// marked for deletion if `mask` contains [`ServiceNotify::DELETE_PENDING`]. The first event
+    /// is the state the service is in when the subscription starts, if `mask` selects it.
+    ///
+    /// The `ServiceHandle` must come from a [`ServiceManager`](crate::service_manager::ServiceManager).
+    /// The subscription opens a handle of its own and stops when the returned [`ServiceEvents`]
+    /// is dropped, or after the service is marked for deletion.
     ///
     /// # Errors
     ///
-    /// This function will return an error if it can't update the service configuration.
-    pub fn update_config(&self, options: ServiceConfig) -> Result<(), UpdateServiceError> {
-        let handle = self.handle.ok_or(UpdateServiceError::InvalidHandle(
-            0,
-            "[update_config] invalid service handle".to_string(),
-        ))?;
-
-        let display_name = U16CString::from_str(options.display_name.clone()).map_err(|_| {
-            UpdateServiceError::InvalidParameter(
-                0,
-                "[update_config] invalid display_name".to_string(),
-            )
-        })?;
-        let binary_path = U16CString::from_str(options.binary_path).map_err(|_| {
-            UpdateServiceError::InvalidParameter(
+    /// This function will return an error if `mask` is invalid or it can't open the service to watch.
+    pub fn subscribe(&self, mask: ServiceNotify) -> Result<ServiceEvents, QueryServiceError> {
+        let (Some(manager), Some(service_name)) = (&self.manager, &self.service_name) else {
+            return Err(QueryServiceError::InvalidHandle(
                 0,
-                "[update_config] invalid binary_path".to_string(),
-            )
-        })?;
-        unsafe {
-            if ChangeServiceConfigW(
-                handle,
-                options.service_type as u32,
-                options.start_type as u32,
-                options.error_control as u32,
-                binary_path.as_ptr(),
-                std::ptr::null(),
-                std::ptr::null_mut(),
-                std::ptr::null(),
-                std::ptr::null(),
-                std::ptr::null(),
-                display_name.as_ptr(),
-            ) == FALSE
-            {
-                return Err(UpdateServiceError::from((
-                    get_last_error(),
-                    "[update_config] failed".to_string(),
-                )));
-            }
+                "[subscribe] service wasn't opened through a ServiceManager".to_string(),
+            ));
+        };
+
+        self.check_access::<QueryServiceError>(ServiceAccess::QUERY_STATUS, "subscribe")?;
+        if mask.is_empty() || !ServiceNotify::SERVICE_EVENTS.contains(mask) {
+            return Err(QueryServiceError::InvalidParameter(
+                ERROR_INVALID_PARAMETER,
+                self.context(format!("[subscribe] a service can't report {mask}")),
+            ));
         }
-        Ok(())
+
+        ServiceEvents::subscribe(manager.clone(), Some(service_name), mask)
     }
 
-    /// Returns the get start type of this [`ServiceHandle`].
+    /// Returns every service that depends on this one, directly or through other dependents,
+    /// in reverse start order: a service is always listed before the services it depends on.
     ///
     /// # Errors
     ///
-    /// This function will return an error if it can't get the start type.
-    pub fn get_start_type(&self) -> Result<ServiceStartType, QueryServiceError> {
-        let config = self.get_config()?;
+    /// This function will return an error if it can't enumerate the dependents.
+    pub fn all_dependents(
+        &self,
+        state: ServiceStateFilter,
+    ) -> Result<Vec<DependentService>, QueryServiceError> {
+        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
+            0,
+            "[all_dependents] invalid service handle".to_string(),
+        ))?;
+        self.check_access::<QueryServiceError>(
+            ServiceAccess::ENUMERATE_DEPENDENTS,
+            "all_dependents",
+        )?;
 
-        ServiceStartType::try_from(config.dwStartType)
+        self.backend
+            .enum_dependent_services(handle, state as u32)
+            .map_err(|err| {
+                QueryServiceError::from((
+                    err,
+                    self.context("[all_dependents] EnumDependentServicesW failed".to_string()),
+                ))
+            })?
+            .into_iter()
+            .map(DependentService::try_from)
+            .collect()
     }
 
-    /// Sets the start type of the service.
+    /// Returns the services that list this service, or its load order group, as a dependency.
+    ///
+    /// The `ServiceHandle` must come from a [`ServiceManager`](crate::service_manager::ServiceManager),
+    /// which is used to open the dependents and read their configuration.
     ///
     /// # Errors
     ///
-    /// This function will return an error if it can't set the start type.
-    pub fn set_start_type(&self, start_type: ServiceStartType) -> Result<(), UpdateServiceError> {
-        let handle = self.handle.ok_or(UpdateServiceError::InvalidHandle(
-            0,
-            "[set_start_type] invalid service handle".to_string(),
-        ))?;
-        let config = self.get_config().map_err(|_| UpdateServiceError::AccessDenied(get_last_error(), "[set_start_type] failed to get service config".to_string()))?;
-
-        unsafe {
-            let mut tag_id = config.dwTagId;
-            if ChangeServiceConfigW(
-                handle,
-                config.dwServiceType,
-                start_type as u32,
-                config.dwErrorControl,
-                config.lpBinaryPathName,
-                config.lpLoadOrderGroup,
-                &mut tag_id,
-                config.lpDependencies,
-                config.lpServiceStartName,
-                std::ptr::null(),
-                config.lpDisplayName,
-            ) == FALSE
-            {
-                return Err(UpdateServiceError::from((
-                    get_last_error(),
-                    "[set_start_type] ChangeServiceConfig failed".to_string(),
-                )));
-            }
+    /// This function will return an error if it can't enumerate the dependents or query their configuration.
+    pub fn direct_dependents(
+        &self,
+        state: ServiceStateFilter,
+    ) -> Result<Vec<DependentService>, QueryServiceError> {
+        let (Some(manager), Some(service_name)) = (&self.manager, &self.service_name) else {
+            return Err(QueryServiceError::InvalidHandle(
+                0,
+                "[direct_dependents] service wasn't opened through a ServiceManager".to_string(),
+            ));
+        };
+        let group = self
+            .query_base_config("direct_dependents")?
+            .load_order_group;
+
+        let mut dependents = Vec::new();
+        for dependent in self.all_dependents(state)? {
+            let handle = self
+                .backend
+                .open_service(
+                    manager.handle,
+                    &dependent.service_name,
+                    ServiceAccess::QUERY_CONFIG.bits(),
+                )
+                .map_err(|err| {
+                    QueryServiceError::from((
+                        err,
+                        self.context(format!(
+                            "[direct_dependents] can't open {}",
+                            dependent.service_name
+                        )),
+                    ))
+                })?;
+            let config = ServiceHandle::with_backend(self.backend.clone(), handle)
+                .with_access(ServiceAccess::QUERY_CONFIG)
+                .with_manager(manager.clone(), &dependent.service_name)
+                .query_base_config("direct_dependents")?;
+
+            if config
+                .dependencies
+                .iter()
+                .any(|dependency| match dependency {
+                    ServiceDependency::Service(name) => name.eq_ignore_ascii_case(service_name),
+                    ServiceDependency::Group(name) => group
+                        .as_ref()
+                        .is_some_and(|group| name.eq_ignore_ascii_case(group)),
+                })
+            {
+                dependents.push(dependent);
+            }
+        }
+        Ok(dependents)
+    }
+
+    /// Updates the configuration of the service.
+    ///
+    /// The change is validated before anything is written, but the update isn't atomic: the
+    /// delayed auto-start flag and preshutdown timeout are set through `ChangeServiceConfig2W`
+    /// before the rest goes through `ChangeServiceConfigW`, so if that last call fails they stay
+    /// applied.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't update the service configuration.
+    pub fn update_config(&self, change: ServiceConfigChange) -> Result<(), UpdateServiceError> {
+        let handle = self.handle.ok_or(UpdateServiceError::InvalidHandle(
+            0,
+            "[update_config] invalid service handle".to_string(),
+        ))?;
+        self.check_access::<UpdateServiceError>(ServiceAccess::CHANGE_CONFIG, "update_config")?;
+
+        for (field, value) in [
+            ("binary_path", &change.binary_path),
+            ("load_order_group", &change.load_order_group),
+            ("password", &change.password),
+            ("display_name", &change.display_name),
+        ] {
+            if value.as_ref().is_some_and(|value| value.contains('\0')) {
+                return Err(UpdateServiceError::InvalidParameter(
+                    0,
+                    format!("[update_config] invalid {field}"),
+                ));
+            }
+        }
+        if change
+            .dependencies
+            .iter()
+            .flatten()
+            .any(|dependency| dependency.to_raw().contains('\0'))
+        {
+            return Err(UpdateServiceError::InvalidParameter(
+                0,
+                "[update_config] invalid dependencies".to_string(),
+            ));
+        }
+
+        if let (Some(account), password) = (&change.account, &change.password) {
+            if account.requires_password() != password.is_some() {
+                return Err(UpdateServiceError::InvalidConfig(
+                    ERROR_INVALID_PARAMETER,
+                    format!(
+                        "[update_config] account {account} {} a password",
+                        if account.requires_password() {
+                            "requires"
+                        } else {
+                            "doesn't take"
+                        }
+                    ),
+                ));
+            }
+        }
+
+        if let (Some(start_type), Some(start_mode)) = (change.start_type, change.start_mode) {
+            if start_mode.start_type() != start_type {
+                return Err(UpdateServiceError::InvalidConfig(
+                    ERROR_INVALID_PARAMETER,
+                    format!("[update_config] start mode {start_mode:?} contradicts start type {start_type:?}"),
+                ));
+            }
+        }
+        // Any start mode or type other than a delayed one clears the flag, which would
+        // otherwise turn a later plain auto start into a delayed one. Drivers don't have it.
+        let delayed_auto_start = match (change.start_mode, change.start_type) {
+            (Some(start_mode), _) => Some(start_mode == ServiceStartMode::AutoDelayed),
+            (None, Some(_)) => Some(false),
+            (None, None) => None,
+        };
+        let delayed_auto_start = match delayed_auto_start {
+            Some(false) if self.is_driver(change.service_type)? => None,
+            delayed => delayed,
+        };
+        let preshutdown_timeout = change
+            .preshutdown_timeout
+            .map(|timeout| {
+                u32::try_from(timeout.as_millis()).map_err(|_| {
+                    UpdateServiceError::InvalidConfig(
+                        ERROR_INVALID_PARAMETER,
+                        format!("[update_config] preshutdown timeout {timeout:?} is too long"),
+                    )
+                })
+            })
+            .transpose()?;
+
+        // The ChangeServiceConfig2W settings go first, so that if one is refused the main
+        // configuration is left untouched.
+        let config2_failed = |err| {
+            UpdateServiceError::from((
+                err,
+                self.context("[update_config] ChangeServiceConfig2W failed".to_string()),
+            ))
+        };
+        if let Some(delayed) = delayed_auto_start {
+            self.backend
+                .change_service_delayed_auto_start(handle, delayed)
+                .map_err(config2_failed)?;
+        }
+        if let Some(timeout) = preshutdown_timeout {
+            self.backend
+                .change_service_preshutdown_timeout(handle, timeout)
+                .map_err(config2_failed)?;
+        }
+
+        self.backend
+            .change_service_config(handle, &RawConfigChange::from(change))
+            .map_err(|err| {
+                UpdateServiceError::from((err, self.context("[update_config] failed".to_string())))
+            })
+    }
+
+    /// Returns the get start type of this [`ServiceHandle`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't get the start type.
+    pub fn get_start_type(&self) -> Result<ServiceStartType, QueryServiceError> {
+        Ok(self.query_base_config("get_start_type")?.start_type)
+    }
+
+    /// Returns how the service is started, telling delayed auto-start apart.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't query the service configuration.
+    pub fn start_mode(&self) -> Result<ServiceStartMode, QueryServiceError> {
+        Ok(self.query_config()?.start_mode)
+    }
+
+    /// Sets how the service is started, including whether an auto-start is delayed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't update the service configuration.
+    pub fn set_start_mode(&self, start_mode: ServiceStartMode) -> Result<(), UpdateServiceError> {
+        self.update_config(ServiceConfigChange {
+            start_mode: Some(start_mode),
+            ..Default::default()
+        })
+    }
+
+    /// Returns how long the service gets to handle `SERVICE_CONTROL_PRESHUTDOWN`, or `None`
+    /// for drivers.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't query the service configuration.
+    pub fn preshutdown_timeout(&self) -> Result<Option<Duration>, QueryServiceError> {
+        Ok(self.query_config()?.preshutdown_timeout)
+    }
+
+    /// Sets how long the service gets to handle `SERVICE_CONTROL_PRESHUTDOWN` before the
+    /// system shuts down anyway.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't update the service configuration.
+    pub fn set_preshutdown_timeout(&self, timeout: Duration) -> Result<(), UpdateServiceError> {
+        self.update_config(ServiceConfigChange {
+            preshutdown_timeout: Some(timeout),
+            ..Default::default()
+        })
+    }
+
+    /// Returns an owned snapshot of the service configuration.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't query the service configuration.
+    pub fn query_config(&self) -> Result<ServiceConfigSnapshot, QueryServiceError> {
+        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
+            0,
+            "[query_config] invalid service handle".to_string(),
+        ))?;
+        let mut snapshot = self.query_base_config("query_config")?;
+
+        // Drivers have neither setting.
+        if !snapshot.service_type.is_driver() {
+            let config2_failed = |err| {
+                QueryServiceError::from((
+                    err,
+                    self.context("[query_config] QueryServiceConfig2W failed".to_string()),
+                ))
+            };
+            let delayed = self
+                .backend
+                .query_service_delayed_auto_start(handle)
+                .map_err(config2_failed)?;
+            snapshot.start_mode = ServiceStartMode::new(snapshot.start_type, delayed);
+            let timeout = self
+                .backend
+                .query_service_preshutdown_timeout(handle)
+                .map_err(config2_failed)?;
+            snapshot.preshutdown_timeout = Some(Duration::from_millis(timeout.into()));
+        }
+        Ok(snapshot)
+    }
+
+    /// Returns the `QueryServiceConfigW` part of the configuration, without the two
+    /// `QueryServiceConfig2W` calls [`query_config`](Self::query_config) makes for the start mode
+    /// and preshutdown timeout.
+    fn query_base_config(
+        &self,
+        function: &str,
+    ) -> Result<ServiceConfigSnapshot, QueryServiceError> {
+        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
+            0,
+            format!("[{function}] invalid service handle"),
+        ))?;
+        self.check_access::<QueryServiceError>(ServiceAccess::QUERY_CONFIG, function)?;
+        let config = self.backend.query_service_config(handle).map_err(|err| {
+            QueryServiceError::from((
+                err,
+                self.context(format!("[{function}] QueryServiceConfig failed")),
+            ))
+        })?;
+        ServiceConfigSnapshot::try_from(config)
+    }
+
+    /// Sets the start type of the service, clearing the delayed auto-start flag of a service
+    /// that isn't a driver.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't set the start type.
+    pub fn set_start_type(&self, start_type: ServiceStartType) -> Result<(), UpdateServiceError> {
+        self.update_config(ServiceConfigChange {
+            start_type: Some(start_type),
+            ..Default::default()
+        })
+    }
+
+    /// Tells whether the service is a driver, going by `service_type` when the change sets one.
+    /// Without the right to query the configuration, the service is taken not to be one.
+    #[doc(hidden)]
+    fn is_driver(&self, service_type: Option<ServiceType>) -> Result<bool, UpdateServiceError> {
+        if let Some(service_type) = service_type {
+            return Ok(service_type.is_driver());
+        }
+        if !self.access.contains(ServiceAccess::QUERY_CONFIG) {
+            return Ok(false);
+        }
+        let config = self.query_base_config("update_config")?;
+        Ok(config.service_type.is_driver())
+    }
+
+    /// Returns the description of the service, or `None` if it has none.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't query the description.
+    pub fn description(&self) -> Result<Option<ServiceDescription>, QueryServiceError> {
+        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
+            0,
+            "[description] invalid service handle".to_string(),
+        ))?;
+        self.check_access::<QueryServiceError>(ServiceAccess::QUERY_CONFIG, "description")?;
+        let description = self
+            .backend
+            .query_service_description(handle)
+            .map_err(|err| {
+                QueryServiceError::from((
+                    err,
+                    self.context("[description] QueryServiceConfig2W failed".to_string()),
+                ))
+            })?;
+
+        Ok((!description.is_empty()).then(|| ServiceDescription::from_raw(&description)))
+    }
+
+    /// Sets the description of the service. An empty text deletes it, like
+    /// [`clear_description`](Self::clear_description).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the description contains a NUL character or it
+    /// can't set the description.
+    pub fn set_description(
+        &self,
+        description: impl Into<ServiceDescription>,
+    ) -> Result<(), UpdateServiceError> {
+        self.change_description("set_description", &description.into().to_string())
+    }
+
+    /// Deletes the description of the service.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't delete the description.
+    pub fn clear_description(&self) -> Result<(), UpdateServiceError> {
+        self.change_description("clear_description", "")
+    }
+
+    #[doc(hidden)]
+    fn change_description(
+        &self,
+        function: &str,
+        description: &str,
+    ) -> Result<(), UpdateServiceError> {
+        let handle = self.handle.ok_or(UpdateServiceError::InvalidHandle(
+            0,
+            format!("[{function}] invalid service handle"),
+        ))?;
+        self.check_access::<UpdateServiceError>(ServiceAccess::CHANGE_CONFIG, function)?;
+        if description.contains('\0') {
+            return Err(UpdateServiceError::InvalidParameter(
+                0,
+                format!("[{function}] invalid description"),
+            ));
+        }
+
+        self.backend
+            .change_service_description(handle, description)
+            .map_err(|err| {
+                UpdateServiceError::from((
+                    err,
+                    self.context(format!("[{function}] ChangeServiceConfig2W failed")),
+                ))
+            })
+    }
+
+    /// Returns what the service control manager does when the service fails.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't query the failure actions.
+    pub fn failure_actions(&self) -> Result<FailureActions, QueryServiceError> {
+        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
+            0,
+            "[failure_actions] invalid service handle".to_string(),
+        ))?;
+        self.check_access::<QueryServiceError>(ServiceAccess::QUERY_CONFIG, "failure_actions")?;
+        let query_failed = |err| {
+            QueryServiceError::from((
+                err,
+                self.context("[failure_actions] QueryServiceConfig2W failed".to_string()),
+            ))
+        };
+
+        let mut failure_actions = FailureActions::try_from(
+            self.backend
+                .query_service_failure_actions(handle)
+                .map_err(query_failed)?,
+        )?;
+        failure_actions.on_non_crash_failures = self
+            .backend
+            .query_service_failure_actions_flag(handle)
+            .map_err(query_failed)?;
+        Ok(failure_actions)
+    }
+
+    /// Replaces what the service control manager does when the service fails. Passing
+    /// `FailureActions::default()` removes every action.
+    ///
+    /// A restart action needs a handle with [`ServiceAccess::START`], and a reboot action
+    /// [`Privilege::Shutdown`], which is enabled for the duration of the call.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the failure actions are invalid, the handle or
+    /// the process lacks a right their actions need, or it can't set them.
+    pub fn set_failure_actions(
+        &self,
+        failure_actions: &FailureActions,
+    ) -> Result<(), UpdateServiceError> {
+        let function = "set_failure_actions";
+        let handle = self.handle.ok_or(UpdateServiceError::InvalidHandle(
+            0,
+            format!("[{function}] invalid service handle"),
+        ))?;
+        let raw = self.raw_failure_actions(function, failure_actions)?;
+
+        let has_action = |action_type| {
+            failure_actions
+                .actions
+                .iter()
+                .any(|action| action.action_type == action_type)
+        };
+        let mut required_access = ServiceAccess::CHANGE_CONFIG;
+        if has_action(FailureActionType::Restart) {
+            required_access |= ServiceAccess::START;
+        }
+        self.check_access::<UpdateServiceError>(required_access, function)?;
+        let _shutdown = if has_action(FailureActionType::Reboot) {
+            let guard = PrivilegeGuard::with_backend(self.backend.clone(), &[Privilege::Shutdown])
+                .map_err(|err| {
+                    UpdateServiceError::AccessDenied(
+                        ERROR_PRIVILEGE_NOT_HELD,
+                        self.context(format!(
+                            "[{function}] a reboot action requires {}: {err}",
+                            Privilege::Shutdown
+                        )),
+                    )
+                })?;
+            Some(guard)
+        } else {
+            None
+        };
+
+        let change_failed = |err| {
+            UpdateServiceError::from((
+                err,
+                self.context(format!("[{function}] ChangeServiceConfig2W failed")),
+            ))
+        };
+        self.backend
+            .change_service_failure_actions(handle, &raw)
+            .map_err(change_failed)?;
+        self.backend
+            .change_service_failure_actions_flag(handle, failure_actions.on_non_crash_failures)
+            .map_err(change_failed)
+    }
+
+    /// Checks `failure_actions` for values `ChangeServiceConfig2W` can't take and converts them.
+    #[doc(hidden)]
+    fn raw_failure_actions(
+        &self,
+        function: &str,
+        failure_actions: &FailureActions,
+    ) -> Result<RawFailureActions, UpdateServiceError> {
+        let invalid = |reason: String| {
+            UpdateServiceError::InvalidConfig(
+                ERROR_INVALID_PARAMETER,
+                self.context(format!("[{function}] {reason}")),
+            )
+        };
+
+        for (field, value) in [
+            ("reboot message", &failure_actions.reboot_message),
+            ("command", &failure_actions.command),
+        ] {
+            if value.as_ref().is_some_and(|value| value.contains('\0')) {
+                return Err(UpdateServiceError::InvalidParameter(
+                    0,
+                    self.context(format!("[{function}] invalid {field}")),
+                ));
+            }
+        }
+        let command = failure_actions.command.clone().unwrap_or_default();
+        if command.is_empty()
+            && failure_actions
+                .actions
+                .iter()
+                .any(|action| action.action_type == FailureActionType::RunCommand)
+        {
+            return Err(invalid(
+                "a run-command action requires a command".to_string(),
+            ));
+        }
+
+        let reset_period = match failure_actions.reset_period {
+            None => INFINITE,
+            Some(reset_period) => u32::try_from(reset_period.as_secs())
+                .ok()
+                .filter(|&seconds| seconds != INFINITE)
+                .ok_or_else(|| invalid(format!("reset period {reset_period:?} is too long")))?,
+        };
+        let actions = failure_actions
+            .actions
+            .iter()
+            .enumerate()
+            .map(|(index, action)| {
+                let delay = u32::try_from(action.delay.as_millis()).map_err(|_| {
+                    invalid(format!(
+                        "delay {:?} of action {index} is too long",
+                        action.delay
+                    ))
+                })?;
+                Ok((action.action_type as u32, delay))
+            })
+            .collect::<Result<_, UpdateServiceError>>()?;
+
+        Ok(RawFailureActions {
+            reset_period,
+            reboot_message: failure_actions.reboot_message.clone().unwrap_or_default(),
+            command,
+            actions,
+        })
+    }
+
+    /// Returns the security identifier type of the service.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't query the SID type.
+    pub fn sid_type(&self) -> Result<ServiceSidType, QueryServiceError> {
+        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
+            0,
+            "[sid_type] invalid service handle".to_string(),
+        ))?;
+        self.check_access::<QueryServiceError>(ServiceAccess::QUERY_CONFIG, "sid_type")?;
+        let sid_type = self.backend.query_service_sid_type(handle).map_err(|err| {
+            QueryServiceError::from((
+                err,
+                self.context("[sid_type] QueryServiceConfig2W failed".to_string()),
+            ))
+        })?;
+        ServiceSidType::try_from(sid_type)
+    }
+
+    /// Sets the security identifier type of the service.
+    ///
+    /// Services sharing a process must agree on whether their SID is restricted, so when the
+    /// service runs in a shared process and was opened through a
+    /// [`ServiceManager`](crate::service_manager::ServiceManager), the running services with
+    /// the same binary path are checked as well. Stopped ones aren't, so that the services of
+    /// a process can be switched one at a time while it is down. This needs a handle with
+    /// [`ServiceAccess::QUERY_CONFIG`] besides [`ServiceAccess::CHANGE_CONFIG`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service is a driver, if the SID type doesn't
+    /// match that of a running service it shares a process with, or if it can't set it.
+    pub fn set_sid_type(&self, sid_type: ServiceSidType) -> Result<(), UpdateServiceError> {
+        let function = "set_sid_type";
+        let handle = self.handle.ok_or(UpdateServiceError::InvalidHandle(
+            0,
+            format!("[{function}] invalid service handle"),
+        ))?;
+        self.check_access::<UpdateServiceError>(
+            ServiceAccess::QUERY_CONFIG | ServiceAccess::CHANGE_CONFIG,
+            function,
+        )?;
+        let config = self.query_base_config(function)?;
+        if config.service_type.is_driver() && sid_type != ServiceSidType::None {
+            return Err(UpdateServiceError::InvalidConfig(
+                ERROR_INVALID_PARAMETER,
+                self.context(format!("[{function}] drivers can't have a service SID")),
+            ));
+        }
+        if config.service_type == ServiceType::Win32ShareProcess {
+            self.check_shared_sid_type(function, &config.binary_path, sid_type)?;
+        }
+
+        self.backend
+            .change_service_sid_type(handle, sid_type as u32)
+            .map_err(|err| {
+                UpdateServiceError::from((
+                    err,
+                    self.context(format!("[{function}] ChangeServiceConfig2W failed")),
+                ))
+            })
+    }
+
+    /// Checks that the running services hosted by `binary_path` are restricted exactly when
+    /// `sid_type` is, as the service control manager won't start the service into a process
+    /// hosting the other kind.
+    #[doc(hidden)]
+    fn check_shared_sid_type(
+        &self,
+        function: &str,
+        binary_path: &str,
+        sid_type: ServiceSidType,
+    ) -> Result<(), UpdateServiceError> {
+        let (Some(manager), Some(service_name)) = (&self.manager, &self.service_name) else {
+            return Ok(());
+        };
+        let entries = self
+            .backend
+            .enum_services_status_ex(
+                manager.handle,
+                SERVICE_WIN32_SHARE_PROCESS,
+                SERVICE_ACTIVE,
+                None,
+            )
+            .map_err(|err| {
+                UpdateServiceError::from((
+                    err,
+                    self.context(format!("[{function}] EnumServicesStatusExW failed")),
+                ))
+            })?;
+
+        // A service deleted since the enumeration, or one the caller can't read, is left for
+        // the SCM to check when the process starts.
+        let skipped = |err: u32| {
+            matches!(
+                err,
+                ERROR_ACCESS_DENIED
+                    | ERROR_SERVICE_DOES_NOT_EXIST
+                    | ERROR_SERVICE_MARKED_FOR_DELETE
+            )
+        };
+        let unreadable = |err: &QueryServiceError| match err {
+            QueryServiceError::AccessDenied(..) => true,
+            QueryServiceError::Unknown(err, _) => skipped(*err),
+            _ => false,
+        };
+        for entry in entries
+            .iter()
+            .filter(|entry| !entry.service_name.eq_ignore_ascii_case(service_name))
+        {
+            let handle = match self.backend.open_service(
+                manager.handle,
+                &entry.service_name,
+                ServiceAccess::QUERY_CONFIG.bits(),
+            ) {
+                Ok(handle) => handle,
+                Err(err) if skipped(err) => continue,
+                Err(err) => {
+                    return Err(UpdateServiceError::from((
+                        err,
+                        self.context(format!("[{function}] can't open {}", entry.service_name)),
+                    )))
+                }
+            };
+            let other = ServiceHandle::with_backend(self.backend.clone(), handle)
+                .with_access(ServiceAccess::QUERY_CONFIG)
+                .with_manager(manager.clone(), &entry.service_name);
+            let config = match other.query_base_config(function) {
+                Ok(config) => config,
+                Err(err) if unreadable(&err) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if !config.binary_path.eq_ignore_ascii_case(binary_path) {
+                continue;
+            }
+            let other_sid_type = match other.sid_type() {
+                Ok(sid_type) => sid_type,
+                Err(err) if unreadable(&err) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if (other_sid_type == ServiceSidType::Restricted)
+                != (sid_type == ServiceSidType::Restricted)
+            {
+                return Err(UpdateServiceError::InvalidConfig(
+                    ERROR_INVALID_PARAMETER,
+                    self.context(format!(
+                        "[{function}] SID type {sid_type:?} conflicts with {other_sid_type:?} of {}, running in the same process",
+                        entry.service_name
+                    )),
+                ));
+            }
         }
         Ok(())
     }
+
+    /// Returns the privileges the service keeps in its token, or an empty list if it keeps
+    /// every privilege of its account.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't query the privileges or one of them is
+    /// unknown.
+    pub fn required_privileges(&self) -> Result<Vec<Privilege>, QueryServiceError> {
+        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
+            0,
+            "[required_privileges] invalid service handle".to_string(),
+        ))?;
+        self.check_access::<QueryServiceError>(ServiceAccess::QUERY_CONFIG, "required_privileges")?;
+        self.backend
+            .query_service_required_privileges(handle)
+            .map_err(|err| {
+                QueryServiceError::from((
+                    err,
+                    self.context("[required_privileges] QueryServiceConfig2W failed".to_string()),
+                ))
+            })?
+            .iter()
+            .map(|name| {
+                name.parse().map_err(|_| {
+                    QueryServiceError::from((
+                        0,
+                        self.context(format!("[required_privileges] unknown privilege {name:?}")),
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Strips every privilege but `privileges` from the token of the service when it starts.
+    /// [`Privilege::ChangeNotify`] is always kept, and an empty slice restores every privilege
+    /// of the account. Privileges the account doesn't hold make the service fail to start.
+    ///
+    /// This needs a handle with [`ServiceAccess::QUERY_CONFIG`] besides
+    /// [`ServiceAccess::CHANGE_CONFIG`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service is a driver or it can't set the
+    /// privileges.
+    pub fn set_required_privileges(
+        &self,
+        privileges: &[Privilege],
+    ) -> Result<(), UpdateServiceError> {
+        let function = "set_required_privileges";
+        let handle = self.handle.ok_or(UpdateServiceError::InvalidHandle(
+            0,
+            format!("[{function}] invalid service handle"),
+        ))?;
+        self.check_access::<UpdateServiceError>(
+            ServiceAccess::QUERY_CONFIG | ServiceAccess::CHANGE_CONFIG,
+            function,
+        )?;
+        let config = self.query_base_config(function)?;
+        if config.service_type.is_driver() && !privileges.is_empty() {
+            return Err(UpdateServiceError::InvalidConfig(
+                ERROR_INVALID_PARAMETER,
+                self.context(format!(
+                    "[{function}] drivers run in the kernel and can't drop privileges"
+                )),
+            ));
+        }
+
+        let mut privileges = privileges.to_vec();
+        privileges.sort();
+        privileges.dedup();
+        let names: Vec<String> = privileges
+            .iter()
+            .map(|privilege| privilege.name().to_string())
+            .collect();
+        self.backend
+            .change_service_required_privileges(handle, &names)
+            .map_err(|err| {
+                UpdateServiceError::from((
+                    err,
+                    self.context(format!("[{function}] ChangeServiceConfig2W failed")),
+                ))
+            })
+    }
+
+    /// Returns the triggers that start or stop the service when a system event occurs.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't query the service triggers.
+    pub fn triggers(&self) -> Result<Vec<ServiceTrigger>, QueryServiceError> {
+        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
+            0,
+            "[triggers] invalid service handle".to_string(),
+        ))?;
+        self.check_access::<QueryServiceError>(ServiceAccess::QUERY_CONFIG, "triggers")?;
+        let triggers = self.backend.query_service_triggers(handle).map_err(|err| {
+            QueryServiceError::from((
+                err,
+                self.context("[triggers] QueryServiceConfig2W failed".to_string()),
+            ))
+        })?;
+        Ok(triggers.into_iter().map(ServiceTrigger::from).collect())
+    }
+
+    /// Replaces the triggers of the service. Passing an empty slice removes them all.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a trigger is invalid or it can't set them.
+    pub fn set_triggers(&self, triggers: &[ServiceTrigger]) -> Result<(), UpdateServiceError> {
+        let function = "set_triggers";
+        let handle = self.handle.ok_or(UpdateServiceError::InvalidHandle(
+            0,
+            format!("[{function}] invalid service handle"),
+        ))?;
+        for (index, trigger) in triggers.iter().enumerate() {
+            let invalid = |reason: &str| {
+                UpdateServiceError::InvalidConfig(
+                    ERROR_INVALID_PARAMETER,
+                    self.context(format!("[{function}] trigger {index} {reason}")),
+                )
+            };
+            for data_item in &trigger.data_items {
+                match data_item {
+                    TriggerDataItem::String(entries)
+                        if entries.iter().any(|entry| entry.contains('\0')) =>
+                    {
+                        return Err(invalid("has a string with a NUL character"));
+                    }
+                    TriggerDataItem::Level(_)
+                    | TriggerDataItem::KeywordAny(_)
+                    | TriggerDataItem::KeywordAll(_)
+                        if !matches!(trigger.event, TriggerEvent::Custom(_)) =>
+                    {
+                        return Err(invalid(
+                            "has level or keyword data but isn't a custom ETW event",
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.check_access::<UpdateServiceError>(ServiceAccess::CHANGE_CONFIG, function)?;
+
+        let raw: Vec<RawServiceTrigger> = triggers.iter().map(RawServiceTrigger::from).collect();
+        self.backend
+            .change_service_triggers(handle, &raw)
+            .map_err(|err| {
+                UpdateServiceError::from((
+                    err,
+                    self.context(format!("[{function}] ChangeServiceConfig2W failed")),
+                ))
+            })
+    }
     pub fn delete(&self) -> Result<(), DeleteServiceError> {
         let handle = self.handle.ok_or(DeleteServiceError::InvalidHandle(
             0,
             "[delete] invalid service handle".to_string(),
         ))?;
-        unsafe {
-            if DeleteService(handle) == FALSE {
-                return Err(DeleteServiceError::from((
-                    get_last_error(),
-                    "[delete] DeleteService failed".to_string(),
-                )));
-            }
-        }
-        Ok(())
+        self.check_access::<DeleteServiceError>(ServiceAccess::DELETE, "delete")?;
+        self.backend.delete_service(handle).map_err(|err| {
+            DeleteServiceError::from((
+                err,
+                self.context("[delete] DeleteService failed".to_string()),
+            ))
+        })
     }
 
     /// Starts the service and blocks until it is running.
     ///
     /// # Errors
     ///
-    /// This function will return an error if it can't start the service.
+    /// This function will return an error if it can't start the service, if the service stops
+    /// advancing its checkpoint or if it doesn't end up running.
     pub fn start_blocking(&self) -> Result<(), ControlServiceError> {
-        self.control_blocking(ServiceState::Running, || self.start())
+        self.control_blocking("start_blocking", ServiceState::Running, None, || {
+            self.start()
+        })
+    }
+
+    /// Starts the service and blocks until it is running, for at most `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't start the service, if the service stops
+    /// advancing its checkpoint, if it doesn't end up running or if `timeout` elapses.
+    pub fn start_blocking_timeout(&self, timeout: Duration) -> Result<(), ControlServiceError> {
+        self.control_blocking(
+            "start_blocking",
+            ServiceState::Running,
+            Some(timeout),
+            || self.start(),
+        )
     }
 
     /// Stops the service and blocks until it is stopped.
     ///
     /// # Errors
     ///
-    /// This function will return an error if it can't stop the service.
+    /// This function will return an error if it can't stop the service, if the service stops
+    /// advancing its checkpoint or if it doesn't end up stopped.
     pub fn stop_blocking(&self) -> Result<(), ControlServiceError> {
-        self.control_blocking(ServiceState::Stopped, || self.stop())
+        self.control_blocking("stop_blocking", ServiceState::Stopped, None, || self.stop())
+    }
+
+    /// Stops the service and blocks until it is stopped, for at most `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't stop the service, if the service stops
+    /// advancing its checkpoint, if it doesn't end up stopped or if `timeout` elapses.
+    pub fn stop_blocking_timeout(&self, timeout: Duration) -> Result<(), ControlServiceError> {
+        self.control_blocking(
+            "stop_blocking",
+            ServiceState::Stopped,
+            Some(timeout),
+            || self.stop(),
+        )
+    }
+
+    /// Blocks until the service reaches `service_state` without sending it a control, for at
+    /// most `timeout`, such as after [`stop_with_reason`](Self::stop_with_reason).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service stops advancing its checkpoint, if it
+    /// settles in another state or if `timeout` elapses.
+    pub fn wait_for_state(
+        &self,
+        service_state: ServiceState,
+        timeout: Duration,
+    ) -> Result<(), ControlServiceError> {
+        self.control_blocking("wait_for_state", service_state, Some(timeout), || Ok(()))
     }
 
     /// Pauses the service and blocks until it is paused.
     ///
     /// # Errors
     ///
-    /// This function will return an error if it can't pause the service.
+    /// This function will return an error if it can't pause the service, if the service stops
+    /// advancing its checkpoint or if it doesn't end up paused.
     pub fn pause_blocking(&self) -> Result<(), ControlServiceError> {
-        self.control_blocking(ServiceState::Paused, || self.pause())
+        self.control_blocking("pause_blocking", ServiceState::Paused, None, || {
+            self.pause()
+        })
+    }
+
+    /// Pauses the service and blocks until it is paused, for at most `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't pause the service, if the service stops
+    /// advancing its checkpoint, if it doesn't end up paused or if `timeout` elapses.
+    pub fn pause_blocking_timeout(&self, timeout: Duration) -> Result<(), ControlServiceError> {
+        self.control_blocking(
+            "pause_blocking",
+            ServiceState::Paused,
+            Some(timeout),
+            || self.pause(),
+        )
+    }
+
+    /// Stops the running dependents of the service, then the service itself, and returns the
+    /// dependents that were stopped, in the order they were stopped.
+    ///
+    /// Dependents are stopped before the services they depend on, waiting for each one. Pass
+    /// the returned names to [`ServiceHandle::start_dependents`] to bring them back.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't enumerate or open the dependents, or if
+    /// one of them or the service itself doesn't stop.
+    pub fn stop_with_dependents(&self) -> Result<Vec<String>, ControlServiceError> {
+        let mut report = RestartReport::default();
+        self.stop_dependents(
+            "stop_with_dependents",
+            &RestartOptions::default(),
+            &mut report,
+        )?;
+        self.stop_blocking()?;
+        Ok(report.stopped_dependents)
+    }
+
+    /// Stops the running dependents of the service, leaves first, as configured by `options`.
+    /// Each one is added to `report` as soon as it is stopped, so that it is known even if a
+    /// later one fails.
+    #[doc(hidden)]
+    fn stop_dependents(
+        &self,
+        function: &str,
+        options: &RestartOptions,
+        report: &mut RestartReport,
+    ) -> Result<(), ControlServiceError> {
+        for dependent in self.all_dependents(ServiceStateFilter::Active)? {
+            let dependent_handle = self.open_dependent(
+                function,
+                &dependent.service_name,
+                ServiceAccess::STOP | ServiceAccess::QUERY_STATUS,
+            )?;
+            // It stopped on its own since the enumeration.
+            if dependent_handle.state()? == ServiceState::Stopped {
+                continue;
+            }
+            match dependent_handle.stop_for_restart(function, options) {
+                Ok(killed) => {
+                    if let Some(process_id) = killed {
+                        report
+                            .killed_dependents
+                            .push((dependent.service_name.clone(), process_id));
+                    }
+                    report.stopped_dependents.push(dependent.service_name);
+                }
+                Err(ControlServiceError::ServiceNotActive(..)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops the service if it is running and starts it again, as configured by `options`.
+    ///
+    /// A pending start, pause or continue is waited for before stopping, and a pending stop is
+    /// waited for as if the service had been asked to stop.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a dependent or the service doesn't stop, even
+    /// after terminating its process when [`RestartOptions::force_kill`] is set, or if one of
+    /// them doesn't start again. The dependents stopped by then are started again where
+    /// possible, and the error carries the partial report and those left stopped.
+    pub fn restart(&self, options: &RestartOptions) -> Result<RestartReport, RestartError> {
+        let mut report = RestartReport::default();
+        match self.restart_phases(options, &mut report) {
+            Ok(()) => Ok(report),
+            Err(error) => {
+                // The service may well still be running, so bring back what can be.
+                let mut left_stopped: Vec<String> = report
+                    .stopped_dependents
+                    .iter()
+                    .rev()
+                    .filter(|service_name| {
+                        self.start_dependents_within(
+                            "restart",
+                            std::slice::from_ref(*service_name),
+                            options.start_timeout,
+                        )
+                        .is_err()
+                    })
+                    .cloned()
+                    .collect();
+                left_stopped.reverse();
+                Err(RestartError {
+                    error,
+                    report: Box::new(report),
+                    left_stopped,
+                })
+            }
+        }
+    }
+
+    #[doc(hidden)]
+    fn restart_phases(
+        &self,
+        options: &RestartOptions,
+        report: &mut RestartReport,
+    ) -> Result<(), ControlServiceError> {
+        let phase = Instant::now();
+        if options.restart_dependents {
+            self.stop_dependents("restart", options, report)?;
+        }
+        report.stop_dependents = phase.elapsed();
+
+        let phase = Instant::now();
+        report.killed_process_id = self.stop_for_restart("restart", options)?;
+        report.stop = phase.elapsed();
+
+        let phase = Instant::now();
+        self.control_blocking(
+            "restart",
+            ServiceState::Running,
+            options.start_timeout,
+            || self.start_service("restart", &[]),
+        )?;
+        report.start = phase.elapsed();
+
+        let phase = Instant::now();
+        self.start_dependents_within("restart", &report.stopped_dependents, options.start_timeout)?;
+        report.start_dependents = phase.elapsed();
+        Ok(())
+    }
+
+    /// Stops the service as [`restart`](Self::restart) does, and returns the process it
+    /// terminated, if any.
+    #[doc(hidden)]
+    fn stop_for_restart(
+        &self,
+        function: &str,
+        options: &RestartOptions,
+    ) -> Result<Option<u32>, ControlServiceError> {
+        let timeout = options.stop_timeout;
+        let wait =
+            |service_state| self.control_blocking(function, service_state, timeout, || Ok(()));
+        let stop =
+            || self.control_blocking(function, ServiceState::Stopped, timeout, || self.stop());
+
+        let state = ServiceState::try_from(self.get_status()?.current_state)?;
+        let result = match state {
+            ServiceState::Stopped => return Ok(None),
+            ServiceState::StopPending => wait(ServiceState::Stopped),
+            ServiceState::StartPending | ServiceState::ContinuePending => {
+                wait(ServiceState::Running).and_then(|_| stop())
+            }
+            ServiceState::PausePending => wait(ServiceState::Paused).and_then(|_| stop()),
+            ServiceState::Running | ServiceState::Paused => stop(),
+        };
+
+        match result {
+            Err(err @ (ControlServiceError::TimedOut(..) | ControlServiceError::Stalled(..)))
+                if options.force_kill =>
+            {
+                let process_id = self.kill(function, err)?;
+                wait(ServiceState::Stopped).map(|_| Some(process_id))
+            }
+            result => result.map(|_| None),
+        }
+    }
+
+    /// Terminates the process of a service that failed to stop with `stop_error`, which is
+    /// returned with the reason if the process can't safely be terminated.
+    #[doc(hidden)]
+    fn kill(
+        &self,
+        function: &str,
+        stop_error: ControlServiceError,
+    ) -> Result<u32, ControlServiceError> {
+        let status = self.get_status()?;
+        let refusal = if self.machine_name().is_some() {
+            Some("it runs on a remote machine")
+        } else if status.process_id == 0 {
+            Some("it has no process")
+        } else if status.service_type & SERVICE_WIN32 != SERVICE_WIN32_OWN_PROCESS {
+            Some("it doesn't run in a process of its own")
+        } else if status.service_flags & SERVICE_RUNS_IN_SYSTEM_PROCESS != 0 {
+            Some("it runs in a system process")
+        } else {
+            None
+        };
+        if let Some(reason) = refusal {
+            let not_killed = |context: String| format!("{context}, not terminated since {reason}");
+            return Err(match stop_error {
+                ControlServiceError::TimedOut(err, context) => {
+                    ControlServiceError::TimedOut(err, not_killed(context))
+                }
+                ControlServiceError::Stalled(err, context) => {
+                    ControlServiceError::Stalled(err, not_killed(context))
+                }
+                other => other,
+            });
+        }
+
+        self.backend
+            .terminate_process(status.process_id, ERROR_PROCESS_ABORTED)
+            .map_err(|err| {
+                ControlServiceError::from((
+                    err,
+                    self.context(format!(
+                        "[{function}] TerminateProcess failed for process {}",
+                        status.process_id
+                    )),
+                ))
+            })?;
+        Ok(status.process_id)
+    }
+
+    /// Starts the dependents returned by [`ServiceHandle::stop_with_dependents`] again, in the
+    /// reverse order they were stopped, and waits for each one to run.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't open a dependent or one doesn't start.
+    pub fn start_dependents(&self, dependents: &[String]) -> Result<(), ControlServiceError> {
+        self.start_dependents_within("start_dependents", dependents, None)
+    }
+
+    /// Starts `dependents` as [`start_dependents`](Self::start_dependents) does, waiting at
+    /// most `timeout` for each one.
+    #[doc(hidden)]
+    fn start_dependents_within(
+        &self,
+        function: &str,
+        dependents: &[String],
+        timeout: Option<Duration>,
+    ) -> Result<(), ControlServiceError> {
+        for service_name in dependents.iter().rev() {
+            let dependent_handle = self.open_dependent(
+                function,
+                service_name,
+                ServiceAccess::START | ServiceAccess::QUERY_STATUS,
+            )?;
+            let started =
+                dependent_handle.control_blocking(function, ServiceState::Running, timeout, || {
+                    dependent_handle.start_service(function, &[])
+                });
+            match started {
+                // Starting a later dependent already started it as a dependency.
+                Ok(()) | Err(ControlServiceError::ServiceAlreadyRunning(..)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens a service through the manager this one came from.
+    #[doc(hidden)]
+    fn open_dependent(
+        &self,
+        function: &str,
+        service_name: &str,
+        access: ServiceAccess,
+    ) -> Result<ServiceHandle, ControlServiceError> {
+        let Some(manager) = &self.manager else {
+            return Err(ControlServiceError::InvalidHandle(
+                0,
+                format!("[{function}] service wasn't opened through a ServiceManager"),
+            ));
+        };
+        let handle = self
+            .backend
+            .open_service(manager.handle, service_name, access.bits())
+            .map_err(|err| {
+                ControlServiceError::from((
+                    err,
+                    self.context(format!("[{function}] can't open {service_name}")),
+                ))
+            })?;
+
+        Ok(ServiceHandle::with_backend(self.backend.clone(), handle)
+            .with_access(access)
+            .with_manager(manager.clone(), service_name))
+    }
+
+    /// Resumes the service and blocks until it is running.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't resume the service, if the service stops
+    /// advancing its checkpoint or if it doesn't end up running.
+    pub fn resume_blocking(&self) -> Result<(), ControlServiceError> {
+        self.control_blocking("resume_blocking", ServiceState::Running, None, || {
+            self.resume()
+        })
+    }
+
+    /// Resumes the service and blocks until it is running, for at most `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't resume the service, if the service stops
+    /// advancing its checkpoint, if it doesn't end up running or if `timeout` elapses.
+    pub fn resume_blocking_timeout(&self, timeout: Duration) -> Result<(), ControlServiceError> {
+        self.control_blocking(
+            "resume_blocking",
+            ServiceState::Running,
+            Some(timeout),
+            || self.resume(),
+        )
     }
 
     /// Starts the service.
@@ -344,20 +2347,74 @@ impl ServiceHandle {
     ///
     /// This function will return an error if it can't start the service.
     pub fn start(&self) -> Result<(), ControlServiceError> {
+        self.start_service("start", &[])
+    }
+
+    /// Starts the service, passing `arguments` to its `ServiceMain` after the service name.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an argument contains a null character or it can't
+    /// start the service.
+    pub fn start_with_args<I, S>(&self, arguments: I) -> Result<(), ControlServiceError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.start_service("start_with_args", &collect_arguments(arguments))
+    }
+
+    /// Starts the service with `arguments` and blocks until it is running.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an argument contains a null character, it can't
+    /// start the service, the service stops advancing its checkpoint or it doesn't end up running.
+    pub fn start_with_args_blocking<I, S>(&self, arguments: I) -> Result<(), ControlServiceError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let arguments = collect_arguments(arguments);
+        self.control_blocking(
+            "start_with_args_blocking",
+            ServiceState::Running,
+            None,
+            || self.start_service("start_with_args_blocking", &arguments),
+        )
+    }
+
+    #[doc(hidden)]
+    fn start_service(
+        &self,
+        function: &str,
+        arguments: &[String],
+    ) -> Result<(), ControlServiceError> {
         let handle = self.handle.ok_or(ControlServiceError::InvalidHandle(
             0,
-            "[start] invalid service handle".to_string(),
+            format!("[{function}] invalid service handle"),
         ))?;
-        unsafe {
-            if StartServiceW(handle, 0, std::ptr::null()) == FALSE {
-                return Err(ControlServiceError::from((
-                    get_last_error(),
-                    "[start] StartServiceW failed".to_string(),
-                )));
-            }
+        self.check_access::<ControlServiceError>(ServiceAccess::START, function)?;
+        if let Some(index) = arguments
+            .iter()
+            .position(|argument| argument.contains('\0'))
+        {
+            return Err(ControlServiceError::InvalidParameter(
+                ERROR_INVALID_PARAMETER,
+                self.context(format!(
+                    "[{function}] argument {index} contains a null character"
+                )),
+            ));
         }
 
-        Ok(())
+        self.backend
+            .start_service(handle, arguments)
+            .map_err(|err| {
+                ControlServiceError::from((
+                    err,
+                    self.context(format!("[{function}] StartServiceW failed")),
+                ))
+            })
     }
 
     /// Stops the service.
@@ -366,7 +2423,50 @@ impl ServiceHandle {
     ///
     /// This function will return an error if it can't stop the service.
     pub fn stop(&self) -> Result<(), ControlServiceError> {
-        self.control(SERVICE_CONTROL_STOP)
+        self.control("stop", SERVICE_CONTROL_STOP)
+    }
+
+    /// Stops the service, recording `reason` and `comment` in the system event log, and returns
+    /// the status the service reported in response.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `comment` is 128 UTF-16 units or longer, since the
+    /// limit includes the terminating NUL, or contains a NUL character, or if it can't stop the
+    /// service.
+    pub fn stop_with_reason(
+        &self,
+        reason: StopReason,
+        comment: Option<&str>,
+    ) -> Result<ServiceStatus, ControlServiceError> {
+        let function = "stop_with_reason";
+        if let Some(comment) = comment {
+            let problem = if comment.contains('\0') {
+                Some("contains a NUL character")
+            } else if comment.encode_utf16().count() >= MAX_SERVICE_STOP_COMMENT_LEN {
+                Some("is 128 characters or longer")
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                return Err(ControlServiceError::InvalidParameter(
+                    ERROR_INVALID_PARAMETER,
+                    self.context(format!("[{function}] the comment {problem}")),
+                ));
+            }
+        }
+        let handle = self.check_control(function, SERVICE_CONTROL_STOP)?;
+
+        let status = self
+            .backend
+            .control_service_with_reason(handle, SERVICE_CONTROL_STOP, reason.bits(), comment)
+            .map_err(|err| {
+                ControlServiceError::from((
+                    err,
+                    self.context(format!("[{function}] ControlServiceExW failed")),
+                ))
+            })?;
+        Ok(ServiceStatus::try_from(status)?)
     }
 
     /// Pauses the service.
@@ -375,107 +2475,238 @@ impl ServiceHandle {
     ///
     /// This function will return an error if it can't pause the service.
     pub fn pause(&self) -> Result<(), ControlServiceError> {
-        self.control(SERVICE_CONTROL_PAUSE)
+        self.control("pause", SERVICE_CONTROL_PAUSE)
+    }
+
+    /// Resumes a paused service.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't resume the service.
+    pub fn resume(&self) -> Result<(), ControlServiceError> {
+        self.control("resume", SERVICE_CONTROL_CONTINUE)
+    }
+
+    /// Asks the service to report its current status to the service control manager.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't interrogate the service.
+    pub fn interrogate(&self) -> Result<(), ControlServiceError> {
+        self.control("interrogate", SERVICE_CONTROL_INTERROGATE)
+    }
+
+    /// Tells the service that its startup parameters changed and should be reread.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't notify the service.
+    pub fn param_change(&self) -> Result<(), ControlServiceError> {
+        self.control("param_change", SERVICE_CONTROL_PARAMCHANGE)
+    }
+
+    /// Tells a network service that one of its bindings changed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't notify the service.
+    pub fn netbind_change(&self, change: NetBindChange) -> Result<(), ControlServiceError> {
+        self.control("netbind_change", change as u32)
     }
+
+    /// Sends a control code the service defines itself, in the `128..=255` range.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `control` is out of range or it can't send it.
+    pub fn user_control(&self, control: u32) -> Result<(), ControlServiceError> {
+        if !(128..=255).contains(&control) {
+            return Err(ControlServiceError::InvalidServiceControl(
+                ERROR_INVALID_SERVICE_CONTROL,
+                self.context(format!(
+                    "[user_control] control code {control} is outside 128..=255"
+                )),
+            ));
+        }
+        self.control("user_control", control)
+    }
+
+    /// Sends `control`, checking first that the service accepts it when the handle is allowed
+    /// to query its status.
     #[doc(hidden)]
-    fn control(&self, control: u32) -> Result<(), ControlServiceError> {
+    fn control(&self, function: &str, control: u32) -> Result<(), ControlServiceError> {
+        let handle = self.check_control(function, control)?;
+
+        self.backend
+            .control_service(handle, control)
+            .map(|_| ())
+            .map_err(|err| {
+                ControlServiceError::from((
+                    err,
+                    self.context(format!("[{function}] ControlService failed")),
+                ))
+            })
+    }
+
+    /// Checks that the handle may send `control` and, when it is allowed to query the status,
+    /// that the service accepts it. Returns the handle to send it with.
+    #[doc(hidden)]
+    fn check_control(&self, function: &str, control: u32) -> Result<ScHandle, ControlServiceError> {
         let handle = self.handle.ok_or(ControlServiceError::InvalidHandle(
             0,
-            "[control] invalid service handle".to_string(),
+            format!("[{function}] invalid service handle"),
         ))?;
+        self.check_access::<ControlServiceError>(control_access(control), function)?;
 
-        unsafe {
-            let mut service_status = std::mem::zeroed::<SERVICE_STATUS>();
-
-            if ControlService(handle, control, &mut service_status) == FALSE {
-                return Err(ControlServiceError::from((
-                    get_last_error(),
-                    "[control] ControlService failed".to_string(),
-                )));
+        let accepted = control_accepted(control);
+        if !accepted.is_empty() && self.access.contains(ServiceAccess::QUERY_STATUS) {
+            let status = self.backend.query_service_status(handle).map_err(|err| {
+                ControlServiceError::from((
+                    err,
+                    self.context(format!("[{function}] failed to get service state")),
+                ))
+            })?;
+            let state = ServiceState::try_from(status.current_state).ok();
+            if let Some(state) = state.as_ref().filter(|state| state.is_pending()) {
+                return Err(ControlServiceError::CannotAcceptControl(
+                    ERROR_SERVICE_CANNOT_ACCEPT_CTRL,
+                    self.context(format!("[{function}] service is {state}")),
+                ));
+            }
+            // A stopped service accepts no control, which ControlService reports itself.
+            let controls = ServiceControls::from_bits_retain(status.controls_accepted);
+            if state != Some(ServiceState::Stopped) && !controls.contains(accepted) {
+                return Err(ControlServiceError::InvalidServiceControl(
+                    ERROR_INVALID_SERVICE_CONTROL,
+                    self.context(format!(
+                        "[{function}] service doesn't accept {accepted}, only {controls}"
+                    )),
+                ));
             }
         }
 
-        Ok(())
+        Ok(handle)
     }
+
+    /// Runs `control_fn` and waits for the service to reach `service_state`, following the
+    /// `dwCheckPoint`/`dwWaitHint` protocol: the service is considered hung once its checkpoint
+    /// hasn't advanced for longer than its wait hint, or a second if that is shorter.
     #[doc(hidden)]
     fn control_blocking<F>(
         &self,
+        function: &str,
         service_state: ServiceState,
+        timeout: Option<Duration>,
         control_fn: F,
     ) -> Result<(), ControlServiceError>
     where
         F: Fn() -> Result<(), ControlServiceError>,
     {
+        let handle = self.handle.ok_or(ControlServiceError::InvalidHandle(
+            0,
+            format!("[{function}] invalid service handle"),
+        ))?;
+        self.check_access::<ControlServiceError>(ServiceAccess::QUERY_STATUS, function)?;
+
         control_fn()?;
 
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut progress = None;
+        let mut last_progress = Instant::now();
+        let mut failures = 0;
         loop {
-            if let Ok(status) = self.state() {
-                if status == service_state {
-                    break;
+            let mut wait_hint = 0;
+            match self.backend.query_service_status(handle) {
+                // A failed query may be transient, so only several in a row are fatal.
+                Err(err) => {
+                    failures += 1;
+                    if failures >= MAX_QUERY_FAILURES {
+                        return Err(ControlServiceError::from((
+                            err,
+                            self.context(format!("[{function}] failed to get service state")),
+                        )));
+                    }
                 }
-            } else {
-                return Err(ControlServiceError::Unknown(
-                    get_last_error(),
-                    "[control_blocking] failed to get service state".to_string(),
-                ));
-            }
+                Ok(status) => {
+                    failures = 0;
+                    let state = ServiceState::try_from(status.current_state).map_err(|_| {
+                        ControlServiceError::UnexpectedState(
+                            status.win32_exit_code,
+                            self.context(format!(
+                                "[{function}] unknown service state {}",
+                                status.current_state
+                            )),
+                        )
+                    })?;
+                    if state == service_state {
+                        return Ok(());
+                    }
+                    if !state.is_pending() {
+                        return Err(ControlServiceError::UnexpectedState(
+                            status.win32_exit_code,
+                            self.context(format!(
+                                "[{function}] service is {state} instead of {service_state}{}",
+                                exit_code_suffix(&status)
+                            )),
+                        ));
+                    }
 
-            std::thread::sleep(std::time::Duration::from_millis(100))
-        }
-
-        Ok(())
-    }
-    #[doc(hidden)]
-    fn get_config(&self) -> Result<QUERY_SERVICE_CONFIGW, QueryServiceError> {
-        let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
-            0,
-            "[get_config] invalid service handle".to_string(),
-        ))?;
-        unsafe {
-            let mut bytes_needed: u32 = 0;
-
-            if QueryServiceConfigW(handle, std::ptr::null_mut(), 0, &mut bytes_needed) == FALSE
-                && get_last_error() != ERROR_INSUFFICIENT_BUFFER
-            {
-                return Err(QueryServiceError::from((
-                    get_last_error(),
-                    "[get_config] QueryServiceConfig failed".to_string(),
-                )));
+                    let current = Some((state.clone(), status.check_point));
+                    let stall_window =
+                        Duration::from_millis(status.wait_hint.into()).max(MIN_STALL_WINDOW);
+                    if progress != current {
+                        progress = current;
+                        last_progress = Instant::now();
+                    } else if last_progress.elapsed() > stall_window {
+                        return Err(ControlServiceError::Stalled(
+                            ERROR_SERVICE_REQUEST_TIMEOUT,
+                            self.context(format!(
+                                "[{function}] service stayed {state} at checkpoint {} for longer than {stall_window:?} with a {} ms wait hint",
+                                status.check_point, status.wait_hint
+                            )),
+                        ));
+                    }
+                    wait_hint = status.wait_hint;
+                }
             }
 
-            let config_buffer = vec![0u8; bytes_needed as usize];
-            let config = config_buffer.as_ptr() as *mut QUERY_SERVICE_CONFIGW;
-
-            if QueryServiceConfigW(
-                handle,
-                config,
-                config_buffer.len() as u32,
-                &mut bytes_needed,
-            ) == FALSE
-            {
-                return Err(QueryServiceError::from((
-                    get_last_error(),
-                    "[get_config] QueryServiceConfig failed".to_string(),
-                )));
+            // Windows recommends waiting a tenth of the wait hint between queries.
+            let mut interval = (Duration::from_millis(wait_hint.into()) / 10)
+                .clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL);
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    let waited = timeout.unwrap_or_default();
+                    let context = match &progress {
+                        Some((state, check_point)) => format!(
+                            "[{function}] service still {state} at checkpoint {check_point} after {waited:?}"
+                        ),
+                        None => format!(
+                            "[{function}] service didn't become {service_state} within {waited:?}"
+                        ),
+                    };
+                    return Err(ControlServiceError::TimedOut(
+                        ERROR_TIMEOUT,
+                        self.context(context),
+                    ));
+                }
+                interval = interval.min(remaining);
             }
-            Ok(*config)
+            std::thread::sleep(interval);
         }
     }
     #[doc(hidden)]
-    fn get_status(&self) -> Result<SERVICE_STATUS, QueryServiceError> {
+    fn get_status(&self) -> Result<RawServiceStatus, QueryServiceError> {
         let handle = self.handle.ok_or(QueryServiceError::InvalidHandle(
             0,
             "[get_status] invalid service handle".to_string(),
         ))?;
-        unsafe {
-            let mut status = std::mem::zeroed::<SERVICE_STATUS>();
-            if QueryServiceStatus(handle, &mut status) == FALSE {
-                return Err(QueryServiceError::from((
-                    get_last_error(),
-                    "[get_status] QueryServiceConfig failed".to_string(),
-                )));
-            }
-            Ok(status)
-        }
+        self.check_access::<QueryServiceError>(ServiceAccess::QUERY_STATUS, "get_status")?;
+        self.backend.query_service_status(handle).map_err(|err| {
+            QueryServiceError::from((
+                err,
+                self.context("[get_status] QueryServiceStatusEx failed".to_string()),
+            ))
+        })
     }
 }
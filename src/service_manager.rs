@@ -1,19 +1,29 @@
 //! This module provides functionalities for managing Windows services.
 //! It includes structs for service configuration and a `ServiceManager` for creating, retrieving, and managing services.
 
-use widestring::U16CString;
-use windows_sys::Win32::{
-    Security::SC_HANDLE,
-    System::Services::{
-        CloseServiceHandle, CreateServiceW, OpenSCManagerW, OpenServiceW, SC_MANAGER_ALL_ACCESS,
-        SERVICE_ALL_ACCESS,
-    },
-};
+use std::sync::Arc;
 
+#[cfg(windows)]
+use crate::win32::Win32Backend;
 use crate::{
-    common::{get_last_error, set_privilege},
-    error::{CreateServiceError, OpenServiceError, ServiceManagerError},
-    service::{ServiceErrorControl, ServiceHandle, ServiceStartType, ServiceType},
+    access::{ScmAccess, ServiceAccess},
+    account::ServiceAccount,
+    backend::{RawServiceEntry, ScHandle, ScmBackend},
+    consts::{
+        ERROR_ACCESS_DENIED, ERROR_DUPLICATE_SERVICE_NAME, ERROR_INVALID_PARAMETER,
+        ERROR_SERVICE_DOES_NOT_EXIST, SERVICE_ACTIVE, SERVICE_DRIVER, SERVICE_INACTIVE,
+        SERVICE_STATE_ALL, SERVICE_WIN32,
+    },
+    error::{
+        CreateServiceError, OpenServiceError, PrivilegeError, QueryServiceError,
+        ServiceManagerError,
+    },
+    notify::{ServiceEvents, ServiceNotify},
+    privilege::{Privilege, PrivilegeGuard},
+    service::{
+        ServiceDependency, ServiceDescription, ServiceErrorControl, ServiceHandle,
+        ServiceStartType, ServiceStatus, ServiceType,
+    },
 };
 
 /// Configuration for a Windows service.
@@ -25,30 +35,219 @@ pub struct ServiceConfig {
     pub service_type: ServiceType,
     pub start_type: ServiceStartType,
     pub error_control: ServiceErrorControl,
+    pub load_order_group: Option<String>,
+    /// Asks the SCM for a tag within `load_order_group`, see [`ServiceHandle::tag_id`].
+    pub request_tag: bool,
+    pub dependencies: Vec<ServiceDependency>,
+    pub account: Option<ServiceAccount>,
+    pub password: Option<String>,
+    /// Set with `ChangeServiceConfig2W` right after the service is created.
+    pub description: Option<ServiceDescription>,
 }
 
-/// Manages Windows services, providing functionalities to create, retrieve, and control services.
-pub struct ServiceManager {
-    handle: Option<SC_HANDLE>,
-}
+impl ServiceConfig {
+    /// Checks the configuration for values and combinations that `CreateServiceW` rejects.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error describing the first invalid setting.
+    pub fn validate(&self) -> Result<(), CreateServiceError> {
+        if self.service_name.is_empty() || self.service_name.contains(['\0', '/', '\\']) {
+            return Err(CreateServiceError::InvalidName(
+                0,
+                "[create_service] invalid service name".to_string(),
+            ));
+        }
+        if self.display_name.contains('\0') {
+            return Err(CreateServiceError::InvalidName(
+                0,
+                "[create_service] invalid display name".to_string(),
+            ));
+        }
+        let description = self.description.as_ref().map(ToString::to_string);
+        for (field, value) in [
+            ("binary path", Some(&self.binary_path)),
+            ("load order group", self.load_order_group.as_ref()),
+            ("password", self.password.as_ref()),
+            ("description", description.as_ref()),
+        ] {
+            if value.is_some_and(|value| value.contains('\0')) {
+                return Err(CreateServiceError::InvalidParameter(
+                    0,
+                    format!("[create_service] invalid {field}"),
+                ));
+            }
+        }
+        if self
+            .dependencies
+            .iter()
+            .any(|dependency| dependency.to_raw().contains('\0'))
+        {
+            return Err(CreateServiceError::InvalidParameter(
+                0,
+                "[create_service] invalid dependencies".to_string(),
+            ));
+        }
 
-impl Drop for ServiceManager {
-    fn drop(&mut self) {
-        if let Some(handle) = self.handle {
-            unsafe {
-                CloseServiceHandle(handle);
+        let is_driver = self.service_type.is_driver();
+        let invalid = |reason: &str| {
+            Err(CreateServiceError::InvalidConfig(
+                ERROR_INVALID_PARAMETER,
+                format!("[create_service] {reason}"),
+            ))
+        };
+        if !is_driver
+            && matches!(
+                self.start_type,
+                ServiceStartType::BootStart | ServiceStartType::SystemStart
+            )
+        {
+            return invalid("boot and system start are only valid for drivers");
+        }
+        if is_driver && self.account.is_some() {
+            return invalid("drivers can't run under an account");
+        }
+        match (&self.account, &self.password) {
+            (None, Some(_)) => return invalid("a password requires an account"),
+            (Some(account), None) if account.requires_password() => {
+                return invalid(&format!("account {account} requires a password"));
+            }
+            (Some(account), Some(_)) if !account.requires_password() => {
+                return invalid(&format!("account {account} doesn't take a password"));
             }
+            _ => {}
         }
+        if let Some(ServiceAccount::Virtual(name)) = &self.account {
+            if !name.eq_ignore_ascii_case(&self.service_name) {
+                return invalid(&format!(
+                    "virtual account NT SERVICE\\{name} doesn't match service {}",
+                    self.service_name
+                ));
+            }
+        }
+        if self.request_tag {
+            if !is_driver
+                || !matches!(
+                    self.start_type,
+                    ServiceStartType::BootStart | ServiceStartType::SystemStart
+                )
+            {
+                return invalid("tags are only valid for boot and system start drivers");
+            }
+            if self.load_order_group.is_none() {
+                return invalid("a tag requires a load order group");
+            }
+        }
+        if self
+            .dependencies
+            .iter()
+            .any(|dependency| *dependency == ServiceDependency::Service(self.service_name.clone()))
+        {
+            return Err(CreateServiceError::CircularDependency(
+                0,
+                "[create_service] a service can't depend on itself".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Selects services by type when enumerating.
+#[repr(u32)]
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum ServiceTypeFilter {
+    /// Kernel, file system and recognizer drivers.
+    Drivers = SERVICE_DRIVER,
+    /// Own-process and share-process Win32 services.
+    Win32 = SERVICE_WIN32,
+    #[default]
+    All = SERVICE_DRIVER | SERVICE_WIN32,
+}
+
+/// Selects services by state when enumerating.
+#[repr(u32)]
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum ServiceStateFilter {
+    /// Services that aren't stopped, including pending and paused ones.
+    Active = SERVICE_ACTIVE,
+    /// Stopped services.
+    Inactive = SERVICE_INACTIVE,
+    #[default]
+    All = SERVICE_STATE_ALL,
+}
+
+/// Filter applied by [`ServiceManager::enum_services`].
+#[derive(Default, Clone, Debug)]
+pub struct ServiceFilter {
+    pub service_type: ServiceTypeFilter,
+    pub state: ServiceStateFilter,
+    /// Restricts the result to one load order group. `Some("")` selects the services that
+    /// belong to no group and `None` doesn't filter by group.
+    pub load_order_group: Option<String>,
+}
+
+/// A service returned by [`ServiceManager::enum_services`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceEntry {
+    pub service_name: String,
+    pub display_name: String,
+    pub status: ServiceStatus,
+}
+
+/// Service control manager handle shared by a [`ServiceManager`] and the services opened
+/// through it, so that a `ServiceHandle` can open related services after the manager is gone.
+#[derive(Debug)]
+pub(crate) struct ScmConnection {
+    pub(crate) backend: Arc<dyn ScmBackend>,
+    pub(crate) handle: ScHandle,
+    /// The remote machine the connection was made to, `None` for the local machine.
+    pub(crate) machine_name: Option<String>,
+    /// The database the connection was made to, `None` for the active database.
+    pub(crate) database_name: Option<String>,
+}
+
+/// Appends the machine to an error context, so that errors from a remote service control
+/// manager say which host failed.
+#[doc(hidden)]
+pub(crate) fn on_machine(context: String, machine_name: Option<&str>) -> String {
+    match machine_name {
+        Some(machine_name) => format!("{context} on \\\\{machine_name}"),
+        None => context,
     }
 }
+
+impl Drop for ScmConnection {
+    fn drop(&mut self) {
+        self.backend.close_service_handle(self.handle);
+    }
+}
+
+/// Manages Windows services, providing functionalities to create, retrieve, and control services.
+#[derive(Debug)]
+pub struct ServiceManager {
+    backend: Arc<dyn ScmBackend>,
+    connection: Option<Arc<ScmConnection>>,
+    access: ScmAccess,
+    privileges: Vec<PrivilegeGuard>,
+}
 /// # Examples
 ///
+/// The example runs against the in-memory [`FakeScm`](crate::fake::FakeScm);
+/// on Windows, `ServiceManager::new` connects to the real service control manager instead.
+///
 /// ```rust
-/// let service_manager = ServiceManager::new()?;
-
+/// # use std::sync::Arc;
+/// # use scmanager_windows_rs::{
+/// #     fake::FakeScm,
+/// #     service::{ServiceConfigChange, ServiceStartType, ServiceState, ServiceType},
+/// #     service_manager::{ServiceConfig, ServiceManager},
+/// # };
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let service_manager = ServiceManager::with_backend(Arc::new(FakeScm::new()))?;
+///
 /// let service_name = "test";
 /// let service_path = r"C:\Windows\system32\test.sys";
-
+///
 /// let service_handle = service_manager.create_or_get(ServiceConfig {
 ///     service_name: service_name.to_string(),
 ///     display_name: service_name.to_string(),
@@ -57,59 +256,214 @@ impl Drop for ServiceManager {
 ///     service_type: ServiceType::KernelDriver,
 ///     ..Default::default()
 /// })?;
-
+///
 /// assert_eq!(
 ///     service_handle.get_start_type()?,
 ///     ServiceStartType::DemandStart
 /// );
-
-/// service_handle.update_config(ServiceConfig {
-///     display_name: service_name.to_string(),
-///     binary_path: service_path.to_string(),
-///     service_type: ServiceType::KernelDriver,
+///
+/// service_handle.update_config(ServiceConfigChange {
+///     binary_path: Some(service_path.to_string()),
 ///     ..Default::default()
 /// })?;
 /// service_handle.start_blocking()?;
-
+///
 /// assert_eq!(service_handle.state()?, ServiceState::Running);
-
+///
 /// service_handle.stop_blocking()?;
-
+///
 /// assert_eq!(service_handle.state()?, ServiceState::Stopped);
-
-/// std::thread::sleep(std::time::Duration::from_secs(2));
-
+///
 /// service_handle.delete()?;
+/// # Ok(())
+/// # }
 /// ```
 impl ServiceManager {
     /// Creates a new `ServiceManager` with access to the service control manager.
     ///
+    /// No privilege is enabled; see [`ServiceManager::with_privileges`].
+    ///
     /// # Errors
     ///
     /// This function will return an error if it can't open the service control manager.
+    #[cfg(windows)]
     pub fn new() -> Result<Self, ServiceManagerError> {
-        let handle =
-            unsafe { OpenSCManagerW(std::ptr::null(), std::ptr::null(), SC_MANAGER_ALL_ACCESS) };
+        Self::with_backend(Arc::new(Win32Backend))
+    }
 
-        if handle == 0 {
-            return Err(ServiceManagerError::from((
-                get_last_error(),
-                "[ServiceManager::new] handle == 0".to_string(),
-            )));
-        }
+    /// Creates a new `ServiceManager` that only requests `access` from the service control manager,
+    /// such as [`ScmAccess::READ`] for a monitoring agent running without administrator rights.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't open the service control manager.
+    #[cfg(windows)]
+    pub fn with_access(access: ScmAccess) -> Result<Self, ServiceManagerError> {
+        Self::with_backend_access(Arc::new(Win32Backend), access)
+    }
 
-        set_privilege("SeLoadDriverPrivilege".to_string()).map_err(|_| {
-            ServiceManagerError::AccessDenied(
-                get_last_error(),
-                "[ServiceManager::set_privilege] failed".to_string(),
-            )
-        })?;
+    /// Creates a new `ServiceManager` connected to the service control manager of `machine_name`,
+    /// given as `host` or `\\host`. `database_name` defaults to the active database.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error naming the machine if it can't open its service control manager.
+    #[cfg(windows)]
+    pub fn connect(
+        machine_name: &str,
+        database_name: Option<&str>,
+    ) -> Result<Self, ServiceManagerError> {
+        Self::connect_with_access(machine_name, database_name, ScmAccess::ALL_ACCESS)
+    }
+
+    /// Creates a new `ServiceManager` connected to `machine_name` that only requests `access`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error naming the machine if it can't open its service control manager.
+    #[cfg(windows)]
+    pub fn connect_with_access(
+        machine_name: &str,
+        database_name: Option<&str>,
+        access: ScmAccess,
+    ) -> Result<Self, ServiceManagerError> {
+        Self::with_backend_machine(
+            Arc::new(Win32Backend),
+            Some(machine_name),
+            database_name,
+            access,
+        )
+    }
+
+    /// Creates a new `ServiceManager` on top of `backend`, such as a [`FakeScm`](crate::fake::FakeScm) in tests.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the backend can't open the service control manager.
+    pub fn with_backend(backend: Arc<dyn ScmBackend>) -> Result<Self, ServiceManagerError> {
+        Self::with_backend_access(backend, ScmAccess::ALL_ACCESS)
+    }
+
+    /// Creates a new `ServiceManager` on top of `backend` that only requests `access`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the backend can't open the service control manager.
+    pub fn with_backend_access(
+        backend: Arc<dyn ScmBackend>,
+        access: ScmAccess,
+    ) -> Result<Self, ServiceManagerError> {
+        Self::with_backend_machine(backend, None, None, access)
+    }
+
+    /// Creates a new `ServiceManager` on top of `backend` that opens `database_name` on
+    /// `machine_name`, such as one of the machines added with [`FakeScm::add_machine`](crate::fake::FakeScm::add_machine).
+    ///
+    /// `None` selects the local machine and the active database respectively.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error naming the machine if the backend can't open its
+    /// service control manager.
+    pub fn with_backend_machine(
+        backend: Arc<dyn ScmBackend>,
+        machine_name: Option<&str>,
+        database_name: Option<&str>,
+        access: ScmAccess,
+    ) -> Result<Self, ServiceManagerError> {
+        let machine_name = machine_name
+            .map(|machine_name| machine_name.trim_start_matches('\\'))
+            .filter(|machine_name| !machine_name.is_empty());
+        let handle = backend
+            .open_sc_manager(machine_name, database_name, access.bits())
+            .map_err(|err| {
+                ServiceManagerError::from((
+                    err,
+                    on_machine(
+                        format!("[ServiceManager::new] OpenSCManagerW with {access} failed"),
+                        machine_name,
+                    ),
+                ))
+            })?;
 
         Ok(Self {
-            handle: Some(handle),
+            connection: Some(Arc::new(ScmConnection {
+                backend: backend.clone(),
+                handle,
+                machine_name: machine_name.map(str::to_string),
+                database_name: database_name.map(str::to_string),
+            })),
+            backend,
+            access,
+            privileges: Vec::new(),
         })
     }
 
+    /// Enables `privileges` in the token of the current process for as long as this
+    /// `ServiceManager` lives, such as [`Privilege::LoadDriver`] for tools that load drivers themselves.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`PrivilegeError::NotAllAssigned`] naming the privileges the
+    /// token doesn't hold, leaving the token as it was.
+    pub fn with_privileges(mut self, privileges: &[Privilege]) -> Result<Self, PrivilegeError> {
+        self.privileges.push(PrivilegeGuard::with_backend(
+            self.backend.clone(),
+            privileges,
+        )?);
+        Ok(self)
+    }
+
+    /// Returns the rights this `ServiceManager` was opened with.
+    pub fn access(&self) -> ScmAccess {
+        self.access
+    }
+
+    /// Returns the remote machine this `ServiceManager` is connected to, without the leading
+    /// backslashes, or `None` for the local machine.
+    pub fn machine_name(&self) -> Option<&str> {
+        self.connection
+            .as_ref()
+            .and_then(|connection| connection.machine_name.as_deref())
+    }
+
+    #[doc(hidden)]
+    fn context(&self, context: String) -> String {
+        on_machine(context, self.machine_name())
+    }
+
+    #[doc(hidden)]
+    fn check_access<E: From<(u32, String)>>(
+        &self,
+        required: ScmAccess,
+        function: &str,
+    ) -> Result<(), E> {
+        match self.access.missing(required, function) {
+            Some(context) => Err(E::from((ERROR_ACCESS_DENIED, self.context(context)))),
+            None => Ok(()),
+        }
+    }
+
+    #[doc(hidden)]
+    fn handle(&self) -> Option<ScHandle> {
+        self.connection.as_ref().map(|connection| connection.handle)
+    }
+
+    #[doc(hidden)]
+    fn service_handle(
+        &self,
+        handle: ScHandle,
+        service_name: &str,
+        access: ServiceAccess,
+    ) -> ServiceHandle {
+        let service_handle =
+            ServiceHandle::with_backend(self.backend.clone(), handle).with_access(access);
+        match &self.connection {
+            Some(connection) => service_handle.with_manager(connection.clone(), service_name),
+            None => service_handle,
+        }
+    }
+
     /// Creates a new service with the specified configuration.
     ///
     /// # Errors
@@ -119,75 +473,304 @@ impl ServiceManager {
         &self,
         options: ServiceConfig,
     ) -> Result<ServiceHandle, CreateServiceError> {
-        let scm_handle = self.handle.ok_or(CreateServiceError::InvalidHandle(
-            get_last_error(),
+        let scm_handle = self.handle().ok_or(CreateServiceError::InvalidHandle(
+            0,
             "[create_service] invalid service manager handle".to_string(),
         ))?;
 
-        let service_name = U16CString::from_str(options.service_name.clone()).map_err(|_| {
-            CreateServiceError::InvalidName(0, "[create_service] invalid service name".to_string())
-        })?;
-        let display_name = U16CString::from_str(options.display_name.clone()).map_err(|_| {
-            CreateServiceError::InvalidName(0, "[create_service] invalid display name".to_string())
-        })?;
-        let binary_path = U16CString::from_str(options.binary_path.clone()).map_err(|_| {
-            CreateServiceError::InvalidParameter(
+        self.check_access::<CreateServiceError>(ScmAccess::CREATE_SERVICE, "create_service")?;
+        options.validate()?;
+
+        let (handle, tag_id) = self
+            .backend
+            .create_service(scm_handle, &options, ServiceAccess::ALL_ACCESS.bits())
+            .map_err(|err| {
+                CreateServiceError::from((
+                    err,
+                    self.context("[create_service] handle == 0".to_string()),
+                ))
+            })?;
+
+        // CreateServiceW takes no description, so a service whose description can't be set is
+        // deleted again rather than left half configured.
+        if let Some(description) = &options.description {
+            if let Err(err) = self
+                .backend
+                .change_service_description(handle, &description.to_string())
+            {
+                let _ = self.backend.delete_service(handle);
+                self.backend.close_service_handle(handle);
+                return Err(CreateServiceError::from((
+                    err,
+                    self.context(format!(
+                        "[create_service] ChangeServiceConfig2W failed to set the description of {:?}",
+                        options.service_name
+                    )),
+                )));
+            }
+        }
+
+        let service_handle =
+            self.service_handle(handle, &options.service_name, ServiceAccess::ALL_ACCESS);
+        Ok(if options.request_tag {
+            service_handle.with_tag_id(tag_id)
+        } else {
+            service_handle
+        })
+    }
+
+    /// Retrieves an existing service by name, opened with [`ServiceAccess::ALL_ACCESS`] so
+    /// that the handle can control, reconfigure and delete it, which usually takes administrator
+    /// rights. Read-only callers should use [`open_service`](Self::open_service) with
+    /// [`ServiceAccess::READ`] instead.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't retrieve the service.
+    pub fn get_service(&self, service_name: String) -> Result<ServiceHandle, OpenServiceError> {
+        self.open_service(&service_name, ServiceAccess::ALL_ACCESS)
+    }
+
+    /// Opens an existing service with only the rights in `access`, such as
+    /// [`ServiceAccess::QUERY_STATUS`] to watch its state.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't open the service with those rights.
+    pub fn open_service(
+        &self,
+        service_name: &str,
+        access: ServiceAccess,
+    ) -> Result<ServiceHandle, OpenServiceError> {
+        let scm_handle = self.handle().ok_or(OpenServiceError::InvalidHandle(
+            0,
+            "[open_service] invalid service manager handle".to_string(),
+        ))?;
+
+        if service_name.contains('\0') {
+            return Err(OpenServiceError::InvalidName(
                 0,
-                "[create_service] invalid binary path".to_string(),
-            )
-        })?;
+                "[open_service] invalid service name".to_string(),
+            ));
+        }
+        let handle = self
+            .backend
+            .open_service(scm_handle, service_name, access.bits())
+            .map_err(|err| {
+                OpenServiceError::from((
+                    err,
+                    self.context(format!(
+                        "[open_service] OpenServiceW {service_name:?} with {access} failed"
+                    )),
+                ))
+            })?;
+
+        Ok(self.service_handle(handle, service_name, access))
+    }
+
+    /// Lists the services matching `filter`, along with their display names and process status.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it can't enumerate the services.
+    pub fn enum_services(
+        &self,
+        filter: &ServiceFilter,
+    ) -> Result<Vec<ServiceEntry>, QueryServiceError> {
+        let scm_handle = self.handle().ok_or(QueryServiceError::InvalidHandle(
+            0,
+            "[enum_services] invalid service manager handle".to_string(),
+        ))?;
+
+        self.check_access::<QueryServiceError>(ScmAccess::ENUMERATE_SERVICE, "enum_services")?;
+        if filter
+            .load_order_group
+            .as_ref()
+            .is_some_and(|group| group.contains('\0'))
+        {
+            return Err(QueryServiceError::InvalidParameter(
+                0,
+                "[enum_services] invalid load order group".to_string(),
+            ));
+        }
 
-        let handle = unsafe {
-            CreateServiceW(
+        self.backend
+            .enum_services_status_ex(
                 scm_handle,
-                service_name.as_ptr(),
-                display_name.as_ptr(),
-                SERVICE_ALL_ACCESS,
-                options.service_type as u32,
-                options.start_type as u32,
-                options.error_control as u32,
-                binary_path.as_ptr(),
-                std::ptr::null(),
-                std::ptr::null_mut(),
-                std::ptr::null(),
-                std::ptr::null(),
-                std::ptr::null(),
+                filter.service_type as u32,
+                filter.state as u32,
+                filter.load_order_group.as_deref(),
             )
+            .map_err(|err| {
+                QueryServiceError::from((
+                    err,
+                    self.context("[enum_services] EnumServicesStatusExW".to_string()),
+                ))
+            })?
+            .into_iter()
+            .map(
+                |entry: RawServiceEntry| match ServiceStatus::try_from(entry.status) {
+                    Ok(status) => Ok(ServiceEntry {
+                        service_name: entry.service_name,
+                        display_name: entry.display_name,
+                        status,
+                    }),
+                    Err(_) => Err(QueryServiceError::Unknown(
+                        0,
+                        self.context(format!(
+                        "[enum_services] {:?} reports an invalid status, type {:#x} in state {}",
+                        entry.service_name, entry.status.service_type, entry.status.current_state
+                    )),
+                    )),
+                },
+            )
+            .collect()
+    }
+
+    /// Subscribes to services being created or deleted, as selected by `mask`, which may only
+    /// contain [`ServiceNotify::CREATED`] and [`ServiceNotify::DELETED`].
+    ///
+    /// The subscription uses a connection of its own and stops when the returned
+    /// [`ServiceEvents`] is dropped.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `mask` is invalid or it can't open a connection to watch.
+    pub fn subscribe(&self, mask: ServiceNotify) -> Result<ServiceEvents, QueryServiceError> {
+        let Some(connection) = &self.connection else {
+            return Err(QueryServiceError::InvalidHandle(
+                0,
+                "[subscribe] invalid service manager handle".to_string(),
+            ));
         };
 
-        if handle == 0 {
-            return Err(CreateServiceError::from((
-                get_last_error(),
-                "[create_service] handle == 0".to_string(),
-            )));
+        self.check_access::<QueryServiceError>(ScmAccess::ENUMERATE_SERVICE, "subscribe")?;
+        if mask.is_empty() || !ServiceNotify::MANAGER_EVENTS.contains(mask) {
+            return Err(QueryServiceError::InvalidParameter(
+                ERROR_INVALID_PARAMETER,
+                self.context(format!("[subscribe] a service manager can't report {mask}")),
+            ));
         }
 
-        Ok(ServiceHandle::new(handle))
+        ServiceEvents::subscribe(connection.clone(), None, mask)
     }
 
-    /// Retrieves an existing service by name.
+    /// Returns the key name of the service whose display name is `display_name`.
     ///
     /// # Errors
     ///
-    /// This function will return an error if it can't retrieve the service.
-    pub fn get_service(&self, service_name: String) -> Result<ServiceHandle, OpenServiceError> {
-        let scm_handle = self.handle.ok_or(OpenServiceError::InvalidHandle(
-            get_last_error(),
-            "[get_service] invalid service manager handle".to_string(),
+    /// This function will return an error if no service has that display name.
+    pub fn get_service_key_name(&self, display_name: &str) -> Result<String, OpenServiceError> {
+        let scm_handle = self.handle().ok_or(OpenServiceError::InvalidHandle(
+            0,
+            "[get_service_key_name] invalid service manager handle".to_string(),
+        ))?;
+        if display_name.contains('\0') {
+            return Err(OpenServiceError::InvalidName(
+                0,
+                "[get_service_key_name] invalid display name".to_string(),
+            ));
+        }
+
+        self.backend
+            .get_service_key_name(scm_handle, display_name)
+            .map_err(|err| {
+                OpenServiceError::from((
+                    err,
+                    self.context(format!(
+                        "[get_service_key_name] GetServiceKeyNameW {display_name:?} failed"
+                    )),
+                ))
+            })
+    }
+
+    /// Returns the display name of the service whose key name is `service_name`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service doesn't exist.
+    pub fn get_service_display_name(&self, service_name: &str) -> Result<String, OpenServiceError> {
+        let scm_handle = self.handle().ok_or(OpenServiceError::InvalidHandle(
+            0,
+            "[get_service_display_name] invalid service manager handle".to_string(),
         ))?;
+        if service_name.contains('\0') {
+            return Err(OpenServiceError::InvalidName(
+                0,
+                "[get_service_display_name] invalid service name".to_string(),
+            ));
+        }
 
-        let service_name = U16CString::from_str(service_name)
-            .map_err(|_| OpenServiceError::InvalidName(0, "invalid service named".to_string()))?;
-        let handle = unsafe { OpenServiceW(scm_handle, service_name.as_ptr(), SERVICE_ALL_ACCESS) };
+        self.backend
+            .get_service_display_name(scm_handle, service_name)
+            .map_err(|err| {
+                OpenServiceError::from((
+                    err,
+                    self.context(format!(
+                        "[get_service_display_name] GetServiceDisplayNameW {service_name:?} failed"
+                    )),
+                ))
+            })
+    }
 
-        if handle == 0 {
-            return Err(OpenServiceError::from((
-                get_last_error(),
-                "[get_service] handle == 0".to_string(),
-            )));
+    /// Retrieves an existing service by display name, opened with
+    /// [`ServiceAccess::ALL_ACCESS`] like [`get_service`](Self::get_service). Read-only callers
+    /// should look up the key name with [`get_service_key_name`](Self::get_service_key_name)
+    /// and pass it to [`open_service`](Self::open_service).
+    ///
+    /// Display names are compared case-insensitively, like the SCM does.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no service has that display name, or
+    /// [`OpenServiceError::AmbiguousName`] listing the candidates if several do.
+    pub fn get_service_by_display_name(
+        &self,
+        display_name: &str,
+    ) -> Result<ServiceHandle, OpenServiceError> {
+        if display_name.contains('\0') {
+            return Err(OpenServiceError::InvalidName(
+                0,
+                "[get_service_by_display_name] invalid display name".to_string(),
+            ));
         }
 
-        Ok(ServiceHandle::new(handle))
+        let display_key = display_name.to_lowercase();
+        let matches: Vec<_> = self
+            .enum_services(&ServiceFilter::default())
+            .map_err(|err| match err {
+                QueryServiceError::AccessDenied(err, display) => {
+                    OpenServiceError::AccessDenied(err, display)
+                }
+                QueryServiceError::InvalidHandle(err, display) => {
+                    OpenServiceError::InvalidHandle(err, display)
+                }
+                QueryServiceError::InvalidParameter(err, display)
+                | QueryServiceError::Unknown(err, display) => {
+                    OpenServiceError::Unknown(err, display)
+                }
+            })?
+            .into_iter()
+            .filter(|entry| entry.display_name.to_lowercase() == display_key)
+            .map(|entry| entry.service_name)
+            .collect();
+
+        match matches.as_slice() {
+            [] => Err(OpenServiceError::ServiceDoesNotExist(
+                ERROR_SERVICE_DOES_NOT_EXIST,
+                self.context(format!(
+                    "[get_service_by_display_name] no service is named {display_name:?}"
+                )),
+            )),
+            [service_name] => self.get_service(service_name.clone()),
+            _ => Err(OpenServiceError::AmbiguousName(
+                ERROR_DUPLICATE_SERVICE_NAME,
+                self.context(format!(
+                    "[get_service_by_display_name] {display_name:?} matches {}",
+                    matches.join(", ")
+                )),
+            )),
+        }
     }
 
     /// Creates a new service if it doesn't exist, otherwise retrieves the existing service.
@@ -199,7 +782,9 @@ impl ServiceManager {
         &self,
         options: ServiceConfig,
     ) -> Result<ServiceHandle, CreateServiceError> {
-        if let Ok(service_handle) = self.get_service(options.service_name.clone()) {
+        if let Ok(service_handle) =
+            self.open_service(&options.service_name, ServiceAccess::ALL_ACCESS)
+        {
             return Ok(service_handle);
         }
 
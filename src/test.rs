@@ -1,8 +1,8 @@
 #[cfg(test)]
-#[cfg(feature = "tests")]
+#[cfg(all(windows, feature = "tests"))]
 mod tests {
     use crate::{
-        service::{ServiceStartType, ServiceState, ServiceType},
+        service::{ServiceConfigChange, ServiceStartType, ServiceState, ServiceType},
         service_manager::{ServiceConfig, ServiceManager},
     };
 
@@ -30,10 +30,8 @@ mod tests {
             ServiceStartType::DemandStart
         );
 
-        service_handle.update_config(ServiceConfig {
-            display_name: service_name.to_string(),
-            binary_path: service_path.to_string(),
-            service_type: ServiceType::KernelDriver,
+        service_handle.update_config(ServiceConfigChange {
+            binary_path: Some(service_path.to_string()),
             ..Default::default()
         })?;
         service_handle.start_blocking()?;
@@ -53,3 +51,1973 @@ mod tests {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod fake_tests {
+    use std::{sync::Arc, time::Duration};
+
+    use crate::{
+        access::{ScmAccess, ServiceAccess},
+        account::ServiceAccount,
+        error::{
+            ControlServiceError, CreateServiceError, DeleteServiceError, OpenServiceError,
+            PrivilegeError, QueryServiceError, RestartError, ServiceManagerError,
+            UpdateServiceError,
+        },
+        fake::{FakeScm, FakeServiceBehavior},
+        notify::{ServiceEvent, ServiceNotify},
+        privilege::{Privilege, PrivilegeGuard},
+        service::{
+            DependentService, FailureAction, FailureActionType, FailureActions, NetBindChange,
+            RestartOptions, ServiceConfigChange, ServiceConfigSnapshot, ServiceControls,
+            ServiceDependency, ServiceDescription, ServiceErrorControl, ServiceFlags,
+            ServiceSidType, ServiceStartMode, ServiceStartType, ServiceState, ServiceType,
+            ServiceTypeFlags, StopReason, StopReasonKind, StopReasonMajor, StopReasonMinor,
+        },
+        service_manager::{
+            ServiceConfig, ServiceFilter, ServiceManager, ServiceStateFilter, ServiceTypeFilter,
+        },
+        trigger::{Guid, ServiceTrigger, TriggerAction, TriggerDataItem, TriggerEvent},
+    };
+
+    use anyhow::Result;
+
+    fn driver_config(service_name: &str) -> ServiceConfig {
+        ServiceConfig {
+            service_name: service_name.to_string(),
+            display_name: service_name.to_string(),
+            binary_path: r"C:\Windows\system32\test.sys".to_string(),
+            start_type: ServiceStartType::DemandStart,
+            service_type: ServiceType::KernelDriver,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fake_lifecycle() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+
+        let service_handle = service_manager.create_or_get(driver_config("test"))?;
+        assert_eq!(service_handle.state()?, ServiceState::Stopped);
+
+        service_handle.start()?;
+        assert_eq!(service_handle.state()?, ServiceState::StartPending);
+        assert_eq!(service_handle.state()?, ServiceState::Running);
+
+        service_handle.stop_blocking()?;
+        assert_eq!(service_handle.state()?, ServiceState::Stopped);
+
+        service_handle.delete()?;
+        assert!(scm.contains("test"));
+        drop(service_handle);
+        assert!(!scm.contains("test"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_service_type_modifiers() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+
+        for (raw, service_type, flags) in [
+            (
+                0x110,
+                ServiceType::Win32OwnProcess,
+                ServiceTypeFlags::INTERACTIVE_PROCESS,
+            ),
+            (
+                0x50,
+                ServiceType::Win32OwnProcess,
+                ServiceTypeFlags::USER_SERVICE,
+            ),
+            (
+                0xe0,
+                ServiceType::Win32ShareProcess,
+                ServiceTypeFlags::USER_SERVICE | ServiceTypeFlags::USERSERVICE_INSTANCE,
+            ),
+        ] {
+            let name = format!("modifiers{raw:x}");
+            let service_handle = service_manager.create_or_get(ServiceConfig {
+                service_type,
+                ..driver_config(&name)
+            })?;
+            assert!(scm.set_service_type(&name, raw));
+
+            let status = service_handle.status()?;
+            assert_eq!(status.service_type, service_type);
+            assert_eq!(status.service_type_flags, flags);
+            let snapshot = service_handle.query_config()?;
+            assert_eq!(snapshot.service_type, service_type);
+            assert_eq!(snapshot.service_type_flags, flags);
+
+            service_handle.start_blocking()?;
+            assert_eq!(service_handle.state()?, ServiceState::Running);
+            service_handle.stop_blocking()?;
+            assert_eq!(service_handle.state()?, ServiceState::Stopped);
+        }
+        assert!(!scm.set_service_type("missing", 0x110));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_error_codes() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+
+        let service_handle = service_manager.create_service(driver_config("test"))?;
+        assert!(matches!(
+            service_manager.create_service(driver_config("TEST")),
+            Err(CreateServiceError::ServiceExists(..))
+        ));
+        assert!(matches!(
+            service_manager.get_service("missing".to_string()),
+            Err(OpenServiceError::ServiceDoesNotExist(..))
+        ));
+        assert!(matches!(
+            service_handle.stop(),
+            Err(ControlServiceError::ServiceNotActive(..))
+        ));
+
+        service_handle.set_start_type(ServiceStartType::Disabled)?;
+        assert!(matches!(
+            service_handle.start(),
+            Err(ControlServiceError::ServiceDisabled(..))
+        ));
+
+        service_handle.set_start_type(ServiceStartType::DemandStart)?;
+        assert!(scm.set_behavior(
+            "test",
+            FakeServiceBehavior {
+                pending_steps: 0,
+                ..Default::default()
+            }
+        ));
+        service_handle.start()?;
+        assert!(matches!(
+            service_handle.start(),
+            Err(ControlServiceError::ServiceAlreadyRunning(..))
+        ));
+
+        service_handle.delete()?;
+        assert!(matches!(
+            service_handle.delete(),
+            Err(DeleteServiceError::ErrorServiceMarkedForDelete(..))
+        ));
+        assert!(matches!(
+            service_manager.create_service(driver_config("test")),
+            Err(CreateServiceError::ServiceMarkedForDelete(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_query_config() -> Result<()> {
+        let service_manager = ServiceManager::with_backend(Arc::new(FakeScm::new()))?;
+        let service_handle = service_manager.create_service(ServiceConfig {
+            display_name: "Test Driver".to_string(),
+            error_control: ServiceErrorControl::ErrorSevere,
+            ..driver_config("test")
+        })?;
+
+        service_handle.set_start_type(ServiceStartType::SystemStart)?;
+
+        let config = service_handle.query_config()?;
+        assert_eq!(config.service_type, ServiceType::KernelDriver);
+        assert_eq!(config.start_type, ServiceStartType::SystemStart);
+        assert_eq!(config.error_control, ServiceErrorControl::ErrorSevere);
+        assert_eq!(config.binary_path, r"C:\Windows\system32\test.sys");
+        assert_eq!(config.display_name, "Test Driver");
+        assert_eq!(config.load_order_group, None);
+        assert!(config.dependencies.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_partial_update() -> Result<()> {
+        let service_manager = ServiceManager::with_backend(Arc::new(FakeScm::new()))?;
+        let service_handle = service_manager.create_service(ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            start_type: ServiceStartType::AutoStart,
+            binary_path: r"C:\test\old.exe".to_string(),
+            ..driver_config("test")
+        })?;
+
+        service_handle.update_config(ServiceConfigChange {
+            binary_path: Some(r"C:\test\new.exe".to_string()),
+            ..Default::default()
+        })?;
+        service_handle.update_config(ServiceConfigChange {
+            display_name: Some("Renamed".to_string()),
+            ..Default::default()
+        })?;
+
+        let config = service_handle.query_config()?;
+        assert_eq!(config.binary_path, r"C:\test\new.exe");
+        assert_eq!(config.display_name, "Renamed");
+        assert_eq!(config.service_type, ServiceType::Win32OwnProcess);
+        assert_eq!(config.start_type, ServiceStartType::AutoStart);
+
+        // Nothing is written when part of the change is invalid.
+        assert!(matches!(
+            service_handle.update_config(ServiceConfigChange {
+                binary_path: Some("C:\\test\0.exe".to_string()),
+                start_mode: Some(ServiceStartMode::AutoDelayed),
+                ..Default::default()
+            }),
+            Err(UpdateServiceError::InvalidParameter(..))
+        ));
+        assert_eq!(service_handle.start_mode()?, ServiceStartMode::Auto);
+
+        // A refused ChangeServiceConfigW call leaves the ChangeServiceConfig2W settings applied.
+        service_manager.create_service(driver_config("other"))?;
+        assert!(service_handle
+            .update_config(ServiceConfigChange {
+                display_name: Some("other".to_string()),
+                preshutdown_timeout: Some(Duration::from_secs(30)),
+                ..Default::default()
+            })
+            .is_err());
+        let config = service_handle.query_config()?;
+        assert_eq!(config.display_name, "Renamed");
+        assert_eq!(config.preshutdown_timeout, Some(Duration::from_secs(30)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_create_full_config() -> Result<()> {
+        let service_manager = ServiceManager::with_backend(Arc::new(FakeScm::new()))?;
+        service_manager.create_service(ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            binary_path: r"C:\test\base.exe".to_string(),
+            ..driver_config("base")
+        })?;
+        let service_handle = service_manager.create_service(ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            binary_path: r"C:\test\app.exe".to_string(),
+            load_order_group: Some("AppGroup".to_string()),
+            dependencies: vec![
+                ServiceDependency::Service("base".to_string()),
+                ServiceDependency::Group("NetworkProvider".to_string()),
+            ],
+            account: Some("svc-app".parse()?),
+            password: Some("secret".to_string()),
+            ..driver_config("app")
+        })?;
+
+        let config = service_handle.query_config()?;
+        assert_eq!(config.load_order_group.as_deref(), Some("AppGroup"));
+        assert_eq!(
+            config.account,
+            Some(ServiceAccount::User(r".\svc-app".to_string()))
+        );
+        assert_eq!(
+            config.dependencies,
+            vec![
+                ServiceDependency::Service("base".to_string()),
+                ServiceDependency::Group("NetworkProvider".to_string()),
+            ]
+        );
+
+        let first = service_manager.create_service(ServiceConfig {
+            start_type: ServiceStartType::BootStart,
+            load_order_group: Some("Boot Bus Extender".to_string()),
+            request_tag: true,
+            ..driver_config("first")
+        })?;
+        let second = service_manager.create_service(ServiceConfig {
+            start_type: ServiceStartType::BootStart,
+            load_order_group: Some("Boot Bus Extender".to_string()),
+            request_tag: true,
+            ..driver_config("second")
+        })?;
+        assert_eq!(first.tag_id(), Some(1));
+        assert_eq!(second.tag_id(), Some(2));
+        assert_eq!(service_handle.tag_id(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_enum_services() -> Result<()> {
+        let service_manager = ServiceManager::with_backend(Arc::new(FakeScm::new()))?;
+        service_manager.create_service(ServiceConfig {
+            load_order_group: Some("Extended Base".to_string()),
+            ..driver_config("Driver")
+        })?;
+        for service_name in ["HostA", "HostB"] {
+            service_manager
+                .create_service(ServiceConfig {
+                    service_type: ServiceType::Win32ShareProcess,
+                    binary_path: r"C:\Windows\system32\svchost.exe -k test".to_string(),
+                    ..driver_config(service_name)
+                })?
+                .start_blocking()?;
+        }
+
+        let all = service_manager.enum_services(&ServiceFilter::default())?;
+        let names: Vec<_> = all
+            .iter()
+            .map(|entry| entry.service_name.as_str())
+            .collect();
+        assert_eq!(names, ["Driver", "HostA", "HostB"]);
+        assert_eq!(all[1].status.state, ServiceState::Running);
+        assert_eq!(all[1].status.service_type, ServiceType::Win32ShareProcess);
+        assert!(all[1].status.process_id.is_some());
+        assert_eq!(all[1].status.process_id, all[2].status.process_id);
+
+        let active_win32 = service_manager.enum_services(&ServiceFilter {
+            service_type: ServiceTypeFilter::Win32,
+            state: ServiceStateFilter::Active,
+            ..Default::default()
+        })?;
+        assert_eq!(active_win32.len(), 2);
+
+        let inactive_drivers = service_manager.enum_services(&ServiceFilter {
+            service_type: ServiceTypeFilter::Drivers,
+            state: ServiceStateFilter::Inactive,
+            load_order_group: Some("extended base".to_string()),
+        })?;
+        assert_eq!(inactive_drivers.len(), 1);
+        assert_eq!(inactive_drivers[0].status.process_id, None);
+
+        let ungrouped = service_manager.enum_services(&ServiceFilter {
+            load_order_group: Some(String::new()),
+            ..Default::default()
+        })?;
+        assert_eq!(ungrouped.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_dependents() -> Result<()> {
+        let service_manager = ServiceManager::with_backend(Arc::new(FakeScm::new()))?;
+        let win32_config =
+            |service_name: &str, dependencies: Vec<ServiceDependency>| ServiceConfig {
+                service_type: ServiceType::Win32OwnProcess,
+                binary_path: format!(r"C:\test\{service_name}.exe"),
+                dependencies,
+                ..driver_config(service_name)
+            };
+        let base = service_manager.create_service(ServiceConfig {
+            load_order_group: Some("Core".to_string()),
+            ..win32_config("base", vec![])
+        })?;
+        service_manager.create_service(win32_config(
+            "app",
+            vec![ServiceDependency::Service("Base".to_string())],
+        ))?;
+        let web = service_manager.create_service(win32_config(
+            "web",
+            vec![ServiceDependency::Service("app".to_string())],
+        ))?;
+        service_manager.create_service(win32_config(
+            "plugin",
+            vec![ServiceDependency::Group("core".to_string())],
+        ))?;
+        web.start_blocking()?;
+
+        let names = |dependents: Vec<DependentService>| -> Vec<String> {
+            dependents
+                .into_iter()
+                .map(|dependent| dependent.service_name)
+                .collect()
+        };
+        assert_eq!(
+            names(base.all_dependents(ServiceStateFilter::All)?),
+            ["web", "app", "plugin"]
+        );
+        assert_eq!(
+            names(base.direct_dependents(ServiceStateFilter::All)?),
+            ["app", "plugin"]
+        );
+
+        let active = base.all_dependents(ServiceStateFilter::Active)?;
+        assert!(active
+            .iter()
+            .all(|dependent| dependent.state == ServiceState::Running));
+        assert_eq!(names(active), ["web", "app"]);
+        assert!(web.all_dependents(ServiceStateFilter::All)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_stop_with_dependents() -> Result<()> {
+        let service_manager = ServiceManager::with_backend(Arc::new(FakeScm::new()))?;
+        let base = service_manager.create_service(driver_config("base"))?;
+        let app = service_manager.create_service(ServiceConfig {
+            dependencies: vec![ServiceDependency::Service("base".to_string())],
+            ..driver_config("app")
+        })?;
+        let web = service_manager.create_service(ServiceConfig {
+            dependencies: vec![ServiceDependency::Service("app".to_string())],
+            ..driver_config("web")
+        })?;
+        service_manager.create_service(ServiceConfig {
+            dependencies: vec![ServiceDependency::Service("base".to_string())],
+            ..driver_config("idle")
+        })?;
+        web.start_blocking()?;
+
+        assert!(matches!(
+            base.stop(),
+            Err(ControlServiceError::DependentServicesRunning(..))
+        ));
+        let stopped = base.stop_with_dependents()?;
+        assert_eq!(stopped, ["web", "app"]);
+        for service_handle in [&base, &app, &web] {
+            assert_eq!(service_handle.state()?, ServiceState::Stopped);
+        }
+
+        base.start_blocking()?;
+        base.start_dependents(&stopped)?;
+        for service_handle in [&base, &app, &web] {
+            assert_eq!(service_handle.state()?, ServiceState::Running);
+        }
+        let idle = service_manager.get_service("idle".to_string())?;
+        assert_eq!(idle.state()?, ServiceState::Stopped);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_restart() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let win32_config = |service_name: &str, service_type: ServiceType| ServiceConfig {
+            service_type,
+            binary_path: format!(r"C:\test\{service_name}.exe"),
+            ..driver_config(service_name)
+        };
+        let base =
+            service_manager.create_service(win32_config("base", ServiceType::Win32OwnProcess))?;
+        let app = service_manager.create_service(ServiceConfig {
+            dependencies: vec![ServiceDependency::Service("base".to_string())],
+            ..win32_config("app", ServiceType::Win32OwnProcess)
+        })?;
+
+        let report = base.restart(&RestartOptions::default())?;
+        assert_eq!(base.state()?, ServiceState::Running);
+        assert!(report.stopped_dependents.is_empty());
+        assert_eq!(report.killed_process_id, None);
+
+        app.start_blocking()?;
+        assert!(matches!(
+            base.restart(&RestartOptions::default()),
+            Err(RestartError {
+                error: ControlServiceError::DependentServicesRunning(..),
+                ..
+            })
+        ));
+        let report = base.restart(&RestartOptions {
+            restart_dependents: true,
+            ..Default::default()
+        })?;
+        assert_eq!(report.stopped_dependents, ["app"]);
+        assert_eq!(app.state()?, ServiceState::Running);
+
+        let hanging_stop = FakeServiceBehavior {
+            wait_hint: 200,
+            hang_in: Some(ServiceState::StopPending),
+            ..Default::default()
+        };
+        scm.set_behavior("app", hanging_stop.clone());
+        let process_id = app.status()?.process_id;
+        match app.restart(&RestartOptions::default()) {
+            Err(RestartError {
+                error: ControlServiceError::Stalled(_, message),
+                ..
+            }) => {
+                assert!(message.contains("StopPending"), "{message}")
+            }
+            other => panic!("expected a stalled service, got {other:?}"),
+        }
+        let report = app.restart(&RestartOptions {
+            stop_timeout: Some(Duration::from_secs(5)),
+            force_kill: true,
+            ..Default::default()
+        })?;
+        assert_eq!(report.killed_process_id, process_id);
+        assert_eq!(app.state()?, ServiceState::Running);
+        assert_ne!(app.status()?.process_id, process_id);
+
+        let shared = service_manager
+            .create_service(win32_config("shared", ServiceType::Win32ShareProcess))?;
+        shared.start_blocking()?;
+        scm.set_behavior("shared", hanging_stop.clone());
+        match shared.restart(&RestartOptions {
+            force_kill: true,
+            ..Default::default()
+        }) {
+            Err(RestartError {
+                error: ControlServiceError::Stalled(_, message),
+                ..
+            }) => {
+                assert!(message.contains("process of its own"), "{message}")
+            }
+            other => panic!("expected a stalled service, got {other:?}"),
+        }
+
+        // The options apply to the dependents too.
+        scm.set_behavior("shared", FakeServiceBehavior::default());
+        scm.set_behavior("app", hanging_stop);
+        let process_id = app.status()?.process_id;
+        let report = base.restart(&RestartOptions {
+            stop_timeout: Some(Duration::from_secs(5)),
+            force_kill: true,
+            restart_dependents: true,
+            ..Default::default()
+        })?;
+        assert_eq!(report.stopped_dependents, ["app"]);
+        assert_eq!(
+            report.killed_dependents,
+            [("app".to_string(), process_id.unwrap_or_default())]
+        );
+        assert_eq!(app.state()?, ServiceState::Running);
+
+        scm.set_behavior("app", FakeServiceBehavior::default());
+
+        // One that doesn't start again reports the dependents it leaves stopped.
+        scm.set_behavior(
+            "base",
+            FakeServiceBehavior {
+                start_failure: Some(1066),
+                ..Default::default()
+            },
+        );
+        match base.restart(&RestartOptions {
+            restart_dependents: true,
+            ..Default::default()
+        }) {
+            Err(RestartError {
+                report,
+                left_stopped,
+                ..
+            }) => {
+                assert_eq!(report.stopped_dependents, ["app"]);
+                assert_eq!(left_stopped, ["app"]);
+            }
+            other => panic!("expected the start to fail, got {other:?}"),
+        }
+        assert_eq!(app.state()?, ServiceState::Stopped);
+        scm.set_behavior("base", FakeServiceBehavior::default());
+        base.start_blocking()?;
+        app.start_blocking()?;
+
+        // A service that refuses to stop gets its dependents back.
+        scm.set_behavior(
+            "base",
+            FakeServiceBehavior {
+                controls_accepted: Some(0),
+                ..Default::default()
+            },
+        );
+        // The new controls are reported once it runs again.
+        base.restart(&RestartOptions {
+            restart_dependents: true,
+            ..Default::default()
+        })?;
+        match base.restart(&RestartOptions {
+            restart_dependents: true,
+            ..Default::default()
+        }) {
+            Err(RestartError {
+                error: ControlServiceError::InvalidServiceControl(..),
+                report,
+                left_stopped,
+            }) => {
+                assert_eq!(report.stopped_dependents, ["app"]);
+                assert!(left_stopped.is_empty());
+            }
+            other => panic!("expected the stop to be refused, got {other:?}"),
+        }
+        assert_eq!(app.state()?, ServiceState::Running);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_name_lookups() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        service_manager.create_service(ServiceConfig {
+            display_name: "Print Spooler".to_string(),
+            ..driver_config("Spooler")
+        })?;
+        service_manager.create_service(ServiceConfig {
+            display_name: "Other".to_string(),
+            ..driver_config("other")
+        })?;
+
+        assert_eq!(
+            service_manager.get_service_key_name("print spooler")?,
+            "Spooler"
+        );
+        assert_eq!(
+            service_manager.get_service_display_name("spooler")?,
+            "Print Spooler"
+        );
+        let service_handle = service_manager.get_service_by_display_name("PRINT SPOOLER")?;
+        assert_eq!(service_handle.service_name(), Some("Spooler"));
+        assert!(matches!(
+            service_manager.get_service_by_display_name("Missing"),
+            Err(OpenServiceError::ServiceDoesNotExist(..))
+        ));
+
+        assert!(scm.set_display_name("other", "print spooler"));
+        match service_manager.get_service_by_display_name("Print Spooler") {
+            Err(OpenServiceError::AmbiguousName(_, message)) => {
+                assert!(message.contains("other, Spooler"), "{message}")
+            }
+            other => panic!("expected an ambiguous name, got {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_least_privilege() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        ServiceManager::with_backend(scm.clone())?
+            .create_service(driver_config("test"))?
+            .start_blocking()?;
+
+        let service_manager = ServiceManager::with_backend_access(scm, ScmAccess::READ)?;
+        assert_eq!(service_manager.enum_services(&Default::default())?.len(), 1);
+        match service_manager.create_service(driver_config("other")) {
+            Err(CreateServiceError::AccessDenied(_, message)) => {
+                assert!(message.contains("SC_MANAGER_CREATE_SERVICE"), "{message}")
+            }
+            other => panic!("expected access denied, got {other:?}"),
+        }
+
+        let service_handle = service_manager.open_service("test", ServiceAccess::QUERY_STATUS)?;
+        assert_eq!(service_handle.state()?, ServiceState::Running);
+        match service_handle.stop() {
+            Err(ControlServiceError::AccessDenied(_, message)) => {
+                assert!(message.contains("SERVICE_STOP"), "{message}")
+            }
+            other => panic!("expected access denied, got {other:?}"),
+        }
+        assert!(matches!(
+            service_handle.query_config(),
+            Err(QueryServiceError::AccessDenied(..))
+        ));
+
+        assert_eq!(
+            service_manager.get_service("test".to_string())?.access(),
+            ServiceAccess::ALL_ACCESS
+        );
+        let service_handle = service_manager.open_service("test", ServiceAccess::READ)?;
+        assert_eq!(service_handle.access(), ServiceAccess::READ);
+        assert_eq!(service_handle.query_config()?.display_name, "test");
+        assert!(matches!(
+            service_handle.stop(),
+            Err(ControlServiceError::AccessDenied(..))
+        ));
+        match service_manager.open_service("bad\0name", ServiceAccess::READ) {
+            Err(OpenServiceError::InvalidName(_, message)) => {
+                assert!(message.starts_with("[open_service]"), "{message}")
+            }
+            other => panic!("expected an invalid name, got {other:?}"),
+        }
+        assert_eq!(
+            (ServiceAccess::START | ServiceAccess::STOP).to_string(),
+            "SERVICE_START | SERVICE_STOP"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_privileges() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        scm.grant_privilege(Privilege::LoadDriver);
+
+        let service_manager =
+            ServiceManager::with_backend(scm.clone())?.with_privileges(&[Privilege::LoadDriver])?;
+        assert!(scm.privilege_enabled(Privilege::LoadDriver));
+        drop(service_manager);
+        assert!(!scm.privilege_enabled(Privilege::LoadDriver));
+
+        match PrivilegeGuard::with_backend(
+            scm.clone(),
+            &[Privilege::LoadDriver, Privilege::Debug, Privilege::Tcb],
+        ) {
+            Err(PrivilegeError::NotAllAssigned(_, message)) => {
+                assert!(
+                    message.ends_with("SeDebugPrivilege, SeTcbPrivilege"),
+                    "{message}"
+                )
+            }
+            other => panic!("expected missing privileges, got {other:?}"),
+        }
+        assert!(!scm.privilege_enabled(Privilege::LoadDriver));
+
+        assert_eq!(
+            "seloaddriverprivilege".parse::<Privilege>()?,
+            Privilege::LoadDriver
+        );
+        assert!("SeMadeUpPrivilege".parse::<Privilege>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_remote_machines() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        assert!(scm.add_machine(r"\\HostA"));
+        assert!(scm.add_machine("hostb"));
+        assert!(!scm.add_machine("hosta"));
+
+        let host_a = ServiceManager::with_backend_machine(
+            scm.clone(),
+            Some("hosta"),
+            Some("ServicesActive"),
+            ScmAccess::ALL_ACCESS,
+        )?;
+        let host_b = ServiceManager::with_backend_machine(
+            scm.clone(),
+            Some(r"\\HOSTB"),
+            None,
+            ScmAccess::ALL_ACCESS,
+        )?;
+        assert_eq!(host_a.machine_name(), Some("hosta"));
+        assert_eq!(host_b.machine_name(), Some("HOSTB"));
+
+        let service_handle = host_a.create_service(driver_config("test"))?;
+        assert_eq!(service_handle.machine_name(), Some("hosta"));
+        service_handle.start_blocking()?;
+        assert_eq!(service_handle.state()?, ServiceState::Running);
+        assert!(!scm.contains("test"));
+
+        match host_b.get_service("test".to_string()) {
+            Err(OpenServiceError::ServiceDoesNotExist(_, message)) => {
+                assert!(message.ends_with(r"on \\HOSTB"), "{message}")
+            }
+            other => panic!("expected a missing service, got {other:?}"),
+        }
+        match service_handle.start() {
+            Err(ControlServiceError::ServiceAlreadyRunning(_, message)) => {
+                assert!(message.ends_with(r"on \\hosta"), "{message}")
+            }
+            other => panic!("expected an already running service, got {other:?}"),
+        }
+        match service_handle.set_failure_actions(&FailureActions {
+            command: Some("bad\0command".to_string()),
+            ..Default::default()
+        }) {
+            Err(UpdateServiceError::InvalidParameter(_, message)) => {
+                assert!(message.ends_with(r"on \\hosta"), "{message}")
+            }
+            other => panic!("expected an invalid command, got {other:?}"),
+        }
+
+        match ServiceManager::with_backend_machine(
+            scm.clone(),
+            Some("hostc"),
+            None,
+            ScmAccess::ALL_ACCESS,
+        ) {
+            Err(ServiceManagerError::MachineUnavailable(_, message)) => {
+                assert!(message.ends_with(r"on \\hostc"), "{message}")
+            }
+            other => panic!("expected an unavailable machine, got {other:?}"),
+        }
+        assert!(matches!(
+            ServiceManager::with_backend_machine(
+                scm,
+                Some("hosta"),
+                Some("ServicesFailed"),
+                ScmAccess::ALL_ACCESS,
+            ),
+            Err(ServiceManagerError::DatabaseDoesNotExist(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_status() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let service_handle = service_manager.create_service(ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            binary_path: r"C:\Program Files\Test\test.exe".to_string(),
+            ..driver_config("test")
+        })?;
+
+        let status = service_handle.status()?;
+        assert_eq!(status.service_type, ServiceType::Win32OwnProcess);
+        assert_eq!(status.state, ServiceState::Stopped);
+        assert_eq!(status.process_id, None);
+        assert!(status.controls_accepted.is_empty());
+
+        service_handle.start_blocking()?;
+        let status = service_handle.status()?;
+        assert_eq!(status.state, ServiceState::Running);
+        assert!(status.process_id.is_some());
+        assert_eq!(
+            status.controls_accepted,
+            ServiceControls::STOP | ServiceControls::PAUSE_CONTINUE
+        );
+        assert_eq!(status.service_flags, ServiceFlags::empty());
+        service_handle.stop_blocking()?;
+
+        scm.set_behavior(
+            "test",
+            FakeServiceBehavior {
+                start_failure: Some(1067),
+                ..Default::default()
+            },
+        );
+        assert!(service_handle.start_blocking().is_err());
+        let status = service_handle.status()?;
+        assert_eq!(status.state, ServiceState::Stopped);
+        assert_eq!(status.win32_exit_code, 1067);
+        assert_eq!(status.process_id, None);
+
+        let read_only = service_manager.open_service("test", ServiceAccess::QUERY_CONFIG)?;
+        assert!(matches!(
+            read_only.status(),
+            Err(QueryServiceError::AccessDenied(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_controls() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let service_handle = service_manager.create_service(ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            binary_path: r"C:\Program Files\Test\test.exe".to_string(),
+            ..driver_config("test")
+        })?;
+        scm.set_behavior(
+            "test",
+            FakeServiceBehavior {
+                controls_accepted: Some(
+                    (ServiceControls::STOP
+                        | ServiceControls::PAUSE_CONTINUE
+                        | ServiceControls::PARAMCHANGE)
+                        .bits(),
+                ),
+                ..Default::default()
+            },
+        );
+
+        service_handle.start()?;
+        match service_handle.pause() {
+            Err(ControlServiceError::CannotAcceptControl(_, message)) => {
+                assert!(message.contains("StartPending"), "{message}")
+            }
+            other => panic!("expected a pending service, got {other:?}"),
+        }
+        assert_eq!(service_handle.state()?, ServiceState::Running);
+
+        service_handle.pause_blocking()?;
+        assert_eq!(service_handle.state()?, ServiceState::Paused);
+        service_handle.resume_blocking()?;
+        assert_eq!(service_handle.state()?, ServiceState::Running);
+
+        service_handle.interrogate()?;
+        service_handle.param_change()?;
+        service_handle.user_control(200)?;
+        match service_handle.netbind_change(NetBindChange::Add) {
+            Err(ControlServiceError::InvalidServiceControl(_, message)) => {
+                assert!(
+                    message.contains("SERVICE_ACCEPT_NETBINDCHANGE"),
+                    "{message}"
+                )
+            }
+            other => panic!("expected an unaccepted control, got {other:?}"),
+        }
+        assert!(matches!(
+            service_handle.user_control(100),
+            Err(ControlServiceError::InvalidServiceControl(..))
+        ));
+
+        let driver = service_manager.create_service(driver_config("driver"))?;
+        assert!(matches!(
+            driver.resume(),
+            Err(ControlServiceError::ServiceNotActive(..))
+        ));
+        driver.start_blocking()?;
+        assert!(matches!(
+            driver.pause(),
+            Err(ControlServiceError::InvalidServiceControl(..))
+        ));
+
+        let stop_only = service_manager.open_service("test", ServiceAccess::STOP)?;
+        assert!(matches!(
+            stop_only.pause(),
+            Err(ControlServiceError::AccessDenied(..))
+        ));
+        stop_only.stop()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_stop_with_reason() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let service_handle = service_manager.create_service(driver_config("test"))?;
+        let reason = StopReason::new(
+            StopReasonKind::Planned,
+            StopReasonMajor::Software,
+            StopReasonMinor::SoftwareUpdate,
+        );
+        assert_eq!(reason.bits(), 0x4004000E);
+
+        service_handle.start_blocking()?;
+        match service_handle.stop_with_reason(reason, Some(&"x".repeat(128))) {
+            Err(ControlServiceError::InvalidParameter(_, message)) => {
+                assert!(message.contains("128 characters"), "{message}")
+            }
+            other => panic!("expected a rejected comment, got {other:?}"),
+        }
+        assert_eq!(service_handle.state()?, ServiceState::Running);
+
+        let comment = "x".repeat(127);
+        let status = service_handle.stop_with_reason(reason, Some(&comment))?;
+        assert_eq!(status.state, ServiceState::StopPending);
+        assert_eq!(
+            scm.stop_reason("test"),
+            Some((reason.bits(), Some(comment)))
+        );
+        service_handle.wait_for_state(ServiceState::Stopped, Duration::from_secs(5))?;
+
+        assert!(matches!(
+            service_handle.stop_with_reason(reason, None),
+            Err(ControlServiceError::ServiceNotActive(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_description() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let service_handle = service_manager.create_service(ServiceConfig {
+            description: Some("Collects test telemetry".into()),
+            ..driver_config("test")
+        })?;
+        assert_eq!(
+            service_handle.description()?,
+            Some(ServiceDescription::Text(
+                "Collects test telemetry".to_string()
+            ))
+        );
+
+        service_handle.set_description(r"@%SystemRoot%\system32\test.dll,-101")?;
+        assert_eq!(
+            service_handle.description()?,
+            Some(ServiceDescription::Resource {
+                module: r"%SystemRoot%\system32\test.dll".to_string(),
+                id: 101,
+            })
+        );
+        service_handle.clear_description()?;
+        assert_eq!(service_handle.description()?, None);
+
+        let reader = service_manager.open_service("test", ServiceAccess::QUERY_CONFIG)?;
+        match reader.set_description("read only") {
+            Err(UpdateServiceError::AccessDenied(_, message)) => {
+                assert!(message.contains("SERVICE_CHANGE_CONFIG"), "{message}")
+            }
+            other => panic!("expected a missing right, got {other:?}"),
+        }
+
+        assert!(matches!(
+            service_manager.create_service(ServiceConfig {
+                description: Some("bad\0description".into()),
+                ..driver_config("other")
+            }),
+            Err(CreateServiceError::InvalidParameter(..))
+        ));
+        assert!(!scm.contains("other"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_failure_actions() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let service_handle = service_manager.create_service(ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            binary_path: r"C:\Program Files\Test\test.exe".to_string(),
+            ..driver_config("test")
+        })?;
+        assert_eq!(service_handle.failure_actions()?, FailureActions::default());
+
+        let failure_actions = FailureActions {
+            reset_period: Some(Duration::from_secs(86400)),
+            command: Some(r"C:\Program Files\Test\report.exe --crash".to_string()),
+            actions: vec![
+                FailureAction::new(FailureActionType::Restart, Duration::from_secs(5)),
+                FailureAction::new(FailureActionType::Restart, Duration::from_secs(60)),
+                FailureAction::new(FailureActionType::RunCommand, Duration::ZERO),
+            ],
+            on_non_crash_failures: true,
+            ..Default::default()
+        };
+        service_handle.set_failure_actions(&failure_actions)?;
+        assert_eq!(service_handle.failure_actions()?, failure_actions);
+
+        match service_handle.set_failure_actions(&FailureActions {
+            command: None,
+            ..failure_actions.clone()
+        }) {
+            Err(UpdateServiceError::InvalidConfig(_, message)) => {
+                assert!(message.contains("requires a command"), "{message}")
+            }
+            other => panic!("expected a missing command, got {other:?}"),
+        }
+
+        let config_only = service_manager.open_service(
+            "test",
+            ServiceAccess::QUERY_CONFIG | ServiceAccess::CHANGE_CONFIG,
+        )?;
+        match config_only.set_failure_actions(&failure_actions) {
+            Err(UpdateServiceError::AccessDenied(_, message)) => {
+                assert!(message.contains("SERVICE_START"), "{message}")
+            }
+            other => panic!("expected a missing right, got {other:?}"),
+        }
+
+        let reboot = FailureActions {
+            reboot_message: Some("Rebooting after repeated test failures".to_string()),
+            actions: vec![FailureAction::new(
+                FailureActionType::Reboot,
+                Duration::from_secs(120),
+            )],
+            ..Default::default()
+        };
+        match config_only.set_failure_actions(&reboot) {
+            Err(UpdateServiceError::AccessDenied(_, message)) => {
+                assert!(message.contains("SeShutdownPrivilege"), "{message}")
+            }
+            other => panic!("expected a missing privilege, got {other:?}"),
+        }
+        scm.grant_privilege(Privilege::Shutdown);
+        config_only.set_failure_actions(&reboot)?;
+        assert!(!scm.privilege_enabled(Privilege::Shutdown));
+        assert_eq!(config_only.failure_actions()?, reboot);
+
+        config_only.set_failure_actions(&FailureActions::default())?;
+        assert_eq!(config_only.failure_actions()?, FailureActions::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_start_mode_and_preshutdown() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm)?;
+        let win32_config = |name: &str| ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            binary_path: r"C:\Program Files\Test\test.exe".to_string(),
+            ..driver_config(name)
+        };
+        let service_handle = service_manager.create_service(win32_config("test"))?;
+        assert_eq!(service_handle.start_mode()?, ServiceStartMode::Demand);
+        assert_eq!(
+            service_handle.preshutdown_timeout()?,
+            Some(Duration::from_secs(180))
+        );
+
+        service_handle.set_start_mode(ServiceStartMode::AutoDelayed)?;
+        service_handle.set_preshutdown_timeout(Duration::from_secs(300))?;
+        let snapshot = service_handle.query_config()?;
+        assert_eq!(snapshot.start_type, ServiceStartType::AutoStart);
+        assert_eq!(snapshot.start_mode, ServiceStartMode::AutoDelayed);
+        assert_eq!(snapshot.preshutdown_timeout, Some(Duration::from_secs(300)));
+
+        let copy = service_manager.create_service(win32_config("copy"))?;
+        copy.update_config(ServiceConfigChange {
+            start_mode: Some(snapshot.start_mode),
+            preshutdown_timeout: snapshot.preshutdown_timeout,
+            ..Default::default()
+        })?;
+        assert_eq!(
+            copy.query_config()?,
+            ServiceConfigSnapshot {
+                display_name: "copy".to_string(),
+                ..snapshot
+            }
+        );
+
+        service_handle.set_start_mode(ServiceStartMode::Auto)?;
+        assert_eq!(service_handle.start_mode()?, ServiceStartMode::Auto);
+        match service_handle.update_config(ServiceConfigChange {
+            start_type: Some(ServiceStartType::Disabled),
+            start_mode: Some(ServiceStartMode::AutoDelayed),
+            ..Default::default()
+        }) {
+            Err(UpdateServiceError::InvalidConfig(_, message)) => {
+                assert!(message.contains("contradicts"), "{message}")
+            }
+            other => panic!("expected contradicting start settings, got {other:?}"),
+        }
+
+        // Leaving a delayed auto start doesn't keep the flag for the next auto start.
+        service_handle.set_start_mode(ServiceStartMode::AutoDelayed)?;
+        service_handle.set_start_mode(ServiceStartMode::Demand)?;
+        service_handle.set_start_type(ServiceStartType::AutoStart)?;
+        assert_eq!(service_handle.start_mode()?, ServiceStartMode::Auto);
+        service_handle.set_start_mode(ServiceStartMode::AutoDelayed)?;
+        for start_type in [ServiceStartType::DemandStart, ServiceStartType::AutoStart] {
+            service_handle.update_config(ServiceConfigChange {
+                start_type: Some(start_type),
+                ..Default::default()
+            })?;
+        }
+        assert_eq!(service_handle.start_mode()?, ServiceStartMode::Auto);
+        service_handle.set_start_mode(ServiceStartMode::AutoDelayed)?;
+        service_handle.set_start_type(ServiceStartType::AutoStart)?;
+        assert_eq!(service_handle.start_mode()?, ServiceStartMode::Auto);
+
+        let driver = service_manager.create_service(driver_config("driver"))?;
+        driver.set_start_type(ServiceStartType::AutoStart)?;
+        assert_eq!(driver.start_mode()?, ServiceStartMode::Auto);
+        driver.set_start_type(ServiceStartType::DemandStart)?;
+        assert_eq!(driver.start_mode()?, ServiceStartMode::Demand);
+        assert_eq!(driver.preshutdown_timeout()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_sid_type_and_required_privileges() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm)?;
+        let shared_config = |name: &str| ServiceConfig {
+            service_type: ServiceType::Win32ShareProcess,
+            binary_path: r"C:\Windows\system32\svchost.exe -k TestGroup".to_string(),
+            ..driver_config(name)
+        };
+        let first = service_manager.create_service(shared_config("first"))?;
+        let second = service_manager.create_service(shared_config("second"))?;
+        let other_group = service_manager.create_service(ServiceConfig {
+            binary_path: r"C:\Windows\system32\svchost.exe -k OtherGroup".to_string(),
+            ..shared_config("other")
+        })?;
+        assert_eq!(first.sid_type()?, ServiceSidType::None);
+        assert!(first.required_privileges()?.is_empty());
+
+        first.set_sid_type(ServiceSidType::Unrestricted)?;
+        assert_eq!(first.sid_type()?, ServiceSidType::Unrestricted);
+        second.start_blocking()?;
+        match first.set_sid_type(ServiceSidType::Restricted) {
+            Err(UpdateServiceError::InvalidConfig(_, message)) => {
+                assert!(message.contains("second"), "{message}")
+            }
+            other => panic!("expected mixed SID types to be rejected, got {other:?}"),
+        }
+        other_group.start_blocking()?;
+        other_group.set_sid_type(ServiceSidType::Restricted)?;
+
+        second.stop_blocking()?;
+        first.set_sid_type(ServiceSidType::Restricted)?;
+        second.set_sid_type(ServiceSidType::Restricted)?;
+        assert_eq!(first.sid_type()?, ServiceSidType::Restricted);
+        first.start_blocking()?;
+        assert!(matches!(
+            second.set_sid_type(ServiceSidType::None),
+            Err(UpdateServiceError::InvalidConfig(..))
+        ));
+
+        first.set_required_privileges(&[
+            Privilege::ChangeNotify,
+            Privilege::Impersonate,
+            Privilege::ChangeNotify,
+        ])?;
+        assert_eq!(
+            first.required_privileges()?,
+            vec![Privilege::ChangeNotify, Privilege::Impersonate]
+        );
+        first.set_required_privileges(&[])?;
+        assert!(first.required_privileges()?.is_empty());
+
+        let driver = service_manager.create_service(driver_config("driver"))?;
+        assert!(matches!(
+            driver.set_sid_type(ServiceSidType::Unrestricted),
+            Err(UpdateServiceError::InvalidConfig(..))
+        ));
+        assert!(matches!(
+            driver.set_required_privileges(&[Privilege::LoadDriver]),
+            Err(UpdateServiceError::InvalidConfig(..))
+        ));
+
+        let change_only = service_manager.open_service("first", ServiceAccess::CHANGE_CONFIG)?;
+        assert!(matches!(
+            change_only.set_sid_type(ServiceSidType::Restricted),
+            Err(UpdateServiceError::AccessDenied(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_triggers() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm)?;
+        let service_handle = service_manager.create_service(driver_config("test"))?;
+        assert!(service_handle.triggers()?.is_empty());
+
+        let provider = Guid::from_u128(0x22fb2cd6_0e7b_422b_a0c7_2fad1fd0e716);
+        let triggers = vec![
+            ServiceTrigger::new(TriggerAction::Start, TriggerEvent::FirewallPortOpen)
+                .with_data_item(TriggerDataItem::String(vec![
+                    "80".to_string(),
+                    "TCP".to_string(),
+                ])),
+            ServiceTrigger::new(TriggerAction::Stop, TriggerEvent::LastIpAddressRemoval),
+            ServiceTrigger::new(
+                TriggerAction::Start,
+                TriggerEvent::DeviceInterfaceArrival(Guid::from_u128(
+                    0x53f56307_b6bf_11d0_94f2_00a0c91efb8b,
+                )),
+            )
+            .with_data_item(TriggerDataItem::String(vec![
+                r"USBSTOR\GenDisk".to_string()
+            ])),
+            ServiceTrigger::new(TriggerAction::Start, TriggerEvent::Custom(provider))
+                .with_data_item(TriggerDataItem::Level(4))
+                .with_data_item(TriggerDataItem::KeywordAny(0x8000_0000_0000_0010)),
+            ServiceTrigger::new(TriggerAction::Start, TriggerEvent::Custom(provider))
+                .with_data_item(TriggerDataItem::Binary(vec![0xab, 0xcd])),
+        ];
+        service_handle.set_triggers(&triggers)?;
+        assert_eq!(service_handle.triggers()?, triggers);
+
+        match service_handle.set_triggers(&[ServiceTrigger::new(
+            TriggerAction::Start,
+            TriggerEvent::DomainJoin,
+        )
+        .with_data_item(TriggerDataItem::Level(1))])
+        {
+            Err(UpdateServiceError::InvalidConfig(_, message)) => {
+                assert!(message.contains("custom ETW"), "{message}")
+            }
+            other => panic!("expected invalid trigger data, got {other:?}"),
+        }
+        match service_handle.set_triggers(&[ServiceTrigger::new(
+            TriggerAction::Start,
+            TriggerEvent::Other {
+                trigger_type: 99,
+                subtype: None,
+            },
+        )]) {
+            Err(UpdateServiceError::InvalidParameter(..)) => {}
+            other => panic!("expected an unknown trigger type to fail, got {other:?}"),
+        }
+        assert!(matches!(
+            service_handle.set_triggers(&[ServiceTrigger::new(
+                TriggerAction::Other(7),
+                TriggerEvent::DomainJoin,
+            )]),
+            Err(UpdateServiceError::InvalidParameter(..))
+        ));
+        assert_eq!(service_handle.triggers()?, triggers);
+
+        service_handle.set_triggers(&[])?;
+        assert!(service_handle.triggers()?.is_empty());
+
+        let read_only = service_manager.open_service("test", ServiceAccess::QUERY_STATUS)?;
+        assert!(matches!(
+            read_only.triggers(),
+            Err(QueryServiceError::AccessDenied(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_start_arguments() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let service_handle = service_manager.create_service(driver_config("test"))?;
+
+        match service_handle.start_with_args(["--mode", "bad\0argument"]) {
+            Err(ControlServiceError::InvalidParameter(_, message)) => {
+                assert!(message.contains("argument 1"), "{message}")
+            }
+            other => panic!("expected an invalid argument, got {other:?}"),
+        }
+        assert_eq!(service_handle.state()?, ServiceState::Stopped);
+
+        service_handle
+            .start_with_args_blocking(vec!["--mode".to_string(), "repair".to_string()])?;
+        assert_eq!(
+            scm.start_arguments("test"),
+            Some(vec!["--mode".to_string(), "repair".to_string()])
+        );
+        service_handle.stop_blocking()?;
+
+        service_handle.start_blocking()?;
+        assert_eq!(scm.start_arguments("test"), Some(Vec::new()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_blocking_timeouts() -> Result<()> {
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let service_handle = service_manager.create_service(driver_config("test"))?;
+
+        scm.set_behavior(
+            "test",
+            FakeServiceBehavior {
+                pending_steps: u32::MAX,
+                wait_hint: 1000,
+                ..Default::default()
+            },
+        );
+        match service_handle.start_blocking_timeout(Duration::from_millis(300)) {
+            Err(ControlServiceError::TimedOut(_, message)) => {
+                assert!(message.contains("still StartPending"), "{message}")
+            }
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+
+        let hung = service_manager.create_service(driver_config("hung"))?;
+        scm.set_behavior(
+            "hung",
+            FakeServiceBehavior {
+                wait_hint: 200,
+                hang_in: Some(ServiceState::StartPending),
+                ..Default::default()
+            },
+        );
+        match hung.start_blocking() {
+            Err(ControlServiceError::Stalled(_, message)) => {
+                assert!(message.contains("200 ms wait hint"), "{message}")
+            }
+            other => panic!("expected a stalled service, got {other:?}"),
+        }
+
+        // Without a wait hint the service gets at least a second to advance its checkpoint.
+        let quiet = service_manager.create_service(driver_config("quiet"))?;
+        scm.set_behavior(
+            "quiet",
+            FakeServiceBehavior {
+                wait_hint: 0,
+                hang_in: Some(ServiceState::StartPending),
+                ..Default::default()
+            },
+        );
+        let release = {
+            let scm = scm.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(400));
+                scm.set_behavior("quiet", FakeServiceBehavior::default());
+            })
+        };
+        quiet.start_blocking_timeout(Duration::from_secs(5))?;
+        release.join().unwrap();
+        assert_eq!(quiet.state()?, ServiceState::Running);
+
+        let failing = service_manager.create_service(driver_config("failing"))?;
+        scm.set_behavior(
+            "failing",
+            FakeServiceBehavior {
+                start_failure: Some(1066),
+                ..Default::default()
+            },
+        );
+        match failing.start_blocking_timeout(Duration::from_secs(5)) {
+            Err(ControlServiceError::UnexpectedState(1066, message)) => {
+                assert!(message.contains("Stopped instead of Running"), "{message}")
+            }
+            other => panic!("expected an unexpected state, got {other:?}"),
+        }
+
+        scm.set_behavior("failing", FakeServiceBehavior::default());
+        failing.start_blocking_timeout(Duration::from_secs(5))?;
+        assert_eq!(failing.state()?, ServiceState::Running);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_notifications() -> Result<()> {
+        let timeout = Duration::from_secs(5);
+        let scm = Arc::new(FakeScm::new());
+        let service_manager = ServiceManager::with_backend(scm.clone())?;
+        let service_handle = service_manager.create_service(driver_config("test"))?;
+
+        let mut events = service_handle.subscribe(
+            ServiceNotify::STATE_CHANGES.difference(ServiceNotify::PENDING)
+                | ServiceNotify::DELETE_PENDING,
+        )?;
+        assert_eq!(
+            events.recv_timeout(timeout).transpose()?,
+            Some(ServiceEvent::StateChanged(ServiceState::Stopped))
+        );
+        service_handle.start()?;
+        assert_eq!(
+            events.recv_timeout(timeout).transpose()?,
+            Some(ServiceEvent::StateChanged(ServiceState::Running))
+        );
+        service_handle.stop()?;
+        assert_eq!(
+            events.recv_timeout(timeout).transpose()?,
+            Some(ServiceEvent::StateChanged(ServiceState::Stopped))
+        );
+
+        let created = service_manager.subscribe(ServiceNotify::MANAGER_EVENTS)?;
+        service_handle.delete()?;
+        assert_eq!(
+            events.next().transpose()?,
+            Some(ServiceEvent::DeletePending)
+        );
+        assert!(events.next().is_none());
+        drop(service_handle);
+        assert_eq!(
+            created.recv_timeout(timeout).transpose()?,
+            Some(ServiceEvent::Deleted("test".to_string()))
+        );
+        drop(service_manager.create_service(driver_config("other"))?);
+        assert_eq!(
+            created.recv_timeout(timeout).transpose()?,
+            Some(ServiceEvent::Created("other".to_string()))
+        );
+        assert!(created.recv_timeout(Duration::from_millis(200)).is_none());
+
+        // A service already marked for deletion ends the subscription with an event or error.
+        let doomed = service_manager.create_service(driver_config("doomed"))?;
+        doomed.delete()?;
+        let mut events =
+            doomed.subscribe(ServiceNotify::RUNNING | ServiceNotify::DELETE_PENDING)?;
+        assert_eq!(
+            events.next().transpose()?,
+            Some(ServiceEvent::DeletePending)
+        );
+        assert!(events.next().is_none());
+        let mut events = doomed.subscribe(ServiceNotify::RUNNING)?;
+        match events.next() {
+            Some(Err(QueryServiceError::Unknown(_, message))) => {
+                assert!(message.contains("deleted service"), "{message}")
+            }
+            other => panic!("expected the subscription to fail, got {other:?}"),
+        }
+        assert!(events.next().is_none());
+        drop(doomed);
+
+        // So does one deleted while the subscription re-arms after an event.
+        let doomed = service_manager.create_service(driver_config("doomed"))?;
+        let mut events = doomed.subscribe(ServiceNotify::RUNNING)?;
+        doomed.start_blocking()?;
+        assert_eq!(
+            events.recv_timeout(timeout).transpose()?,
+            Some(ServiceEvent::StateChanged(ServiceState::Running))
+        );
+        doomed.delete()?;
+        assert!(matches!(
+            events.recv_timeout(timeout),
+            Some(Err(QueryServiceError::Unknown(..)))
+        ));
+        assert!(events.next().is_none());
+
+        assert!(matches!(
+            service_manager.subscribe(ServiceNotify::RUNNING),
+            Err(QueryServiceError::InvalidParameter(..))
+        ));
+        let read_only = service_manager.open_service("other", ServiceAccess::QUERY_CONFIG)?;
+        assert!(matches!(
+            read_only.subscribe(ServiceNotify::RUNNING),
+            Err(QueryServiceError::AccessDenied(..))
+        ));
+        let status_only = service_manager.open_service("other", ServiceAccess::QUERY_STATUS)?;
+        assert!(matches!(
+            status_only.subscribe(ServiceNotify::CREATED),
+            Err(QueryServiceError::InvalidParameter(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_validation() {
+        let invalid = [
+            ServiceConfig {
+                account: Some(ServiceAccount::LocalSystem),
+                ..driver_config("driver")
+            },
+            ServiceConfig {
+                request_tag: true,
+                load_order_group: Some("Base".to_string()),
+                ..driver_config("driver")
+            },
+            ServiceConfig {
+                start_type: ServiceStartType::SystemStart,
+                request_tag: true,
+                ..driver_config("driver")
+            },
+            ServiceConfig {
+                service_type: ServiceType::Win32OwnProcess,
+                start_type: ServiceStartType::BootStart,
+                ..driver_config("app")
+            },
+            ServiceConfig {
+                service_type: ServiceType::Win32OwnProcess,
+                password: Some("secret".to_string()),
+                ..driver_config("app")
+            },
+            ServiceConfig {
+                service_type: ServiceType::Win32OwnProcess,
+                account: Some(ServiceAccount::User(r"CORP\svc-app".to_string())),
+                ..driver_config("app")
+            },
+            ServiceConfig {
+                service_type: ServiceType::Win32OwnProcess,
+                account: Some(ServiceAccount::LocalService),
+                password: Some("secret".to_string()),
+                ..driver_config("app")
+            },
+            ServiceConfig {
+                service_type: ServiceType::Win32OwnProcess,
+                account: Some(ServiceAccount::Virtual("other".to_string())),
+                ..driver_config("app")
+            },
+        ];
+        for config in invalid {
+            assert!(
+                matches!(
+                    config.validate(),
+                    Err(CreateServiceError::InvalidConfig(..))
+                ),
+                "{config:?}"
+            );
+        }
+        match (ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            account: Some(ServiceAccount::Virtual("other".to_string())),
+            ..driver_config("app")
+        })
+        .validate()
+        {
+            Err(CreateServiceError::InvalidConfig(_, message)) => {
+                assert!(
+                    message.contains(r"NT SERVICE\other") && message.contains("app"),
+                    "{message}"
+                )
+            }
+            other => panic!("expected a mismatched virtual account, got {other:?}"),
+        }
+        assert!(ServiceConfig {
+            service_type: ServiceType::Win32OwnProcess,
+            account: Some(ServiceAccount::Virtual("APP".to_string())),
+            ..driver_config("app")
+        }
+        .validate()
+        .is_ok());
+        assert!(matches!(
+            ServiceConfig {
+                dependencies: vec![ServiceDependency::Service("self".to_string())],
+                ..driver_config("self")
+            }
+            .validate(),
+            Err(CreateServiceError::CircularDependency(..))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod conversion_tests {
+    use crate::{
+        account::ServiceAccount,
+        backend::RawServiceTrigger,
+        consts::{
+            SERVICE_AUTO_START, SERVICE_PAUSED, SERVICE_TRIGGER_DATA_TYPE_LEVEL,
+            SERVICE_TRIGGER_DATA_TYPE_STRING, SERVICE_TRIGGER_TYPE_CUSTOM,
+            SERVICE_WIN32_OWN_PROCESS,
+        },
+        error::{ControlServiceError, QueryServiceError},
+        service::{ServiceDescription, ServiceStartType, ServiceState, ServiceType},
+        trigger::{
+            Guid, ServiceTrigger, TriggerAction, TriggerDataItem, TriggerEvent, DOMAIN_JOIN,
+        },
+    };
+
+    #[test]
+    fn test_raw_conversions() {
+        assert_eq!(
+            ServiceType::try_from(SERVICE_WIN32_OWN_PROCESS).unwrap(),
+            ServiceType::Win32OwnProcess
+        );
+        assert_eq!(
+            ServiceStartType::try_from(SERVICE_AUTO_START).unwrap(),
+            ServiceStartType::AutoStart
+        );
+        assert_eq!(
+            ServiceState::try_from(SERVICE_PAUSED).unwrap(),
+            ServiceState::Paused
+        );
+        assert!(matches!(
+            ServiceState::try_from(0),
+            Err(QueryServiceError::Unknown(..))
+        ));
+        assert!(matches!(
+            ControlServiceError::from((1058, String::new())),
+            ControlServiceError::ServiceDisabled(1058, _)
+        ));
+    }
+
+    #[test]
+    fn test_service_description_parsing() {
+        for (text, description) in [
+            (
+                r"@%SystemRoot%\system32\wuaueng.dll,-106",
+                ServiceDescription::Resource {
+                    module: r"%SystemRoot%\system32\wuaueng.dll".to_string(),
+                    id: 106,
+                },
+            ),
+            (
+                "@a,b.dll,-7",
+                ServiceDescription::Resource {
+                    module: "a,b.dll".to_string(),
+                    id: 7,
+                },
+            ),
+            (
+                "Plain text",
+                ServiceDescription::Text("Plain text".to_string()),
+            ),
+            (
+                "@mention, -1",
+                ServiceDescription::Text("@mention, -1".to_string()),
+            ),
+            ("@x.dll,-", ServiceDescription::Text("@x.dll,-".to_string())),
+            ("@,-5", ServiceDescription::Text("@,-5".to_string())),
+        ] {
+            assert_eq!(ServiceDescription::from_raw(text), description, "{text}");
+            assert_eq!(description.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_service_account_parsing() {
+        for (text, account) in [
+            ("LocalSystem", ServiceAccount::LocalSystem),
+            (r"NT AUTHORITY\SYSTEM", ServiceAccount::LocalSystem),
+            (r"NT AUTHORITY\LocalService", ServiceAccount::LocalService),
+            (r"nt authority\LOCAL SERVICE", ServiceAccount::LocalService),
+            (
+                r"NT AUTHORITY\Network Service",
+                ServiceAccount::NetworkService,
+            ),
+            (
+                r"NT SERVICE\MyService",
+                ServiceAccount::virtual_account("MyService"),
+            ),
+            (
+                r"CORP\svc-web$",
+                ServiceAccount::GroupManaged {
+                    domain: "CORP".to_string(),
+                    name: "svc-web".to_string(),
+                },
+            ),
+            (
+                r"CORP\alice",
+                ServiceAccount::User(r"CORP\alice".to_string()),
+            ),
+            ("bob", ServiceAccount::User(r".\bob".to_string())),
+            (
+                "carol@corp.example",
+                ServiceAccount::User("carol@corp.example".to_string()),
+            ),
+        ] {
+            assert_eq!(text.parse::<ServiceAccount>().unwrap(), account, "{text}");
+        }
+
+        assert_eq!(
+            ServiceAccount::LocalService.to_string(),
+            r"NT AUTHORITY\LocalService"
+        );
+        assert_eq!(
+            r"corp\svc-web$"
+                .parse::<ServiceAccount>()
+                .unwrap()
+                .to_string(),
+            r"corp\svc-web$"
+        );
+        assert!(ServiceAccount::User(r"CORP\alice".to_string()).requires_password());
+        assert!(!ServiceAccount::virtual_account("MyService").requires_password());
+
+        for text in ["", r"NT AUTHORITY\LocalSvc", r"\alice", r"CORP\", "a:b"] {
+            assert!(text.parse::<ServiceAccount>().is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_service_trigger_parsing() {
+        let provider = Guid::from_u128(0x22fb2cd6_0e7b_422b_a0c7_2fad1fd0e716);
+        for (text, trigger) in [
+            (
+                "start/device/53f56307-b6bf-11d0-94f2-00a0c91efb8b/USBSTOR\\GenDisk/USBSTOR\\Disk",
+                ServiceTrigger::new(
+                    TriggerAction::Start,
+                    TriggerEvent::DeviceInterfaceArrival(Guid::from_u128(
+                        0x53f56307_b6bf_11d0_94f2_00a0c91efb8b,
+                    )),
+                )
+                .with_data_item(TriggerDataItem::String(
+                    vec![r"USBSTOR\GenDisk".to_string()],
+                ))
+                .with_data_item(TriggerDataItem::String(vec![r"USBSTOR\Disk".to_string()])),
+            ),
+            (
+                "start/networkon",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::FirstIpAddressArrival),
+            ),
+            (
+                "stop/networkoff",
+                ServiceTrigger::new(TriggerAction::Stop, TriggerEvent::LastIpAddressRemoval),
+            ),
+            (
+                "start/domainjoin",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::DomainJoin),
+            ),
+            (
+                "stop/domainleave",
+                ServiceTrigger::new(TriggerAction::Stop, TriggerEvent::DomainLeave),
+            ),
+            (
+                "start/portopen/5985;TCP;System;WinRM",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::FirewallPortOpen)
+                    .with_data_item(TriggerDataItem::String(vec![
+                        "5985".to_string(),
+                        "TCP".to_string(),
+                        "System".to_string(),
+                        "WinRM".to_string(),
+                    ])),
+            ),
+            (
+                "stop/portclose/5985;TCP",
+                ServiceTrigger::new(TriggerAction::Stop, TriggerEvent::FirewallPortClose)
+                    .with_data_item(TriggerDataItem::String(vec![
+                        "5985".to_string(),
+                        "TCP".to_string(),
+                    ])),
+            ),
+            (
+                "start/machinepolicy",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::MachinePolicyPresent),
+            ),
+            (
+                "start/userpolicy",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::UserPolicyPresent),
+            ),
+            (
+                "start/rpcinterface/367abb81-9844-35f1-ad32-98f038001003",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::RpcInterfaceEvent)
+                    .with_data_item(TriggerDataItem::String(vec![
+                        "367abb81-9844-35f1-ad32-98f038001003".to_string(),
+                    ])),
+            ),
+            (
+                "start/namedpipe/winreg",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::NamedPipeEvent)
+                    .with_data_item(TriggerDataItem::String(vec!["winreg".to_string()])),
+            ),
+            (
+                "start/custom/22fb2cd6-0e7b-422b-a0c7-2fad1fd0e716/ABCD/01",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::Custom(provider))
+                    .with_data_item(TriggerDataItem::Binary(vec![0xab, 0xcd]))
+                    .with_data_item(TriggerDataItem::Binary(vec![0x01])),
+            ),
+            (
+                "start/strcustom/22fb2cd6-0e7b-422b-a0c7-2fad1fd0e716/disk",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::Custom(provider))
+                    .with_data_item(TriggerDataItem::String(vec!["disk".to_string()])),
+            ),
+            (
+                "start/levelcustom/22fb2cd6-0e7b-422b-a0c7-2fad1fd0e716/4/0x10/0x8000000000000001",
+                ServiceTrigger::new(TriggerAction::Start, TriggerEvent::Custom(provider))
+                    .with_data_item(TriggerDataItem::Level(4))
+                    .with_data_item(TriggerDataItem::KeywordAny(0x10))
+                    .with_data_item(TriggerDataItem::KeywordAll(0x8000_0000_0000_0001)),
+            ),
+            (
+                "start/30/22fb2cd6-0e7b-422b-a0c7-2fad1fd0e716/FF",
+                ServiceTrigger::new(
+                    TriggerAction::Start,
+                    TriggerEvent::Other {
+                        trigger_type: 30,
+                        subtype: Some(provider),
+                    },
+                )
+                .with_data_item(TriggerDataItem::Binary(vec![0xff])),
+            ),
+        ] {
+            assert_eq!(text.parse::<ServiceTrigger>().unwrap(), trigger, "{text}");
+            assert_eq!(trigger.to_string(), text);
+        }
+
+        assert_eq!(
+            "START/DomainJoin".parse::<ServiceTrigger>().unwrap(),
+            ServiceTrigger::new(TriggerAction::Start, TriggerEvent::DomainJoin)
+        );
+        assert_eq!(
+            "start/3/{1CE20ABA-9851-4421-9430-1DDEB766E809}"
+                .parse::<ServiceTrigger>()
+                .unwrap()
+                .event,
+            TriggerEvent::DomainJoin
+        );
+        assert_eq!(TriggerEvent::DomainJoin.subtype(), Some(DOMAIN_JOIN));
+        assert_eq!(
+            "{1ce20aba-9851-4421-9430-1ddeb766e809}"
+                .parse::<Guid>()
+                .unwrap(),
+            DOMAIN_JOIN
+        );
+
+        let multi_sz = TriggerDataItem::String(vec!["80".to_string(), "TCP".to_string()]);
+        let (data_type, data) = multi_sz.to_raw();
+        assert_eq!(data, b"8\x000\x00\x00\x00T\x00C\x00P\x00\x00\x00\x00\x00");
+        assert_eq!(TriggerDataItem::from_raw(data_type, &data), multi_sz);
+
+        // Entries of a multi-entry string are written separated by `;` and split again.
+        for trigger in [
+            ServiceTrigger::new(
+                TriggerAction::Start,
+                TriggerEvent::DeviceInterfaceArrival(provider),
+            )
+            .with_data_item(TriggerDataItem::String(vec![
+                r"USBSTOR\GenDisk".to_string(),
+                r"USBSTOR\Disk".to_string(),
+            ]))
+            .with_data_item(TriggerDataItem::String(vec![r"USBSTOR\Other".to_string()])),
+            ServiceTrigger::new(TriggerAction::Start, TriggerEvent::Custom(provider))
+                .with_data_item(TriggerDataItem::String(vec![
+                    "disk".to_string(),
+                    "volume".to_string(),
+                ])),
+        ] {
+            assert_eq!(
+                trigger.to_string().parse::<ServiceTrigger>().unwrap(),
+                trigger,
+                "{trigger}"
+            );
+        }
+
+        // Whatever the model doesn't recognize is kept instead of failing the conversion.
+        let raw = RawServiceTrigger {
+            trigger_type: SERVICE_TRIGGER_TYPE_CUSTOM,
+            action: 7,
+            subtype: Some(provider),
+            data_items: vec![
+                (42, vec![1, 2, 3]),
+                (SERVICE_TRIGGER_DATA_TYPE_STRING, vec![b'a', 0, b'b']),
+                (SERVICE_TRIGGER_DATA_TYPE_LEVEL, vec![1, 2]),
+            ],
+        };
+        let trigger = ServiceTrigger::from(raw.clone());
+        assert_eq!(trigger.action, TriggerAction::Other(7));
+        assert_eq!(
+            trigger.data_items,
+            vec![
+                TriggerDataItem::Other {
+                    data_type: 42,
+                    data: vec![1, 2, 3],
+                },
+                TriggerDataItem::Other {
+                    data_type: SERVICE_TRIGGER_DATA_TYPE_STRING,
+                    data: vec![b'a', 0, b'b'],
+                },
+                TriggerDataItem::Other {
+                    data_type: SERVICE_TRIGGER_DATA_TYPE_LEVEL,
+                    data: vec![1, 2],
+                },
+            ]
+        );
+        assert_eq!(RawServiceTrigger::from(&trigger), raw);
+        assert_eq!(
+            trigger.to_string(),
+            "7/20/22fb2cd6-0e7b-422b-a0c7-2fad1fd0e716/42:010203/2:610062/3:0102"
+        );
+        assert_eq!(
+            trigger.to_string().parse::<ServiceTrigger>().unwrap(),
+            trigger
+        );
+
+        // Data items of mixed kinds fall back to the numeric form, which keeps their types.
+        for data_items in [
+            vec![
+                TriggerDataItem::String(vec!["disk".to_string()]),
+                TriggerDataItem::Level(4),
+            ],
+            vec![
+                TriggerDataItem::Level(4),
+                TriggerDataItem::String(vec!["disk".to_string(), "volume".to_string()]),
+            ],
+            vec![
+                TriggerDataItem::Binary(vec![0xab]),
+                TriggerDataItem::KeywordAll(0x10),
+            ],
+            vec![TriggerDataItem::KeywordAny(0x10), TriggerDataItem::Level(4)],
+            vec![
+                TriggerDataItem::String(vec!["a/b".to_string()]),
+                TriggerDataItem::Other {
+                    data_type: 42,
+                    data: vec![1],
+                },
+            ],
+        ] {
+            let trigger = ServiceTrigger {
+                data_items,
+                ..ServiceTrigger::new(TriggerAction::Start, TriggerEvent::Custom(provider))
+            };
+            let text = trigger.to_string();
+            assert!(text.starts_with("start/20/"), "{text}");
+            assert_eq!(text.parse::<ServiceTrigger>().unwrap(), trigger, "{text}");
+        }
+        let trigger = ServiceTrigger::new(TriggerAction::Start, TriggerEvent::DomainJoin)
+            .with_data_item(TriggerDataItem::Level(1));
+        assert_eq!(
+            trigger.to_string(),
+            "start/3/1ce20aba-9851-4421-9430-1ddeb766e809/3:01"
+        );
+        assert_eq!(
+            trigger.to_string().parse::<ServiceTrigger>().unwrap(),
+            trigger
+        );
+        assert_eq!(
+            "2/networkon".parse::<ServiceTrigger>().unwrap(),
+            ServiceTrigger::new(TriggerAction::Stop, TriggerEvent::FirstIpAddressArrival)
+        );
+
+        for text in [
+            "",
+            "run/networkon",
+            "start",
+            "start/networkon/extra",
+            "start/device",
+            "start/device/not-a-guid",
+            "start/portopen",
+            "start/custom/22fb2cd6-0e7b-422b-a0c7-2fad1fd0e716/ABC",
+            "start/levelcustom/22fb2cd6-0e7b-422b-a0c7-2fad1fd0e716/256",
+            "start/20/22fb2cd6-0e7b-422b-a0c7-2fad1fd0e716/x:01",
+            "start/unknown",
+        ] {
+            assert!(text.parse::<ServiceTrigger>().is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,678 @@
+//! This module provides a typed model of service triggers, the `SERVICE_CONFIG_TRIGGER_INFO`
+//! setting that starts or stops a service when a system event occurs. Every trigger also has
+//! the text form `sc triggerinfo` takes, such as `start/portopen/80;TCP;*;*`, so triggers can
+//! be read from and written to configuration files.
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    backend::RawServiceTrigger,
+    consts::{
+        ERROR_INVALID_PARAMETER, SERVICE_TRIGGER_ACTION_SERVICE_START,
+        SERVICE_TRIGGER_ACTION_SERVICE_STOP, SERVICE_TRIGGER_DATA_TYPE_BINARY,
+        SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ALL, SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ANY,
+        SERVICE_TRIGGER_DATA_TYPE_LEVEL, SERVICE_TRIGGER_DATA_TYPE_STRING,
+        SERVICE_TRIGGER_TYPE_CUSTOM, SERVICE_TRIGGER_TYPE_DEVICE_INTERFACE_ARRIVAL,
+        SERVICE_TRIGGER_TYPE_DOMAIN_JOIN, SERVICE_TRIGGER_TYPE_FIREWALL_PORT_EVENT,
+        SERVICE_TRIGGER_TYPE_GROUP_POLICY, SERVICE_TRIGGER_TYPE_IP_ADDRESS_AVAILABILITY,
+        SERVICE_TRIGGER_TYPE_NETWORK_ENDPOINT,
+    },
+    error::ServiceTriggerError,
+};
+
+/// A GUID, such as a device interface class or an ETW provider.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Guid {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+impl Guid {
+    /// Builds a GUID from its value written as a single number, such as
+    /// `0x4f27f2de_14e2_430b_a549_7cd48cbc8245`.
+    pub const fn from_u128(value: u128) -> Self {
+        Self {
+            data1: (value >> 96) as u32,
+            data2: (value >> 80) as u16,
+            data3: (value >> 64) as u16,
+            data4: (value as u64).to_be_bytes(),
+        }
+    }
+
+    /// Returns the GUID as a single number.
+    pub const fn to_u128(self) -> u128 {
+        (self.data1 as u128) << 96
+            | (self.data2 as u128) << 80
+            | (self.data3 as u128) << 64
+            | u64::from_be_bytes(self.data4) as u128
+    }
+}
+
+/// Formats the GUID without braces, as `sc` does.
+impl Display for Guid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.to_u128();
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            value >> 96,
+            (value >> 80) & 0xffff,
+            (value >> 64) & 0xffff,
+            (value >> 48) & 0xffff,
+            value & 0xffff_ffff_ffff
+        )
+    }
+}
+
+/// Parses a GUID with or without braces, ignoring case.
+impl FromStr for Guid {
+    type Err = ServiceTriggerError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let digits = value
+            .strip_prefix('{')
+            .and_then(|digits| digits.strip_suffix('}'))
+            .unwrap_or(value);
+        let groups: Vec<&str> = digits.split('-').collect();
+        let valid = groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12])
+            && groups
+                .iter()
+                .all(|group| group.bytes().all(|b| b.is_ascii_hexdigit()));
+        if !valid {
+            return Err(invalid(value, "invalid GUID"));
+        }
+        u128::from_str_radix(&groups.concat(), 16)
+            .map(Self::from_u128)
+            .map_err(|_| invalid(value, "invalid GUID"))
+    }
+}
+
+/// `NETWORK_MANAGER_FIRST_IP_ADDRESS_ARRIVAL_GUID`
+pub const FIRST_IP_ADDRESS_ARRIVAL: Guid = Guid::from_u128(0x4f27f2de_14e2_430b_a549_7cd48cbc8245);
+/// `NETWORK_MANAGER_LAST_IP_ADDRESS_REMOVAL_GUID`
+pub const LAST_IP_ADDRESS_REMOVAL: Guid = Guid::from_u128(0xcc4ba62a_162e_4648_847a_b6bdf993e335);
+/// `DOMAIN_JOIN_GUID`
+pub const DOMAIN_JOIN: Guid = Guid::from_u128(0x1ce20aba_9851_4421_9430_1ddeb766e809);
+/// `DOMAIN_LEAVE_GUID`
+pub const DOMAIN_LEAVE: Guid = Guid::from_u128(0xddaf516e_58c2_4866_9574_c3b615d42ea1);
+/// `FIREWALL_PORT_OPEN_GUID`
+pub const FIREWALL_PORT_OPEN: Guid = Guid::from_u128(0xb7569e07_8421_4ee0_ad10_86915afdad09);
+/// `FIREWALL_PORT_CLOSE_GUID`
+pub const FIREWALL_PORT_CLOSE: Guid = Guid::from_u128(0xa144ed38_8e12_4de4_9d96_e64740b1a524);
+/// `MACHINE_POLICY_PRESENT_GUID`
+pub const MACHINE_POLICY_PRESENT: Guid = Guid::from_u128(0x659fcae6_5bdb_4da9_b1ff_ca2a178d46e0);
+/// `USER_POLICY_PRESENT_GUID`
+pub const USER_POLICY_PRESENT: Guid = Guid::from_u128(0x54fb46c8_f089_464c_b1fd_59d1b62c3b50);
+/// `RPC_INTERFACE_EVENT_GUID`
+pub const RPC_INTERFACE_EVENT: Guid = Guid::from_u128(0xbc90d167_9470_4139_a9ba_be0bbbf5b74d);
+/// `NAMED_PIPE_EVENT_GUID`
+pub const NAMED_PIPE_EVENT: Guid = Guid::from_u128(0x1f81d131_3fac_4537_9e0c_7e7b0c2f4b55);
+
+/// What the service control manager does when a trigger fires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerAction {
+    Start,
+    Stop,
+    /// An action the model doesn't know, kept as it is.
+    Other(u32),
+}
+
+/// The system event a trigger fires on, a trigger type along with its subtype.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerEvent {
+    /// A device of this interface class arrives, or is present when the system starts.
+    /// The data items are hardware or compatible IDs the device must match.
+    DeviceInterfaceArrival(Guid),
+    /// The first IP address becomes available.
+    FirstIpAddressArrival,
+    /// The last IP address is removed.
+    LastIpAddressRemoval,
+    DomainJoin,
+    DomainLeave,
+    /// A firewall port opens. The data item lists the port, the protocol and optionally the
+    /// image path and service the port belongs to.
+    FirewallPortOpen,
+    /// A firewall port closes, with the same data item as [`FirewallPortOpen`](Self::FirewallPortOpen).
+    FirewallPortClose,
+    MachinePolicyPresent,
+    UserPolicyPresent,
+    /// A request arrives for the RPC interface whose UUID is the data item.
+    RpcInterfaceEvent,
+    /// A request arrives on the named pipe whose name is the data item.
+    NamedPipeEvent,
+    /// This ETW provider writes an event matching the data items.
+    Custom(Guid),
+    /// A trigger type or subtype the model doesn't know, such as aggregate triggers, kept
+    /// as it is.
+    Other {
+        trigger_type: u32,
+        subtype: Option<Guid>,
+    },
+}
+
+/// A value a trigger event must carry for the trigger to fire.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TriggerDataItem {
+    Binary(Vec<u8>),
+    /// A string, or the entries of a `REG_MULTI_SZ` list when there are several. The text form
+    /// separates the entries with `;`.
+    String(Vec<String>),
+    /// The least severe level of the events of a custom ETW provider.
+    Level(u8),
+    /// Keywords of which events of a custom ETW provider must have at least one.
+    KeywordAny(u64),
+    /// Keywords events of a custom ETW provider must all have.
+    KeywordAll(u64),
+    /// An item of a type the model doesn't know, or whose bytes don't fit its type, kept as
+    /// it is.
+    Other {
+        data_type: u32,
+        data: Vec<u8>,
+    },
+}
+
+/// A trigger of a service, read and written with
+/// [`ServiceHandle::triggers`](crate::service::ServiceHandle::triggers) and
+/// [`ServiceHandle::set_triggers`](crate::service::ServiceHandle::set_triggers).
+///
+/// The text form is an option of `sc triggerinfo`, `action/keyword[/guid][/data...]`:
+/// `start/device/<guid>/<hardware id>...`, `start/networkon`, `stop/networkoff`,
+/// `start/domainjoin`, `stop/domainleave`, `start/portopen/<port;protocol;image;service>`,
+/// `stop/portclose/...`, `start/machinepolicy`, `start/userpolicy`,
+/// `start/rpcinterface/<uuid>`, `start/namedpipe/<name>`, `start/custom/<guid>/<hex>...`,
+/// `start/strcustom/<guid>/<string>...` and
+/// `start/levelcustom/<guid>/<level>[/<keyword any>[/<keyword all>]]`. Triggers `sc` has
+/// no keyword for use the trigger type number instead, as in `start/30/<guid>/<hex>...`, where
+/// a data item other than binary data is written `<data type>:<hex>`, and actions other than
+/// start and stop are written as their number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServiceTrigger {
+    pub action: TriggerAction,
+    pub event: TriggerEvent,
+    pub data_items: Vec<TriggerDataItem>,
+}
+
+#[doc(hidden)]
+fn invalid(value: &str, reason: &str) -> ServiceTriggerError {
+    ServiceTriggerError::InvalidTrigger(
+        ERROR_INVALID_PARAMETER,
+        format!("[ServiceTrigger::from_str] {value:?}: {reason}"),
+    )
+}
+
+impl TriggerAction {
+    /// Returns the `SERVICE_TRIGGER_ACTION_*` value of the action.
+    pub fn to_raw(self) -> u32 {
+        match self {
+            Self::Start => SERVICE_TRIGGER_ACTION_SERVICE_START,
+            Self::Stop => SERVICE_TRIGGER_ACTION_SERVICE_STOP,
+            Self::Other(action) => action,
+        }
+    }
+
+    /// Recognizes an action, falling back to [`Other`](Self::Other).
+    pub fn from_raw(action: u32) -> Self {
+        match action {
+            SERVICE_TRIGGER_ACTION_SERVICE_START => Self::Start,
+            SERVICE_TRIGGER_ACTION_SERVICE_STOP => Self::Stop,
+            _ => Self::Other(action),
+        }
+    }
+}
+
+impl TriggerEvent {
+    /// Returns the `SERVICE_TRIGGER_TYPE_*` value of the event.
+    pub fn trigger_type(self) -> u32 {
+        match self {
+            Self::DeviceInterfaceArrival(_) => SERVICE_TRIGGER_TYPE_DEVICE_INTERFACE_ARRIVAL,
+            Self::FirstIpAddressArrival | Self::LastIpAddressRemoval => {
+                SERVICE_TRIGGER_TYPE_IP_ADDRESS_AVAILABILITY
+            }
+            Self::DomainJoin | Self::DomainLeave => SERVICE_TRIGGER_TYPE_DOMAIN_JOIN,
+            Self::FirewallPortOpen | Self::FirewallPortClose => {
+                SERVICE_TRIGGER_TYPE_FIREWALL_PORT_EVENT
+            }
+            Self::MachinePolicyPresent | Self::UserPolicyPresent => {
+                SERVICE_TRIGGER_TYPE_GROUP_POLICY
+            }
+            Self::RpcInterfaceEvent | Self::NamedPipeEvent => SERVICE_TRIGGER_TYPE_NETWORK_ENDPOINT,
+            Self::Custom(_) => SERVICE_TRIGGER_TYPE_CUSTOM,
+            Self::Other { trigger_type, .. } => trigger_type,
+        }
+    }
+
+    /// Returns the subtype GUID of the event.
+    pub fn subtype(self) -> Option<Guid> {
+        match self {
+            Self::DeviceInterfaceArrival(guid) | Self::Custom(guid) => Some(guid),
+            Self::FirstIpAddressArrival => Some(FIRST_IP_ADDRESS_ARRIVAL),
+            Self::LastIpAddressRemoval => Some(LAST_IP_ADDRESS_REMOVAL),
+            Self::DomainJoin => Some(DOMAIN_JOIN),
+            Self::DomainLeave => Some(DOMAIN_LEAVE),
+            Self::FirewallPortOpen => Some(FIREWALL_PORT_OPEN),
+            Self::FirewallPortClose => Some(FIREWALL_PORT_CLOSE),
+            Self::MachinePolicyPresent => Some(MACHINE_POLICY_PRESENT),
+            Self::UserPolicyPresent => Some(USER_POLICY_PRESENT),
+            Self::RpcInterfaceEvent => Some(RPC_INTERFACE_EVENT),
+            Self::NamedPipeEvent => Some(NAMED_PIPE_EVENT),
+            Self::Other { subtype, .. } => subtype,
+        }
+    }
+
+    /// Recognizes a trigger type and subtype, falling back to [`Other`](Self::Other).
+    pub fn from_raw(trigger_type: u32, subtype: Option<Guid>) -> Self {
+        let event = match (trigger_type, subtype) {
+            (SERVICE_TRIGGER_TYPE_DEVICE_INTERFACE_ARRIVAL, Some(guid)) => {
+                Some(Self::DeviceInterfaceArrival(guid))
+            }
+            (SERVICE_TRIGGER_TYPE_CUSTOM, Some(guid)) => Some(Self::Custom(guid)),
+            (_, Some(guid)) => [
+                Self::FirstIpAddressArrival,
+                Self::LastIpAddressRemoval,
+                Self::DomainJoin,
+                Self::DomainLeave,
+                Self::FirewallPortOpen,
+                Self::FirewallPortClose,
+                Self::MachinePolicyPresent,
+                Self::UserPolicyPresent,
+                Self::RpcInterfaceEvent,
+                Self::NamedPipeEvent,
+            ]
+            .into_iter()
+            .find(|event| event.trigger_type() == trigger_type && event.subtype() == Some(guid)),
+            (_, None) => None,
+        };
+        event.unwrap_or(Self::Other {
+            trigger_type,
+            subtype,
+        })
+    }
+}
+
+impl TriggerDataItem {
+    /// Returns the `SERVICE_TRIGGER_DATA_TYPE_*` value and the bytes of the item.
+    pub fn to_raw(&self) -> (u32, Vec<u8>) {
+        match self {
+            Self::Binary(data) => (SERVICE_TRIGGER_DATA_TYPE_BINARY, data.clone()),
+            Self::String(entries) => {
+                let mut units: Vec<u16> = Vec::new();
+                for entry in entries {
+                    units.extend(entry.encode_utf16());
+                    units.push(0);
+                }
+                if entries.len() != 1 {
+                    units.push(0);
+                }
+                let data = units.iter().flat_map(|unit| unit.to_le_bytes()).collect();
+                (SERVICE_TRIGGER_DATA_TYPE_STRING, data)
+            }
+            Self::Level(level) => (SERVICE_TRIGGER_DATA_TYPE_LEVEL, vec![*level]),
+            Self::KeywordAny(keyword) => (
+                SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ANY,
+                keyword.to_le_bytes().to_vec(),
+            ),
+            Self::KeywordAll(keyword) => (
+                SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ALL,
+                keyword.to_le_bytes().to_vec(),
+            ),
+            Self::Other { data_type, data } => (*data_type, data.clone()),
+        }
+    }
+
+    /// Decodes the bytes of an item of type `data_type`, falling back to [`Other`](Self::Other)
+    /// if the type is unknown or the bytes don't fit it.
+    pub fn from_raw(data_type: u32, data: &[u8]) -> Self {
+        Self::decode(data_type, data).unwrap_or_else(|| Self::Other {
+            data_type,
+            data: data.to_vec(),
+        })
+    }
+
+    #[doc(hidden)]
+    fn decode(data_type: u32, data: &[u8]) -> Option<Self> {
+        match data_type {
+            SERVICE_TRIGGER_DATA_TYPE_BINARY => Some(Self::Binary(data.to_vec())),
+            SERVICE_TRIGGER_DATA_TYPE_STRING if data.len() % 2 == 0 => {
+                let units: Vec<u16> = data
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect();
+                let mut entries: Vec<String> = units
+                    .split(|&unit| unit == 0)
+                    .map(String::from_utf16_lossy)
+                    .collect();
+                while entries.last().is_some_and(String::is_empty) {
+                    entries.pop();
+                }
+                Some(Self::String(entries))
+            }
+            SERVICE_TRIGGER_DATA_TYPE_LEVEL => match data {
+                [level] => Some(Self::Level(*level)),
+                _ => None,
+            },
+            SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ANY => {
+                Some(Self::KeywordAny(u64::from_le_bytes(data.try_into().ok()?)))
+            }
+            SERVICE_TRIGGER_DATA_TYPE_KEYWORD_ALL => {
+                Some(Self::KeywordAll(u64::from_le_bytes(data.try_into().ok()?)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Formats the item as a segment of the text form of a trigger.
+impl Display for TriggerDataItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Binary(data) | Self::Other { data, .. } => {
+                data.iter().try_for_each(|byte| write!(f, "{byte:02X}"))
+            }
+            Self::String(entries) => write!(f, "{}", entries.join(";")),
+            Self::Level(level) => write!(f, "{level}"),
+            Self::KeywordAny(keyword) | Self::KeywordAll(keyword) => write!(f, "{keyword:#x}"),
+        }
+    }
+}
+
+impl ServiceTrigger {
+    pub fn new(action: TriggerAction, event: TriggerEvent) -> Self {
+        Self {
+            action,
+            event,
+            data_items: Vec::new(),
+        }
+    }
+
+    /// Adds a data item the event must match.
+    pub fn with_data_item(mut self, data_item: TriggerDataItem) -> Self {
+        self.data_items.push(data_item);
+        self
+    }
+}
+
+/// Keeps whatever the model doesn't recognize in the `Other` variants, so that reading the
+/// triggers of a service never fails on them.
+impl From<RawServiceTrigger> for ServiceTrigger {
+    fn from(trigger: RawServiceTrigger) -> Self {
+        Self {
+            action: TriggerAction::from_raw(trigger.action),
+            event: TriggerEvent::from_raw(trigger.trigger_type, trigger.subtype),
+            data_items: trigger
+                .data_items
+                .iter()
+                .map(|(data_type, data)| TriggerDataItem::from_raw(*data_type, data))
+                .collect(),
+        }
+    }
+}
+
+impl From<&ServiceTrigger> for RawServiceTrigger {
+    fn from(trigger: &ServiceTrigger) -> Self {
+        Self {
+            trigger_type: trigger.event.trigger_type(),
+            action: trigger.action.to_raw(),
+            subtype: trigger.event.subtype(),
+            data_items: trigger
+                .data_items
+                .iter()
+                .map(TriggerDataItem::to_raw)
+                .collect(),
+        }
+    }
+}
+
+/// Formats the trigger as an option of `sc triggerinfo`. A trigger whose data items don't
+/// all parse back through its keyword, such as a custom trigger mixing strings and levels, is
+/// written with its trigger type number and typed data items instead.
+impl Display for ServiceTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.action {
+            TriggerAction::Start => write!(f, "start/")?,
+            TriggerAction::Stop => write!(f, "stop/")?,
+            TriggerAction::Other(action) => write!(f, "{action}/")?,
+        }
+        let Some(keyword) = self.keyword() else {
+            write!(f, "{}", self.event.trigger_type())?;
+            if let Some(guid) = self.event.subtype() {
+                write!(f, "/{guid}")?;
+            }
+            return self
+                .data_items
+                .iter()
+                .try_for_each(|data_item| match data_item {
+                    TriggerDataItem::Binary(_) => write!(f, "/{data_item}"),
+                    _ => {
+                        let (data_type, data) = data_item.to_raw();
+                        write!(f, "/{data_type}:")?;
+                        data.iter().try_for_each(|byte| write!(f, "{byte:02X}"))
+                    }
+                });
+        };
+        write!(f, "{keyword}")?;
+        if let TriggerEvent::DeviceInterfaceArrival(guid) | TriggerEvent::Custom(guid) = self.event
+        {
+            write!(f, "/{guid}")?;
+        }
+        self.data_items
+            .iter()
+            .try_for_each(|data_item| write!(f, "/{data_item}"))
+    }
+}
+
+impl ServiceTrigger {
+    /// Returns the `sc triggerinfo` keyword of the trigger, or `None` if the event has none or
+    /// the data items wouldn't parse back through it.
+    #[doc(hidden)]
+    fn keyword(&self) -> Option<&'static str> {
+        let strings = self.data_items.iter().all(|data_item| {
+            matches!(data_item, TriggerDataItem::String(entries)
+                if !entries.is_empty() && !entries.iter().any(|entry| entry.contains(['/', ';'])))
+        });
+        let none = self.data_items.is_empty();
+        let one_string = self.data_items.len() == 1 && strings;
+        let endpoint = matches!(self.data_items.as_slice(),
+            [TriggerDataItem::String(entries)]
+                if matches!(entries.as_slice(), [entry] if !entry.contains('/')));
+        let (keyword, fits) = match self.event {
+            TriggerEvent::DeviceInterfaceArrival(_) => ("device", strings),
+            TriggerEvent::FirstIpAddressArrival => ("networkon", none),
+            TriggerEvent::LastIpAddressRemoval => ("networkoff", none),
+            TriggerEvent::DomainJoin => ("domainjoin", none),
+            TriggerEvent::DomainLeave => ("domainleave", none),
+            TriggerEvent::FirewallPortOpen => ("portopen", one_string),
+            TriggerEvent::FirewallPortClose => ("portclose", one_string),
+            TriggerEvent::MachinePolicyPresent => ("machinepolicy", none),
+            TriggerEvent::UserPolicyPresent => ("userpolicy", none),
+            TriggerEvent::RpcInterfaceEvent => ("rpcinterface", endpoint),
+            TriggerEvent::NamedPipeEvent => ("namedpipe", endpoint),
+            TriggerEvent::Custom(_) => {
+                use TriggerDataItem::{Binary, KeywordAll, KeywordAny, Level};
+                match self.data_items.as_slice() {
+                    items if items.iter().all(|item| matches!(item, Binary(_))) => ("custom", true),
+                    [Level(_)]
+                    | [Level(_), KeywordAny(_)]
+                    | [Level(_), KeywordAny(_), KeywordAll(_)] => ("levelcustom", true),
+                    _ => ("strcustom", strings),
+                }
+            }
+            TriggerEvent::Other { .. } => return None,
+        };
+        fits.then_some(keyword)
+    }
+}
+
+/// Parses an option of `sc triggerinfo`, ignoring the case of the action and keyword.
+impl FromStr for ServiceTrigger {
+    type Err = ServiceTriggerError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut segments = value.trim().split('/');
+        let action = match segments.next().map(str::to_lowercase).as_deref() {
+            Some("start") => TriggerAction::Start,
+            Some("stop") => TriggerAction::Stop,
+            action => TriggerAction::from_raw(
+                action
+                    .and_then(|action| action.parse().ok())
+                    .ok_or_else(|| invalid(value, "the action must be start, stop or a number"))?,
+            ),
+        };
+        let keyword = segments
+            .next()
+            .filter(|keyword| !keyword.is_empty())
+            .ok_or_else(|| invalid(value, "missing trigger keyword"))?
+            .to_lowercase();
+        let mut guid = || -> Result<Guid, ServiceTriggerError> {
+            segments
+                .next()
+                .ok_or_else(|| invalid(value, "missing GUID"))?
+                .parse()
+        };
+
+        let strings = |segment: &str| {
+            TriggerDataItem::String(segment.split(';').map(str::to_string).collect())
+        };
+        let (event, data_items) = match keyword.as_str() {
+            "device" => (
+                TriggerEvent::DeviceInterfaceArrival(guid()?),
+                segments.by_ref().map(strings).collect(),
+            ),
+            "networkon" => (TriggerEvent::FirstIpAddressArrival, Vec::new()),
+            "networkoff" => (TriggerEvent::LastIpAddressRemoval, Vec::new()),
+            "domainjoin" => (TriggerEvent::DomainJoin, Vec::new()),
+            "domainleave" => (TriggerEvent::DomainLeave, Vec::new()),
+            "machinepolicy" => (TriggerEvent::MachinePolicyPresent, Vec::new()),
+            "userpolicy" => (TriggerEvent::UserPolicyPresent, Vec::new()),
+            "portopen" | "portclose" => {
+                let event = if keyword == "portopen" {
+                    TriggerEvent::FirewallPortOpen
+                } else {
+                    TriggerEvent::FirewallPortClose
+                };
+                let port = segments
+                    .next()
+                    .ok_or_else(|| invalid(value, "missing port parameter"))?;
+                (event, vec![strings(port)])
+            }
+            "rpcinterface" | "namedpipe" => {
+                let event = if keyword == "rpcinterface" {
+                    TriggerEvent::RpcInterfaceEvent
+                } else {
+                    TriggerEvent::NamedPipeEvent
+                };
+                let endpoint = segments
+                    .next()
+                    .ok_or_else(|| invalid(value, "missing endpoint"))?;
+                (
+                    event,
+                    vec![TriggerDataItem::String(vec![endpoint.to_string()])],
+                )
+            }
+            "custom" => (
+                TriggerEvent::Custom(guid()?),
+                segments
+                    .by_ref()
+                    .map(|segment| parse_hex(value, segment))
+                    .collect::<Result<_, _>>()?,
+            ),
+            "strcustom" => (
+                TriggerEvent::Custom(guid()?),
+                segments.by_ref().map(strings).collect(),
+            ),
+            "levelcustom" => {
+                let event = TriggerEvent::Custom(guid()?);
+                let mut data_items = Vec::new();
+                if let Some(level) = segments.next() {
+                    let level = level
+                        .parse()
+                        .map_err(|_| invalid(value, "the level must be 0 to 255"))?;
+                    data_items.push(TriggerDataItem::Level(level));
+                }
+                if let Some(keyword) = segments.next() {
+                    data_items.push(TriggerDataItem::KeywordAny(parse_keyword(value, keyword)?));
+                }
+                if let Some(keyword) = segments.next() {
+                    data_items.push(TriggerDataItem::KeywordAll(parse_keyword(value, keyword)?));
+                }
+                (event, data_items)
+            }
+            other => {
+                let trigger_type = other
+                    .parse()
+                    .map_err(|_| invalid(value, "unknown trigger keyword"))?;
+                let mut segments = segments.by_ref().peekable();
+                let subtype = match segments.peek() {
+                    Some(segment) if segment.contains('-') => {
+                        let guid = segment.parse()?;
+                        segments.next();
+                        Some(guid)
+                    }
+                    _ => None,
+                };
+                (
+                    TriggerEvent::from_raw(trigger_type, subtype),
+                    segments
+                        .map(|segment| parse_typed(value, segment))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+        };
+        if segments.next().is_some() {
+            return Err(invalid(value, "unexpected data after the trigger"));
+        }
+
+        Ok(Self {
+            action,
+            event,
+            data_items,
+        })
+    }
+}
+
+/// Parses a binary data item written as hexadecimal digits, such as `ABCDABCD` for 4 bytes.
+#[doc(hidden)]
+fn parse_hex(value: &str, segment: &str) -> Result<TriggerDataItem, ServiceTriggerError> {
+    parse_bytes(value, segment).map(TriggerDataItem::Binary)
+}
+
+/// Parses a data item of the numeric form, either binary data or `<data type>:<hex>` for an
+/// item of another type.
+#[doc(hidden)]
+fn parse_typed(value: &str, segment: &str) -> Result<TriggerDataItem, ServiceTriggerError> {
+    let Some((data_type, data)) = segment.split_once(':') else {
+        return parse_hex(value, segment);
+    };
+    let data_type = data_type
+        .parse()
+        .map_err(|_| invalid(value, "the data type must be a number"))?;
+    Ok(TriggerDataItem::from_raw(
+        data_type,
+        &parse_bytes(value, data)?,
+    ))
+}
+
+#[doc(hidden)]
+fn parse_bytes(value: &str, segment: &str) -> Result<Vec<u8>, ServiceTriggerError> {
+    if segment.len() % 2 != 0 || !segment.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid(
+            value,
+            "binary data must be pairs of hexadecimal digits",
+        ));
+    }
+    Ok((0..segment.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&segment[index..index + 2], 16).unwrap_or_default())
+        .collect())
+}
+
+#[doc(hidden)]
+fn parse_keyword(value: &str, segment: &str) -> Result<u64, ServiceTriggerError> {
+    let digits = segment
+        .strip_prefix("0x")
+        .or_else(|| segment.strip_prefix("0X"))
+        .unwrap_or(segment);
+    u64::from_str_radix(digits, 16)
+        .map_err(|_| invalid(value, "keywords must be 64-bit hexadecimal numbers"))
+}
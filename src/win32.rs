@@ -0,0 +1,1040 @@
+//! This module provides the [`ScmBackend`] implementation that talks to the real Windows service control manager.
+
+use std::{
+    ffi::c_void,
+    time::{Duration, Instant},
+};
+
+use defer_lite::defer;
+use widestring::{U16CStr, U16CString};
+use windows_sys::core::GUID;
+use windows_sys::Win32::{
+    Foundation::{
+        CloseHandle, LocalFree, BOOL, ERROR_INSUFFICIENT_BUFFER, ERROR_INVALID_NAME,
+        ERROR_INVALID_PARAMETER, ERROR_MORE_DATA, ERROR_NOT_ALL_ASSIGNED, ERROR_SUCCESS, FALSE,
+        LUID, TRUE,
+    },
+    Security::{
+        AdjustTokenPrivileges, LookupPrivilegeValueW, LUID_AND_ATTRIBUTES, SE_PRIVILEGE_ENABLED,
+        TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_QUERY,
+    },
+    System::Services::{
+        ChangeServiceConfig2W, ChangeServiceConfigW, CloseServiceHandle, ControlService,
+        ControlServiceExW, CreateServiceW, DeleteService, EnumDependentServicesW,
+        EnumServicesStatusExW, GetServiceDisplayNameW, GetServiceKeyNameW,
+        NotifyServiceStatusChangeW, OpenSCManagerW, OpenServiceW, QueryServiceConfig2W,
+        QueryServiceConfigW, QueryServiceStatusEx, StartServiceW, ENUM_SERVICE_STATUSW,
+        ENUM_SERVICE_STATUS_PROCESSW, QUERY_SERVICE_CONFIGW, SC_ACTION, SC_ACTION_TYPE,
+        SC_ENUM_PROCESS_INFO, SC_STATUS_PROCESS_INFO, SERVICE_CONFIG,
+        SERVICE_CONFIG_DELAYED_AUTO_START_INFO, SERVICE_CONFIG_DESCRIPTION,
+        SERVICE_CONFIG_FAILURE_ACTIONS, SERVICE_CONFIG_FAILURE_ACTIONS_FLAG,
+        SERVICE_CONFIG_PRESHUTDOWN_INFO, SERVICE_CONFIG_REQUIRED_PRIVILEGES_INFO,
+        SERVICE_CONFIG_SERVICE_SID_INFO, SERVICE_CONFIG_TRIGGER_INFO,
+        SERVICE_CONTROL_STATUS_REASON_PARAMSW, SERVICE_DELAYED_AUTO_START_INFO,
+        SERVICE_DESCRIPTIONW, SERVICE_FAILURE_ACTIONSW, SERVICE_FAILURE_ACTIONS_FLAG,
+        SERVICE_NOTIFY_2W, SERVICE_NOTIFY_STATUS_CHANGE, SERVICE_PRESHUTDOWN_INFO,
+        SERVICE_REQUIRED_PRIVILEGES_INFOW, SERVICE_SID_INFO, SERVICE_STATUS,
+        SERVICE_STATUS_PROCESS, SERVICE_TRIGGER, SERVICE_TRIGGER_INFO,
+        SERVICE_TRIGGER_SPECIFIC_DATA_ITEM,
+    },
+    System::Threading::{
+        GetCurrentProcess, OpenProcess, OpenProcessToken, SleepEx, TerminateProcess,
+        PROCESS_TERMINATE,
+    },
+};
+
+use crate::{
+    backend::{
+        NotifyRegistration, RawConfigChange, RawFailureActions, RawServiceConfig, RawServiceEntry,
+        RawServiceNotify, RawServiceStatus, RawServiceTrigger, ScHandle, ScmBackend,
+    },
+    common::get_last_error,
+    consts::SERVICE_CONTROL_STATUS_REASON_INFO,
+    service::ServiceDependency,
+    service_manager::ServiceConfig,
+    trigger::Guid,
+};
+
+/// Calls `QueryServiceConfig2W` for `info_level` and returns the filled buffer, which starts with
+/// the structure of that level.
+#[doc(hidden)]
+fn query_service_config2(service: ScHandle, info_level: SERVICE_CONFIG) -> Result<Vec<u64>, u32> {
+    unsafe {
+        let mut bytes_needed: u32 = 0;
+        if QueryServiceConfig2W(
+            service,
+            info_level,
+            std::ptr::null_mut(),
+            0,
+            &mut bytes_needed,
+        ) == FALSE
+            && get_last_error() != ERROR_INSUFFICIENT_BUFFER
+        {
+            return Err(get_last_error());
+        }
+
+        // u64 storage keeps the buffer aligned for the SERVICE_* structures.
+        let mut buffer = vec![0u64; (bytes_needed as usize).div_ceil(8).max(1)];
+        if QueryServiceConfig2W(
+            service,
+            info_level,
+            buffer.as_mut_ptr() as *mut u8,
+            (buffer.len() * 8) as u32,
+            &mut bytes_needed,
+        ) == FALSE
+        {
+            return Err(get_last_error());
+        }
+        Ok(buffer)
+    }
+}
+
+/// Calls `ChangeServiceConfig2W` for `info_level` with `info`, the structure of that level.
+#[doc(hidden)]
+fn change_service_config2<T>(
+    service: ScHandle,
+    info_level: SERVICE_CONFIG,
+    info: &T,
+) -> Result<(), u32> {
+    unsafe {
+        if ChangeServiceConfig2W(service, info_level, info as *const T as *const c_void) == FALSE {
+            return Err(get_last_error());
+        }
+    }
+    Ok(())
+}
+
+/// Backend that forwards every call to `advapi32`.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Win32Backend;
+
+#[doc(hidden)]
+fn to_wide(value: &str, error: u32) -> Result<U16CString, u32> {
+    U16CString::from_str(value).map_err(|_| error)
+}
+
+#[doc(hidden)]
+fn to_wide_opt(value: Option<&str>, error: u32) -> Result<Option<U16CString>, u32> {
+    value.map(|value| to_wide(value, error)).transpose()
+}
+
+#[doc(hidden)]
+fn opt_ptr(value: &Option<U16CString>) -> *const u16 {
+    value
+        .as_ref()
+        .map_or(std::ptr::null(), |value| value.as_ptr())
+}
+
+/// Encodes a list of names as the double-null-terminated string Win32 expects.
+#[doc(hidden)]
+fn to_multi_sz(values: &[String]) -> Result<Vec<u16>, u32> {
+    let mut buffer = Vec::new();
+    for value in values {
+        buffer.extend_from_slice(to_wide(value, ERROR_INVALID_PARAMETER)?.as_slice_with_nul());
+    }
+    buffer.push(0);
+    Ok(buffer)
+}
+
+/// Decodes a double-null-terminated string list.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a valid double-null-terminated string list.
+#[doc(hidden)]
+unsafe fn from_multi_sz(mut ptr: *const u16) -> Vec<String> {
+    let mut values = Vec::new();
+    if ptr.is_null() {
+        return values;
+    }
+    loop {
+        let value = U16CStr::from_ptr_str(ptr);
+        if value.is_empty() {
+            break;
+        }
+        values.push(value.to_string_lossy());
+        ptr = ptr.add(value.len() + 1);
+    }
+    values
+}
+
+/// Copies a possibly null wide string.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a valid null-terminated wide string.
+#[doc(hidden)]
+unsafe fn from_wide_ptr(ptr: *const u16) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    U16CStr::from_ptr_str(ptr).to_string_lossy()
+}
+
+impl From<SERVICE_STATUS> for RawServiceStatus {
+    fn from(status: SERVICE_STATUS) -> Self {
+        Self {
+            service_type: status.dwServiceType,
+            current_state: status.dwCurrentState,
+            controls_accepted: status.dwControlsAccepted,
+            win32_exit_code: status.dwWin32ExitCode,
+            service_specific_exit_code: status.dwServiceSpecificExitCode,
+            check_point: status.dwCheckPoint,
+            wait_hint: status.dwWaitHint,
+            process_id: 0,
+            service_flags: 0,
+        }
+    }
+}
+
+impl From<SERVICE_STATUS_PROCESS> for RawServiceStatus {
+    fn from(status: SERVICE_STATUS_PROCESS) -> Self {
+        Self {
+            service_type: status.dwServiceType,
+            current_state: status.dwCurrentState,
+            controls_accepted: status.dwControlsAccepted,
+            win32_exit_code: status.dwWin32ExitCode,
+            service_specific_exit_code: status.dwServiceSpecificExitCode,
+            check_point: status.dwCheckPoint,
+            wait_hint: status.dwWaitHint,
+            process_id: status.dwProcessId,
+            service_flags: status.dwServiceFlags,
+        }
+    }
+}
+
+/// A `NotifyServiceStatusChangeW` registration, kept in a [`NotifyRegistration`]. It is boxed
+/// so that the buffer Windows writes the notification to stays in place until the callback
+/// runs.
+#[doc(hidden)]
+struct PendingNotify {
+    notify: SERVICE_NOTIFY_2W,
+    fired: bool,
+}
+
+/// Runs as an APC on the registering thread while it waits in [`SleepEx`].
+#[doc(hidden)]
+unsafe extern "system" fn notify_callback(parameter: *const c_void) {
+    let notify = &*(parameter as *const SERVICE_NOTIFY_2W);
+    (*(notify.pContext as *mut PendingNotify)).fired = true;
+}
+
+/// Signature shared by `GetServiceKeyNameW` and `GetServiceDisplayNameW`.
+#[doc(hidden)]
+type NameLookup = unsafe extern "system" fn(ScHandle, *const u16, *mut u16, *mut u32) -> BOOL;
+
+/// Runs a name lookup, growing the buffer once if the first attempt is too small.
+#[doc(hidden)]
+fn lookup_name(scm: ScHandle, name: &str, lookup: NameLookup) -> Result<String, u32> {
+    let name = to_wide(name, ERROR_INVALID_NAME)?;
+    let mut buffer = vec![0u16; 257];
+
+    loop {
+        let mut length = buffer.len() as u32;
+        if unsafe { lookup(scm, name.as_ptr(), buffer.as_mut_ptr(), &mut length) } != FALSE {
+            return Ok(String::from_utf16_lossy(&buffer[..length as usize]));
+        }
+        if get_last_error() != ERROR_INSUFFICIENT_BUFFER || (length as usize) < buffer.len() {
+            return Err(get_last_error());
+        }
+        // The required length excludes the terminating null.
+        buffer.resize(length as usize + 1, 0);
+    }
+}
+
+impl ScmBackend for Win32Backend {
+    fn open_sc_manager(
+        &self,
+        machine_name: Option<&str>,
+        database_name: Option<&str>,
+        desired_access: u32,
+    ) -> Result<ScHandle, u32> {
+        let machine_name = to_wide_opt(machine_name, ERROR_INVALID_NAME)?;
+        let database_name = to_wide_opt(database_name, ERROR_INVALID_NAME)?;
+        let handle = unsafe {
+            OpenSCManagerW(
+                opt_ptr(&machine_name),
+                opt_ptr(&database_name),
+                desired_access,
+            )
+        };
+
+        if handle == 0 {
+            return Err(get_last_error());
+        }
+        Ok(handle)
+    }
+
+    fn open_service(
+        &self,
+        scm: ScHandle,
+        service_name: &str,
+        desired_access: u32,
+    ) -> Result<ScHandle, u32> {
+        let service_name = to_wide(service_name, ERROR_INVALID_NAME)?;
+        let handle = unsafe { OpenServiceW(scm, service_name.as_ptr(), desired_access) };
+
+        if handle == 0 {
+            return Err(get_last_error());
+        }
+        Ok(handle)
+    }
+
+    fn get_service_key_name(&self, scm: ScHandle, display_name: &str) -> Result<String, u32> {
+        lookup_name(scm, display_name, GetServiceKeyNameW)
+    }
+
+    fn get_service_display_name(&self, scm: ScHandle, service_name: &str) -> Result<String, u32> {
+        lookup_name(scm, service_name, GetServiceDisplayNameW)
+    }
+
+    fn create_service(
+        &self,
+        scm: ScHandle,
+        config: &ServiceConfig,
+        desired_access: u32,
+    ) -> Result<(ScHandle, u32), u32> {
+        let service_name = to_wide(&config.service_name, ERROR_INVALID_NAME)?;
+        let display_name = to_wide(&config.display_name, ERROR_INVALID_NAME)?;
+        let binary_path = to_wide(&config.binary_path, ERROR_INVALID_PARAMETER)?;
+        let load_order_group =
+            to_wide_opt(config.load_order_group.as_deref(), ERROR_INVALID_PARAMETER)?;
+        let dependencies = to_multi_sz(
+            &config
+                .dependencies
+                .iter()
+                .map(ServiceDependency::to_raw)
+                .collect::<Vec<_>>(),
+        )?;
+        let account = to_wide_opt(
+            config.account.as_ref().map(ToString::to_string).as_deref(),
+            ERROR_INVALID_PARAMETER,
+        )?;
+        let password = to_wide_opt(config.password.as_deref(), ERROR_INVALID_PARAMETER)?;
+        let mut tag_id: u32 = 0;
+
+        let handle = unsafe {
+            CreateServiceW(
+                scm,
+                service_name.as_ptr(),
+                display_name.as_ptr(),
+                desired_access,
+                config.service_type as u32,
+                config.start_type as u32,
+                config.error_control as u32,
+                binary_path.as_ptr(),
+                opt_ptr(&load_order_group),
+                if config.request_tag {
+                    &mut tag_id
+                } else {
+                    std::ptr::null_mut()
+                },
+                dependencies.as_ptr(),
+                opt_ptr(&account),
+                opt_ptr(&password),
+            )
+        };
+
+        if handle == 0 {
+            return Err(get_last_error());
+        }
+        Ok((handle, tag_id))
+    }
+
+    fn enum_services_status_ex(
+        &self,
+        scm: ScHandle,
+        service_type: u32,
+        service_state: u32,
+        group_name: Option<&str>,
+    ) -> Result<Vec<RawServiceEntry>, u32> {
+        let group_name = to_wide_opt(group_name, ERROR_INVALID_PARAMETER)?;
+        let mut services = Vec::new();
+        let mut resume_handle: u32 = 0;
+        // u64 storage keeps the buffer aligned for ENUM_SERVICE_STATUS_PROCESSW.
+        let mut buffer = vec![0u64; 64 * 1024 / 8];
+
+        loop {
+            let mut bytes_needed: u32 = 0;
+            let mut services_returned: u32 = 0;
+
+            let done = unsafe {
+                EnumServicesStatusExW(
+                    scm,
+                    SC_ENUM_PROCESS_INFO,
+                    service_type,
+                    service_state,
+                    buffer.as_mut_ptr() as *mut u8,
+                    (buffer.len() * 8) as u32,
+                    &mut bytes_needed,
+                    &mut services_returned,
+                    &mut resume_handle,
+                    opt_ptr(&group_name),
+                )
+            } != FALSE;
+
+            if !done && get_last_error() != ERROR_MORE_DATA {
+                return Err(get_last_error());
+            }
+
+            let entries = unsafe {
+                std::slice::from_raw_parts(
+                    buffer.as_ptr() as *const ENUM_SERVICE_STATUS_PROCESSW,
+                    services_returned as usize,
+                )
+            };
+            for entry in entries {
+                services.push(unsafe {
+                    RawServiceEntry {
+                        service_name: from_wide_ptr(entry.lpServiceName),
+                        display_name: from_wide_ptr(entry.lpDisplayName),
+                        status: entry.ServiceStatusProcess.into(),
+                    }
+                });
+            }
+
+            if done {
+                return Ok(services);
+            }
+            if services_returned == 0 {
+                buffer.resize((bytes_needed as usize).div_ceil(8), 0);
+            }
+        }
+    }
+
+    fn enum_dependent_services(
+        &self,
+        service: ScHandle,
+        service_state: u32,
+    ) -> Result<Vec<RawServiceEntry>, u32> {
+        unsafe {
+            let mut bytes_needed: u32 = 0;
+            let mut services_returned: u32 = 0;
+
+            if EnumDependentServicesW(
+                service,
+                service_state,
+                std::ptr::null_mut(),
+                0,
+                &mut bytes_needed,
+                &mut services_returned,
+            ) != FALSE
+            {
+                return Ok(Vec::new());
+            }
+            if get_last_error() != ERROR_MORE_DATA {
+                return Err(get_last_error());
+            }
+
+            // u64 storage keeps the buffer aligned for ENUM_SERVICE_STATUSW.
+            let mut buffer = vec![0u64; (bytes_needed as usize).div_ceil(8)];
+            if EnumDependentServicesW(
+                service,
+                service_state,
+                buffer.as_mut_ptr() as *mut ENUM_SERVICE_STATUSW,
+                (buffer.len() * 8) as u32,
+                &mut bytes_needed,
+                &mut services_returned,
+            ) == FALSE
+            {
+                return Err(get_last_error());
+            }
+
+            Ok(std::slice::from_raw_parts(
+                buffer.as_ptr() as *const ENUM_SERVICE_STATUSW,
+                services_returned as usize,
+            )
+            .iter()
+            .map(|entry| RawServiceEntry {
+                service_name: from_wide_ptr(entry.lpServiceName),
+                display_name: from_wide_ptr(entry.lpDisplayName),
+                status: entry.ServiceStatus.into(),
+            })
+            .collect())
+        }
+    }
+
+    fn adjust_privilege(&self, privilege: &str, enable: bool) -> Result<bool, u32> {
+        let privilege = to_wide(privilege, ERROR_INVALID_PARAMETER)?;
+
+        unsafe {
+            let mut token_handle = 0;
+            if OpenProcessToken(
+                GetCurrentProcess(),
+                TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
+                &mut token_handle,
+            ) == FALSE
+            {
+                return Err(get_last_error());
+            }
+            defer! { CloseHandle(token_handle); }
+
+            let mut luid = std::mem::zeroed::<LUID>();
+            if LookupPrivilegeValueW(std::ptr::null(), privilege.as_ptr(), &mut luid) == FALSE {
+                return Err(get_last_error());
+            }
+
+            let new_state = TOKEN_PRIVILEGES {
+                PrivilegeCount: 1,
+                Privileges: [LUID_AND_ATTRIBUTES {
+                    Luid: luid,
+                    Attributes: if enable { SE_PRIVILEGE_ENABLED } else { 0 },
+                }],
+            };
+            let mut previous_state = std::mem::zeroed::<TOKEN_PRIVILEGES>();
+            let mut length: u32 = 0;
+            if AdjustTokenPrivileges(
+                token_handle,
+                FALSE,
+                &new_state,
+                std::mem::size_of::<TOKEN_PRIVILEGES>() as u32,
+                &mut previous_state,
+                &mut length,
+            ) == FALSE
+            {
+                return Err(get_last_error());
+            }
+            // AdjustTokenPrivileges reports success even when the token lacks the privilege.
+            if get_last_error() == ERROR_NOT_ALL_ASSIGNED {
+                return Err(ERROR_NOT_ALL_ASSIGNED);
+            }
+
+            // The previous state only lists the privileges whose state changed.
+            Ok(if previous_state.PrivilegeCount == 0 {
+                enable
+            } else {
+                previous_state.Privileges[0].Attributes & SE_PRIVILEGE_ENABLED != 0
+            })
+        }
+    }
+
+    fn close_service_handle(&self, handle: ScHandle) {
+        unsafe {
+            CloseServiceHandle(handle);
+        }
+    }
+
+    fn close_notify_handle(&self, handle: ScHandle, registration: NotifyRegistration) {
+        unsafe {
+            CloseServiceHandle(handle);
+            // Closing the handle cancels the registration, but a callback that was already
+            // queued to this thread still writes to the buffer when it runs.
+            if registration.0.is_some() {
+                SleepEx(0, TRUE);
+            }
+        }
+        drop(registration);
+    }
+
+    fn delete_service(&self, service: ScHandle) -> Result<(), u32> {
+        if unsafe { DeleteService(service) } == FALSE {
+            return Err(get_last_error());
+        }
+        Ok(())
+    }
+
+    fn start_service(&self, service: ScHandle, arguments: &[String]) -> Result<(), u32> {
+        let arguments = arguments
+            .iter()
+            .map(|argument| to_wide(argument, ERROR_INVALID_PARAMETER))
+            .collect::<Result<Vec<_>, _>>()?;
+        let pointers: Vec<*const u16> =
+            arguments.iter().map(|argument| argument.as_ptr()).collect();
+        let pointers_ptr = if pointers.is_empty() {
+            std::ptr::null()
+        } else {
+            pointers.as_ptr()
+        };
+
+        if unsafe { StartServiceW(service, pointers.len() as u32, pointers_ptr) } == FALSE {
+            return Err(get_last_error());
+        }
+        Ok(())
+    }
+
+    fn terminate_process(&self, process_id: u32, exit_code: u32) -> Result<(), u32> {
+        unsafe {
+            let process = OpenProcess(PROCESS_TERMINATE, FALSE, process_id);
+            if process == 0 {
+                return Err(get_last_error());
+            }
+            defer! { CloseHandle(process); }
+
+            if TerminateProcess(process, exit_code) == FALSE {
+                return Err(get_last_error());
+            }
+        }
+        Ok(())
+    }
+
+    fn control_service(&self, service: ScHandle, control: u32) -> Result<RawServiceStatus, u32> {
+        unsafe {
+            let mut status = std::mem::zeroed::<SERVICE_STATUS>();
+            if ControlService(service, control, &mut status) == FALSE {
+                return Err(get_last_error());
+            }
+            Ok(status.into())
+        }
+    }
+
+    fn control_service_with_reason(
+        &self,
+        service: ScHandle,
+        control: u32,
+        reason: u32,
+        comment: Option<&str>,
+    ) -> Result<RawServiceStatus, u32> {
+        let comment = to_wide_opt(comment, ERROR_INVALID_PARAMETER)?;
+        unsafe {
+            let mut params = std::mem::zeroed::<SERVICE_CONTROL_STATUS_REASON_PARAMSW>();
+            params.dwReason = reason;
+            params.pszComment = opt_ptr(&comment) as *mut u16;
+            if ControlServiceExW(
+                service,
+                control,
+                SERVICE_CONTROL_STATUS_REASON_INFO,
+                &mut params as *mut SERVICE_CONTROL_STATUS_REASON_PARAMSW as *mut c_void,
+            ) == FALSE
+            {
+                return Err(get_last_error());
+            }
+            Ok(params.ServiceStatus.into())
+        }
+    }
+
+    fn query_service_status(&self, service: ScHandle) -> Result<RawServiceStatus, u32> {
+        unsafe {
+            let mut status = std::mem::zeroed::<SERVICE_STATUS_PROCESS>();
+            let mut bytes_needed = 0;
+            if QueryServiceStatusEx(
+                service,
+                SC_STATUS_PROCESS_INFO,
+                &mut status as *mut SERVICE_STATUS_PROCESS as *mut u8,
+                std::mem::size_of::<SERVICE_STATUS_PROCESS>() as u32,
+                &mut bytes_needed,
+            ) == FALSE
+            {
+                return Err(get_last_error());
+            }
+            Ok(status.into())
+        }
+    }
+
+    fn query_service_config(&self, service: ScHandle) -> Result<RawServiceConfig, u32> {
+        unsafe {
+            let mut bytes_needed: u32 = 0;
+
+            if QueryServiceConfigW(service, std::ptr::null_mut(), 0, &mut bytes_needed) == FALSE
+                && get_last_error() != ERROR_INSUFFICIENT_BUFFER
+            {
+                return Err(get_last_error());
+            }
+
+            // u64 storage keeps the buffer aligned for QUERY_SERVICE_CONFIGW.
+            let mut config_buffer = vec![0u64; (bytes_needed as usize).div_ceil(8)];
+            let config = config_buffer.as_mut_ptr() as *mut QUERY_SERVICE_CONFIGW;
+
+            if QueryServiceConfigW(
+                service,
+                config,
+                (config_buffer.len() * 8) as u32,
+                &mut bytes_needed,
+            ) == FALSE
+            {
+                return Err(get_last_error());
+            }
+
+            let config = &*config;
+            Ok(RawServiceConfig {
+                service_type: config.dwServiceType,
+                start_type: config.dwStartType,
+                error_control: config.dwErrorControl,
+                binary_path_name: from_wide_ptr(config.lpBinaryPathName),
+                load_order_group: from_wide_ptr(config.lpLoadOrderGroup),
+                tag_id: config.dwTagId,
+                dependencies: from_multi_sz(config.lpDependencies),
+                service_start_name: from_wide_ptr(config.lpServiceStartName),
+                display_name: from_wide_ptr(config.lpDisplayName),
+            })
+        }
+    }
+
+    fn change_service_config(
+        &self,
+        service: ScHandle,
+        change: &RawConfigChange,
+    ) -> Result<(), u32> {
+        let binary_path = to_wide_opt(change.binary_path_name.as_deref(), ERROR_INVALID_PARAMETER)?;
+        let load_order_group =
+            to_wide_opt(change.load_order_group.as_deref(), ERROR_INVALID_PARAMETER)?;
+        let dependencies = change
+            .dependencies
+            .as_deref()
+            .map(to_multi_sz)
+            .transpose()?;
+        let service_start_name = to_wide_opt(
+            change.service_start_name.as_deref(),
+            ERROR_INVALID_PARAMETER,
+        )?;
+        let password = to_wide_opt(change.password.as_deref(), ERROR_INVALID_PARAMETER)?;
+        let display_name = to_wide_opt(change.display_name.as_deref(), ERROR_INVALID_NAME)?;
+
+        unsafe {
+            if ChangeServiceConfigW(
+                service,
+                change.service_type,
+                change.start_type,
+                change.error_control,
+                opt_ptr(&binary_path),
+                opt_ptr(&load_order_group),
+                std::ptr::null_mut(),
+                dependencies
+                    .as_ref()
+                    .map_or(std::ptr::null(), |dependencies| dependencies.as_ptr()),
+                opt_ptr(&service_start_name),
+                opt_ptr(&password),
+                opt_ptr(&display_name),
+            ) == FALSE
+            {
+                return Err(get_last_error());
+            }
+        }
+        Ok(())
+    }
+
+    fn query_service_description(&self, service: ScHandle) -> Result<String, u32> {
+        let buffer = query_service_config2(service, SERVICE_CONFIG_DESCRIPTION)?;
+        unsafe {
+            let description = &*(buffer.as_ptr() as *const SERVICE_DESCRIPTIONW);
+            Ok(from_wide_ptr(description.lpDescription))
+        }
+    }
+
+    fn change_service_description(&self, service: ScHandle, description: &str) -> Result<(), u32> {
+        let description = to_wide(description, ERROR_INVALID_PARAMETER)?;
+        let info = SERVICE_DESCRIPTIONW {
+            lpDescription: description.as_ptr() as *mut u16,
+        };
+        change_service_config2(service, SERVICE_CONFIG_DESCRIPTION, &info)
+    }
+
+    fn query_service_failure_actions(&self, service: ScHandle) -> Result<RawFailureActions, u32> {
+        let buffer = query_service_config2(service, SERVICE_CONFIG_FAILURE_ACTIONS)?;
+        unsafe {
+            let failure_actions = &*(buffer.as_ptr() as *const SERVICE_FAILURE_ACTIONSW);
+            let actions = if failure_actions.lpsaActions.is_null() {
+                &[]
+            } else {
+                std::slice::from_raw_parts(
+                    failure_actions.lpsaActions,
+                    failure_actions.cActions as usize,
+                )
+            };
+            Ok(RawFailureActions {
+                reset_period: failure_actions.dwResetPeriod,
+                reboot_message: from_wide_ptr(failure_actions.lpRebootMsg),
+                command: from_wide_ptr(failure_actions.lpCommand),
+                actions: actions
+                    .iter()
+                    .map(|action| (action.Type as u32, action.Delay))
+                    .collect(),
+            })
+        }
+    }
+
+    fn change_service_failure_actions(
+        &self,
+        service: ScHandle,
+        failure_actions: &RawFailureActions,
+    ) -> Result<(), u32> {
+        let reboot_message = to_wide(&failure_actions.reboot_message, ERROR_INVALID_PARAMETER)?;
+        let command = to_wide(&failure_actions.command, ERROR_INVALID_PARAMETER)?;
+        // A non-null, possibly dangling, action array is what makes an empty list delete them.
+        let mut actions: Vec<SC_ACTION> = failure_actions
+            .actions
+            .iter()
+            .map(|&(action_type, delay)| SC_ACTION {
+                Type: action_type as SC_ACTION_TYPE,
+                Delay: delay,
+            })
+            .collect();
+        let info = SERVICE_FAILURE_ACTIONSW {
+            dwResetPeriod: failure_actions.reset_period,
+            lpRebootMsg: reboot_message.as_ptr() as *mut u16,
+            lpCommand: command.as_ptr() as *mut u16,
+            cActions: actions.len() as u32,
+            lpsaActions: actions.as_mut_ptr(),
+        };
+        change_service_config2(service, SERVICE_CONFIG_FAILURE_ACTIONS, &info)
+    }
+
+    fn query_service_failure_actions_flag(&self, service: ScHandle) -> Result<bool, u32> {
+        let buffer = query_service_config2(service, SERVICE_CONFIG_FAILURE_ACTIONS_FLAG)?;
+        unsafe {
+            let flag = &*(buffer.as_ptr() as *const SERVICE_FAILURE_ACTIONS_FLAG);
+            Ok(flag.fFailureActionsOnNonCrashFailures != FALSE)
+        }
+    }
+
+    fn change_service_failure_actions_flag(
+        &self,
+        service: ScHandle,
+        enabled: bool,
+    ) -> Result<(), u32> {
+        let info = SERVICE_FAILURE_ACTIONS_FLAG {
+            fFailureActionsOnNonCrashFailures: enabled as BOOL,
+        };
+        change_service_config2(service, SERVICE_CONFIG_FAILURE_ACTIONS_FLAG, &info)
+    }
+
+    fn query_service_delayed_auto_start(&self, service: ScHandle) -> Result<bool, u32> {
+        let buffer = query_service_config2(service, SERVICE_CONFIG_DELAYED_AUTO_START_INFO)?;
+        unsafe {
+            let info = &*(buffer.as_ptr() as *const SERVICE_DELAYED_AUTO_START_INFO);
+            Ok(info.fDelayedAutostart != FALSE)
+        }
+    }
+
+    fn change_service_delayed_auto_start(
+        &self,
+        service: ScHandle,
+        delayed: bool,
+    ) -> Result<(), u32> {
+        let info = SERVICE_DELAYED_AUTO_START_INFO {
+            fDelayedAutostart: delayed as BOOL,
+        };
+        change_service_config2(service, SERVICE_CONFIG_DELAYED_AUTO_START_INFO, &info)
+    }
+
+    fn query_service_preshutdown_timeout(&self, service: ScHandle) -> Result<u32, u32> {
+        let buffer = query_service_config2(service, SERVICE_CONFIG_PRESHUTDOWN_INFO)?;
+        unsafe {
+            let info = &*(buffer.as_ptr() as *const SERVICE_PRESHUTDOWN_INFO);
+            Ok(info.dwPreshutdownTimeout)
+        }
+    }
+
+    fn change_service_preshutdown_timeout(
+        &self,
+        service: ScHandle,
+        timeout: u32,
+    ) -> Result<(), u32> {
+        let info = SERVICE_PRESHUTDOWN_INFO {
+            dwPreshutdownTimeout: timeout,
+        };
+        change_service_config2(service, SERVICE_CONFIG_PRESHUTDOWN_INFO, &info)
+    }
+
+    fn query_service_triggers(&self, service: ScHandle) -> Result<Vec<RawServiceTrigger>, u32> {
+        let buffer = query_service_config2(service, SERVICE_CONFIG_TRIGGER_INFO)?;
+        unsafe {
+            let info = &*(buffer.as_ptr() as *const SERVICE_TRIGGER_INFO);
+            if info.pTriggers.is_null() {
+                return Ok(Vec::new());
+            }
+            let triggers = std::slice::from_raw_parts(info.pTriggers, info.cTriggers as usize);
+            Ok(triggers
+                .iter()
+                .map(|trigger| {
+                    let data_items = if trigger.pDataItems.is_null() {
+                        &[]
+                    } else {
+                        std::slice::from_raw_parts(trigger.pDataItems, trigger.cDataItems as usize)
+                    };
+                    RawServiceTrigger {
+                        trigger_type: trigger.dwTriggerType,
+                        action: trigger.dwAction,
+                        subtype: trigger.pTriggerSubtype.as_ref().map(|guid| Guid {
+                            data1: guid.data1,
+                            data2: guid.data2,
+                            data3: guid.data3,
+                            data4: guid.data4,
+                        }),
+                        data_items: data_items
+                            .iter()
+                            .map(|item| {
+                                let data = if item.pData.is_null() {
+                                    Vec::new()
+                                } else {
+                                    std::slice::from_raw_parts(item.pData, item.cbData as usize)
+                                        .to_vec()
+                                };
+                                (item.dwDataType, data)
+                            })
+                            .collect(),
+                    }
+                })
+                .collect())
+        }
+    }
+
+    fn change_service_triggers(
+        &self,
+        service: ScHandle,
+        triggers: &[RawServiceTrigger],
+    ) -> Result<(), u32> {
+        // The structures point into these, so they have to outlive the call.
+        let mut subtypes: Vec<GUID> = triggers
+            .iter()
+            .map(|trigger| {
+                let guid = trigger.subtype.unwrap_or_default();
+                GUID {
+                    data1: guid.data1,
+                    data2: guid.data2,
+                    data3: guid.data3,
+                    data4: guid.data4,
+                }
+            })
+            .collect();
+        let mut data: Vec<Vec<u8>> = triggers
+            .iter()
+            .flat_map(|trigger| trigger.data_items.iter().map(|(_, data)| data.clone()))
+            .collect();
+        let mut data_items: Vec<Vec<SERVICE_TRIGGER_SPECIFIC_DATA_ITEM>> = Vec::new();
+        let mut data = data.iter_mut();
+        for trigger in triggers {
+            data_items.push(
+                trigger
+                    .data_items
+                    .iter()
+                    .zip(data.by_ref())
+                    .map(
+                        |((data_type, _), data)| SERVICE_TRIGGER_SPECIFIC_DATA_ITEM {
+                            dwDataType: *data_type,
+                            cbData: data.len() as u32,
+                            pData: data.as_mut_ptr(),
+                        },
+                    )
+                    .collect(),
+            );
+        }
+        let mut raw_triggers: Vec<SERVICE_TRIGGER> = triggers
+            .iter()
+            .zip(subtypes.iter_mut())
+            .zip(data_items.iter_mut())
+            .map(|((trigger, subtype), data_items)| SERVICE_TRIGGER {
+                dwTriggerType: trigger.trigger_type,
+                dwAction: trigger.action,
+                pTriggerSubtype: if trigger.subtype.is_some() {
+                    subtype
+                } else {
+                    std::ptr::null_mut()
+                },
+                cDataItems: data_items.len() as u32,
+                pDataItems: if data_items.is_empty() {
+                    std::ptr::null_mut()
+                } else {
+                    data_items.as_mut_ptr()
+                },
+            })
+            .collect();
+        // A null trigger array with a zero count deletes all triggers.
+        let info = SERVICE_TRIGGER_INFO {
+            cTriggers: raw_triggers.len() as u32,
+            pTriggers: if raw_triggers.is_empty() {
+                std::ptr::null_mut()
+            } else {
+                raw_triggers.as_mut_ptr()
+            },
+            pReserved: std::ptr::null_mut(),
+        };
+        change_service_config2(service, SERVICE_CONFIG_TRIGGER_INFO, &info)
+    }
+
+    fn query_service_sid_type(&self, service: ScHandle) -> Result<u32, u32> {
+        let buffer = query_service_config2(service, SERVICE_CONFIG_SERVICE_SID_INFO)?;
+        unsafe {
+            let info = &*(buffer.as_ptr() as *const SERVICE_SID_INFO);
+            Ok(info.dwServiceSidType)
+        }
+    }
+
+    fn change_service_sid_type(&self, service: ScHandle, sid_type: u32) -> Result<(), u32> {
+        let info = SERVICE_SID_INFO {
+            dwServiceSidType: sid_type,
+        };
+        change_service_config2(service, SERVICE_CONFIG_SERVICE_SID_INFO, &info)
+    }
+
+    fn query_service_required_privileges(&self, service: ScHandle) -> Result<Vec<String>, u32> {
+        let buffer = query_service_config2(service, SERVICE_CONFIG_REQUIRED_PRIVILEGES_INFO)?;
+        unsafe {
+            let info = &*(buffer.as_ptr() as *const SERVICE_REQUIRED_PRIVILEGES_INFOW);
+            Ok(from_multi_sz(info.pmszRequiredPrivileges))
+        }
+    }
+
+    fn change_service_required_privileges(
+        &self,
+        service: ScHandle,
+        privileges: &[String],
+    ) -> Result<(), u32> {
+        let mut privileges = to_multi_sz(privileges)?;
+        // An empty list still has to be double-null-terminated to delete the setting.
+        if privileges.len() == 1 {
+            privileges.push(0);
+        }
+        let info = SERVICE_REQUIRED_PRIVILEGES_INFOW {
+            pmszRequiredPrivileges: privileges.as_mut_ptr(),
+        };
+        change_service_config2(service, SERVICE_CONFIG_REQUIRED_PRIVILEGES_INFO, &info)
+    }
+
+    fn notify_service_status_change(
+        &self,
+        handle: ScHandle,
+        registration: &mut NotifyRegistration,
+        notify_mask: u32,
+        timeout: Duration,
+    ) -> Result<Option<RawServiceNotify>, u32> {
+        if registration.0.is_none() {
+            let mut entry = Box::new(PendingNotify {
+                notify: unsafe { std::mem::zeroed() },
+                fired: false,
+            });
+            entry.notify.dwVersion = SERVICE_NOTIFY_STATUS_CHANGE;
+            entry.notify.pfnNotifyCallback = Some(notify_callback);
+            entry.notify.pContext = &mut *entry as *mut PendingNotify as *mut c_void;
+            let result = unsafe { NotifyServiceStatusChangeW(handle, notify_mask, &entry.notify) };
+            if result != ERROR_SUCCESS {
+                return Err(result);
+            }
+            registration.0 = Some(entry);
+        }
+        let pending = registration
+            .0
+            .as_mut()
+            .and_then(|entry| entry.downcast_mut::<PendingNotify>())
+            .ok_or(ERROR_INVALID_PARAMETER)? as *mut PendingNotify;
+
+        // The callback only runs while this thread is in an alertable wait.
+        let deadline = Instant::now() + timeout;
+        while !unsafe { (*pending).fired } {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            let millis = remaining.as_millis().clamp(1, u32::MAX as u128 - 1) as u32;
+            unsafe {
+                SleepEx(millis, TRUE);
+            }
+        }
+
+        let notify = unsafe { (*pending).notify };
+        registration.0 = None;
+        if notify.dwNotificationStatus != ERROR_SUCCESS {
+            return Err(notify.dwNotificationStatus);
+        }
+        let service_names = unsafe { from_multi_sz(notify.pszServiceNames) };
+        if !notify.pszServiceNames.is_null() {
+            unsafe {
+                LocalFree(notify.pszServiceNames as _);
+            }
+        }
+        Ok(Some(RawServiceNotify {
+            notification_triggered: notify.dwNotificationTriggered,
+            status: notify.ServiceStatus.into(),
+            service_names,
+        }))
+    }
+}